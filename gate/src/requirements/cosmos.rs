@@ -0,0 +1,253 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        AmountLimits, Chain, ChainAddress, NumberId, ReqUserAccess, Requirement, Snapshot, User,
+    },
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+/// Balance of a Cosmos SDK chain: of a CW20 contract when the requirement
+/// has an address, of the bank denom in its data otherwise, falling back to
+/// the native denom of the chain.
+pub struct CosmosRequirement {
+    id: NumberId,
+    chain_id: String,
+    token: Option<String>,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for CosmosRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, |u| u.cosmos_addresses().cloned()) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.cosmos(&self.chain_id) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(self.chain_id.clone()),
+            );
+        };
+
+        let addresses: Vec<String> = user_addresses.iter().map(|a| a.to_string()).collect();
+        let balances = match &self.token {
+            Some(token) => {
+                provider
+                    .get_fungible_balance(token.clone(), &addresses, self.snapshot)
+                    .await
+            }
+            None => provider.get_native_balance(&addresses, self.snapshot).await,
+        };
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for CosmosRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let Some(chain_id) = req.chain.as_ref().and_then(Chain::id) else {
+            return Err(CheckableError::MissingField("chain".into()));
+        };
+
+        let contract = req
+            .address
+            .as_ref()
+            .map(|address| match address {
+                ChainAddress::Cosmos(contract) => Ok(contract.to_string()),
+                _ => Err(CheckableError::MissingTokenAddress(req.id.to_string())),
+            })
+            .transpose()?;
+        let denom = req.data.as_ref().and_then(|data| data.denom.clone());
+
+        Ok(CosmosRequirement {
+            id: req.id,
+            chain_id: chain_id.to_string(),
+            token: contract.or(denom),
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CosmosRequirement;
+    use crate::{
+        address,
+        requirements::Checkable,
+        types::{Amount, Requirement, RequirementType, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        cosmos::CosmosError, cosmos_address, BalanceQuerier, ProviderContext, Snapshot,
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    const NATIVE: &str = "uosmo";
+
+    // Querier answering from a map of (token, address) pairs
+    struct StaticQuerier(HashMap<(&'static str, &'static str), &'static str>);
+
+    impl StaticQuerier {
+        fn balances(
+            &self,
+            token: &str,
+            user_addresses: &[String],
+        ) -> Vec<Result<Amount, CosmosError>> {
+            user_addresses
+                .iter()
+                .map(|ua| {
+                    Ok(self
+                        .0
+                        .iter()
+                        .find(|((t, a), _)| *t == token && *a == ua)
+                        .map(|(_, amount)| amount.parse().unwrap())
+                        .unwrap_or_default())
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl BalanceQuerier for StaticQuerier {
+        type Address = String;
+        type Id = String;
+        type Balance = Amount;
+        type Chain = String;
+        type Error = CosmosError;
+
+        async fn get_native_balance(
+            &self,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(NATIVE, user_addresses)
+        }
+
+        async fn get_fungible_balance(
+            &self,
+            token_address: Self::Address,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(&token_address, user_addresses)
+        }
+
+        async fn get_non_fungible_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances("", user_addresses)
+        }
+
+        async fn get_special_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances("", user_addresses)
+        }
+    }
+
+    #[tokio::test]
+    async fn cosmos_check() {
+        let holder = "osmo1qnufjmd8vwm6j6d3q28wxqr4d8408f34p7knfq";
+        let ibc = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![
+                    address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into(),
+                    cosmos_address!(holder).into(),
+                ],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![
+                    cosmos_address!("cosmos1qnufjmd8vwm6j6d3q28wxqr4d8408f34f99rlj").into(),
+                ],
+                platform_users: None,
+            },
+        ];
+
+        let ctx = ProviderContext::new().with_cosmos(
+            "osmosis-1",
+            Arc::new(StaticQuerier(HashMap::from([
+                ((NATIVE, holder), "10"),
+                ((ibc, holder), "0.5"),
+            ]))),
+        );
+
+        let check = |requirement: serde_json::Value| {
+            let ctx = ctx.clone();
+            let users = users.clone();
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+                assert_eq!(requirement.typ, RequirementType::Cosmos);
+
+                CosmosRequirement::try_from(&requirement)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                    .into_iter()
+                    .map(|a| (a.user_id, a.access, a.error))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 0,
+                "type": "COSMOS",
+                "chain": "osmosis-1",
+                "data": { "minAmount": "5" },
+            }))
+            .await,
+            vec![(0, Some(true), None), (1, Some(false), None)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 1,
+                "type": "COSMOS",
+                "chain": "osmosis-1",
+                "data": { "denom": ibc, "minAmount": "1" },
+            }))
+            .await,
+            vec![(0, Some(false), None), (1, Some(false), None)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 2,
+                "type": "COSMOS",
+                "chain": "juno-1",
+            }))
+            .await[0]
+                .2
+                .as_deref(),
+            Some("Chain `juno-1` is not supported")
+        );
+    }
+}
@@ -4,10 +4,22 @@ use thiserror::Error;
 pub enum CheckableError {
     #[error("Missing field `{0}`")]
     MissingField(String),
+    #[error("Invalid field `{0}`: {1}")]
+    InvalidField(String, String),
     #[error("Chain `{0}` is not supported")]
     NoSuchChain(String),
     #[error("No address attached to user `{0}`")]
     MissingUserAddress(String),
     #[error("No address attached to requirement `id: {0}`")]
     MissingTokenAddress(String),
+    #[error("No Safe Transaction Service configured for chain `{0}`")]
+    NoSafeService(String),
+    #[error("Failed to find the Safes of `{0}`: {1}")]
+    SafeLookup(String, String),
+    #[error("Failed to find the delegations to `{0}`: {1}")]
+    DelegationLookup(String, String),
+    #[error("Failed to get the USD price of `{0}`: {1}")]
+    PriceLookup(String, String),
+    #[error("Failed to resolve ENS name `{0}`: {1}")]
+    EnsResolution(String, String),
 }
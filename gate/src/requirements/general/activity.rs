@@ -0,0 +1,239 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        Amount, AmountLimits, Chain, EvmChain, NumberId, ReqUserAccess, Requirement, Snapshot,
+        User, U256,
+    },
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Number of transactions sent from the addresses of the user.
+pub struct TxCountRequirement {
+    id: NumberId,
+    data: Option<AmountLimits>,
+    chain: EvmChain,
+    snapshot: Option<Snapshot>,
+}
+
+/// Days since the first activity of the addresses of the user.
+pub struct WalletAgeRequirement {
+    id: NumberId,
+    data: Option<AmountLimits>,
+    chain: EvmChain,
+}
+
+fn evm_chain(req: &Requirement) -> Result<EvmChain, CheckableError> {
+    req.chain
+        .as_ref()
+        .and_then(Chain::evm)
+        .ok_or_else(|| CheckableError::MissingField("chain".into()))
+}
+
+#[async_trait]
+impl Checkable for TxCountRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) = match user_addresses(self.id, users, User::evm_addresses) {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
+
+        let Some(activity) = ctx.activity(self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let counts = activity
+            .get_transaction_count(&user_addresses, self.snapshot)
+            .await
+            .into_iter()
+            .map(|count| count.map(|count| Amount::new(U256::from(count), 0)))
+            .collect();
+
+        balance_accesses(self.id, &user_ids, counts, &self.data)
+    }
+}
+
+#[async_trait]
+impl Checkable for WalletAgeRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) = match user_addresses(self.id, users, User::evm_addresses) {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
+
+        let Some(activity) = ctx.activity(self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("This should be fine")
+            .as_secs();
+
+        // Addresses that were never active are zero days old
+        let ages = activity
+            .get_first_activity(&user_addresses)
+            .await
+            .into_iter()
+            .map(|first| {
+                first.map(|first| {
+                    let days = first.map_or(0, |first| now.saturating_sub(first) / SECONDS_PER_DAY);
+
+                    Amount::new(U256::from(days), 0)
+                })
+            })
+            .collect();
+
+        balance_accesses(self.id, &user_ids, ages, &self.data)
+    }
+}
+
+impl TryFrom<&Requirement> for TxCountRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(TxCountRequirement {
+            id: req.id,
+            data: AmountLimits::from_req(req)?,
+            chain: evm_chain(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
+
+impl TryFrom<&Requirement> for WalletAgeRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(WalletAgeRequirement {
+            id: req.id,
+            data: AmountLimits::from_req(req)?,
+            chain: evm_chain(req)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SECONDS_PER_DAY;
+    use crate::{
+        address,
+        requirements::check_access,
+        types::{Address, Requirement, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{activity::WalletActivity, general::ProviderError},
+        EvmChain, MemoryQuerier, ProviderContext, Snapshot,
+    };
+    use std::{
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    const VETERAN: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+
+    // The veteran sent 12 transactions and was first active 100 days ago,
+    // the other addresses were never active
+    struct StaticActivity;
+
+    #[async_trait]
+    impl WalletActivity for StaticActivity {
+        async fn get_transaction_count(
+            &self,
+            user_addresses: &[Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<u64, ProviderError>> {
+            user_addresses
+                .iter()
+                .map(|address| Ok(if *address == address!(VETERAN) { 12 } else { 0 }))
+                .collect()
+        }
+
+        async fn get_first_activity(
+            &self,
+            user_addresses: &[Address],
+        ) -> Vec<Result<Option<u64>, ProviderError>> {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            user_addresses
+                .iter()
+                .map(|address| {
+                    Ok((*address == address!(VETERAN)).then(|| now - 100 * SECONDS_PER_DAY))
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn activity_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(VETERAN).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new()
+            .with_querier(EvmChain::Ethereum, Arc::new(MemoryQuerier::new()))
+            .with_activity(EvmChain::Ethereum, Arc::new(StaticActivity));
+
+        let requirements: Vec<Requirement> = serde_json::from_value(serde_json::json!([
+            {
+                "id": 0,
+                "type": "WALLET_AGE",
+                "chain": "ETHEREUM",
+                "data": { "minAmount": "90" },
+            },
+            {
+                "id": 1,
+                "type": "TX_COUNT",
+                "chain": "ETHEREUM",
+                "data": { "minAmount": "10" },
+            },
+        ]))
+        .unwrap();
+
+        let result = check_access(&ctx, &users, &requirements, "0 AND 1", true).await;
+
+        assert_eq!(
+            result.accesses.iter().map(|a| a.access).collect::<Vec<_>>(),
+            vec![Some(true), Some(false)]
+        );
+
+        let too_old: Requirement = serde_json::from_value(serde_json::json!({
+            "id": 2,
+            "type": "WALLET_AGE",
+            "chain": "ETHEREUM",
+            "data": { "minAmount": "90", "maxAmount": "99" },
+        }))
+        .unwrap();
+        assert_eq!(
+            too_old.inner(&ctx).await.unwrap().check(&ctx, &users).await[0].access,
+            Some(false)
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{
-    requirements::{errors::CheckableError, Checkable},
-    types::{Address, Amount, NumberId, ReqUserAccess, Requirement, User},
+    requirements::{errors::CheckableError, general::ens::resolve_names, Checkable},
+    types::{Amount, ChainAddress, NumberId, ReqUserAccess, Requirement, User, U256},
 };
 use async_trait::async_trait;
+use providers::ProviderContext;
 
 struct AllowlistData {
-    addresses: Vec<Address>,
+    addresses: Vec<ChainAddress>,
+    // ENS names of the list that failed to resolve
+    unresolved: Vec<CheckableError>,
 }
 
 pub struct AllowListRequirement {
@@ -13,22 +16,51 @@ pub struct AllowListRequirement {
     data: AllowlistData,
 }
 
+impl AllowListRequirement {
+    /// Replaces the ENS names of the list with the addresses they resolve to.
+    pub async fn resolve_names(mut self, ctx: &ProviderContext) -> Self {
+        let (addresses, unresolved) = resolve_names(ctx, &self.data.addresses).await;
+
+        self.data = AllowlistData {
+            addresses,
+            unresolved,
+        };
+
+        self
+    }
+}
+
 #[async_trait]
 impl Checkable for AllowListRequirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
+    async fn check(&self, _ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        // Addresses missing from the list might be the ones of the names that
+        // failed to resolve
+        let unresolved = (!self.data.unresolved.is_empty()).then(|| {
+            self.data
+                .unresolved
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+
         users
             .iter()
             .flat_map(|u| u.addresses.iter().cloned().map(|address| (u.id, address)))
             .map(|(user_id, address)| {
                 let access = self.data.addresses.contains(&address);
+                let error = (!access).then(|| unresolved.clone()).flatten();
 
                 ReqUserAccess {
                     requirement_id: self.id,
                     user_id,
-                    access: Some(access),
-                    amount: Some(access as i8 as Amount),
+                    access: error.is_none().then_some(access),
+                    amount: error
+                        .is_none()
+                        .then(|| Amount::new(U256::from(access as u8), 0)),
+                    usd_amount: None,
                     warning: None,
-                    error: None,
+                    error,
                 }
             })
             .collect()
@@ -45,6 +77,7 @@ impl TryFrom<&Requirement> for AllowListRequirement {
                     id: req.id,
                     data: AllowlistData {
                         addresses: addresses.to_vec(),
+                        unresolved: vec![],
                     },
                 }),
                 None => Err(CheckableError::MissingField("addresses".into())),
@@ -61,18 +94,19 @@ mod test {
         requirements::{general::allowlist::AllowListRequirement, Checkable},
         types::User,
     };
+    use providers::ProviderContext;
 
     #[tokio::test]
     async fn allowlist_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")],
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3")],
+            addresses: vec![address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3").into()],
             platform_users: None,
         }];
 
@@ -80,15 +114,16 @@ mod test {
             id: 0,
             data: super::AllowlistData {
                 addresses: vec![
-                    address!("0xe43878ce78934fe8007748ff481f03b8ee3b97de"),
-                    address!("0x20cc54c7ebc5f43b74866d839b4bd5c01bb23503"),
+                    address!("0xe43878ce78934fe8007748ff481f03b8ee3b97de").into(),
+                    address!("0x20cc54c7ebc5f43b74866d839b4bd5c01bb23503").into(),
                 ],
+                unresolved: vec![],
             },
         };
 
         assert_eq!(
             allowlist
-                .check(&users_1)
+                .check(&ProviderContext::new(), &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -97,7 +132,7 @@ mod test {
         );
         assert_ne!(
             allowlist
-                .check(&users_2)
+                .check(&ProviderContext::new(), &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
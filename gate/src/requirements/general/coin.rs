@@ -1,80 +1,82 @@
 use crate::{
-    requirements::{errors::CheckableError, utils::check_if_in_range, Checkable},
-    types::{Address, AmountLimits, EvmChain, NumberId, ReqUserAccess, Requirement, User},
+    requirements::{
+        errors::CheckableError,
+        utils::{
+            balance_accesses, error_for_users, usd_accesses, usd_price, user_addresses,
+            with_holders, Holders,
+        },
+        Checkable,
+    },
+    types::{
+        Address, AmountLimits, Chain, EvmChain, NumberId, ReqUserAccess, Requirement, Snapshot,
+        User,
+    },
 };
 use async_trait::async_trait;
-use providers::{evm::general::PROVIDERS, BalanceQuerier};
+use providers::ProviderContext;
 
 pub struct CoinRequirement {
     id: NumberId,
     data: Option<AmountLimits>,
+    usd: Option<AmountLimits>,
     chain: EvmChain,
+    snapshot: Option<Snapshot>,
+    holders: Holders,
 }
 
 #[async_trait]
 impl Checkable for CoinRequirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
-        let user_addresses: Vec<Address> = users
-            .iter()
-            .flat_map(|u| u.addresses.iter().cloned())
-            .collect();
-
-        if user_addresses.is_empty() {
-            return users
-                .iter()
-                .map(|u| ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id: u.id,
-                    access: None,
-                    amount: None,
-                    warning: None,
-                    error: Some(CheckableError::MissingUserAddress(u.id.to_string()).to_string()),
-                })
-                .collect();
-        }
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (users, failed) = match with_holders(
+            ctx,
+            self.id,
+            self.chain,
+            Address::zero(),
+            users,
+            self.holders,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => return error_for_users(self.id, users, e),
+        };
 
-        let provider = PROVIDERS
-            .get(&(self.chain as u8))
-            .expect("This should be fine");
-
-        provider
-            .get_native_balance(&user_addresses)
-            .await
-            .iter()
-            .enumerate()
-            .map(|(idx, b)| {
-                let mut error = None;
-                let mut amount = None;
-
-                match b {
-                    Ok(v) => amount = Some(*v),
-                    Err(e) => error = Some(e.to_string()),
-                };
+        let (user_ids, user_addresses) = match user_addresses(self.id, &users, User::evm_addresses)
+        {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
+
+        let Some(provider) = ctx.get(self.chain) else {
+            return error_for_users(
+                self.id,
+                &users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let balances = provider
+            .get_native_balance(&user_addresses, self.snapshot)
+            .await;
+
+        // Balances are compared in USD when the requirement has USD limits
+        let mut accesses = match self.usd {
+            Some(_) => match usd_price(ctx, self.chain, Address::zero()).await {
+                Ok(price) => usd_accesses(self.id, &user_ids, balances, price, &self.usd),
+                Err(e) => return error_for_users(self.id, &users, e),
+            },
+            None => balance_accesses(self.id, &user_ids, balances, &self.data),
+        };
+        accesses.extend(failed);
 
-                let user_id = users
-                    .iter()
-                    .find(|u| u.addresses.contains(&user_addresses[idx]))
-                    .unwrap()
-                    .id;
-
-                ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id,
-                    access: if error.is_none() {
-                        Some(check_if_in_range(
-                            amount.expect("This should be fine"),
-                            &self.data,
-                            false,
-                        ))
-                    } else {
-                        None
-                    },
-                    amount: if error.is_none() { amount } else { None },
-                    warning: None,
-                    error,
-                }
-            })
-            .collect()
+        accesses
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        match self.usd {
+            Some(_) => &self.usd,
+            None => &self.data,
+        }
     }
 }
 
@@ -82,16 +84,15 @@ impl TryFrom<&Requirement> for CoinRequirement {
     type Error = CheckableError;
 
     fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
-        match req.chain {
+        match req.chain.as_ref().and_then(Chain::evm) {
             Some(chain) => {
-                if PROVIDERS.get(&(chain as u8)).is_none() {
-                    return Err(CheckableError::NoSuchChain(format!("{chain:?}")));
-                }
-
                 let res = CoinRequirement {
                     id: req.id,
-                    data: AmountLimits::from_req(req),
+                    data: AmountLimits::from_req(req)?,
+                    usd: AmountLimits::usd_from_req(req)?,
                     chain,
+                    snapshot: req.snapshot,
+                    holders: Holders::from_req(req),
                 };
 
                 Ok(res)
@@ -105,35 +106,48 @@ impl TryFrom<&Requirement> for CoinRequirement {
 mod test {
     use crate::{
         address,
-        requirements::{general::coin::CoinRequirement, Checkable},
-        types::{AmountLimits, EvmChain, User},
+        requirements::{general::coin::CoinRequirement, utils::Holders, Checkable},
+        types::{AmountLimits, EvmChain, Requirement, User},
     };
+    use providers::{MemoryQuerier, ProviderContext};
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn coin_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")],
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503")],
+            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
             platform_users: None,
         }];
 
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(MemoryQuerier::new().with_native_balance(
+                address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"),
+                "0.0005".parse().unwrap(),
+            )),
+        );
+
         let req = CoinRequirement {
             id: 0,
             chain: EvmChain::Ethereum,
+            snapshot: None,
+            holders: Holders::default(),
+            usd: None,
             data: Some(AmountLimits {
-                min_amount: Some(0.0004),
+                min_amount: Some("0.0004".parse().unwrap()),
                 max_amount: None,
             }),
         };
 
         assert_eq!(
-            req.check(&users_1)
+            req.check(&ctx, &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -141,7 +155,7 @@ mod test {
             vec![true]
         );
         assert_ne!(
-            req.check(&users_2)
+            req.check(&ctx, &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -149,4 +163,31 @@ mod test {
             vec![true]
         );
     }
+
+    #[tokio::test]
+    async fn invalid_limits() {
+        let ctx =
+            ProviderContext::new().with_querier(EvmChain::Ethereum, Arc::new(MemoryQuerier::new()));
+        let requirement = |min_amount: &str| -> Requirement {
+            serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "COIN",
+                "chain": "ETHEREUM",
+                "data": { "minAmount": min_amount },
+            }))
+            .unwrap()
+        };
+
+        assert!(requirement("1e18").inner(&ctx).await.is_ok());
+        assert_eq!(
+            requirement("-1")
+                .inner(&ctx)
+                .await
+                .err()
+                .unwrap()
+                .to_string(),
+            "Invalid field `minAmount`: Invalid amount `-1`"
+        );
+        assert!(requirement("1,5").inner(&ctx).await.is_err());
+    }
 }
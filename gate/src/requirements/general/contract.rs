@@ -0,0 +1,356 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{error_for_users, missing_user_addresses},
+        Checkable,
+    },
+    types::{
+        Address, Amount, Chain, EvmChain, NumberId, Operator, ReqUserAccess, Requirement, Snapshot,
+        User, U256,
+    },
+};
+use async_trait::async_trait;
+use providers::{
+    evm::contract::{Token, ViewCall},
+    ProviderContext,
+};
+
+/// Replaced by the address of the user in the arguments and the value.
+pub const USER_ADDRESS: &str = "USER_ADDRESS";
+
+/// Result of a read-only contract call compared with a value, like
+/// `isMember(USER_ADDRESS) == true` or `stakedOf(USER_ADDRESS) >= 100`.
+pub struct ContractRequirement {
+    id: NumberId,
+    chain: EvmChain,
+    contract: Address,
+    call: ViewCall,
+    args: Vec<String>,
+    operator: Operator,
+    value: String,
+    snapshot: Option<Snapshot>,
+}
+
+// Flipping the sign bit of two's complement values orders them as unsigned
+fn signed_key(value: U256) -> U256 {
+    value ^ (U256::one() << 255)
+}
+
+fn parse_int(value: &str) -> Option<U256> {
+    let min = U256::one() << 255;
+
+    match value.strip_prefix('-') {
+        Some(abs) => U256::from_dec_str(abs)
+            .ok()
+            .filter(|abs| *abs <= min)
+            .map(|abs| (!abs).overflowing_add(U256::one()).0),
+        None => U256::from_dec_str(value).ok().filter(|value| *value < min),
+    }
+}
+
+impl ContractRequirement {
+    fn substitute(template: &str, address: Address) -> String {
+        template.replace(USER_ADDRESS, &format!("{address:#x}"))
+    }
+
+    fn encode(&self, address: Address) -> Result<Vec<u8>, CheckableError> {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| Self::substitute(arg, address))
+            .collect();
+
+        self.call
+            .encode(&args)
+            .map_err(|e| CheckableError::InvalidField("args".into(), e.to_string()))
+    }
+
+    // Whether the returned value matches, along with the amount of numeric
+    // values
+    fn evaluate(&self, token: Token, address: Address) -> Result<(bool, Option<Amount>), String> {
+        let value = Self::substitute(&self.value, address);
+        let invalid = || format!("Invalid value `{value}` for the returned `{token}`");
+
+        let (ordering, amount) = match &token {
+            Token::Uint(number) => (
+                number.cmp(&U256::from_dec_str(&value).map_err(|_| invalid())?),
+                Some(*number),
+            ),
+            Token::Int(number) => (
+                signed_key(*number).cmp(&signed_key(parse_int(&value).ok_or_else(invalid)?)),
+                None,
+            ),
+            Token::Bool(boolean) => (
+                boolean.cmp(&value.parse().map_err(|_| invalid())?),
+                Some(U256::from(*boolean as u8)),
+            ),
+            Token::Address(address) => (address.cmp(&value.parse().map_err(|_| invalid())?), None),
+            Token::String(string) => (string.as_str().cmp(value.as_str()), None),
+            _ => return Err(format!("Unsupported return value `{token}`")),
+        };
+
+        Ok((
+            self.operator.matches(ordering),
+            amount.map(|amount| Amount::new(amount, 0)),
+        ))
+    }
+}
+
+#[async_trait]
+impl Checkable for ContractRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let user_addresses: Vec<(NumberId, Address)> = users
+            .iter()
+            .flat_map(|u| u.evm_addresses().map(|address| (u.id, address)))
+            .collect();
+
+        if user_addresses.is_empty() {
+            return missing_user_addresses(self.id, users);
+        }
+
+        let Some(caller) = ctx.caller(self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let calls = match user_addresses
+            .iter()
+            .map(|(_, address)| self.encode(*address))
+            .collect()
+        {
+            Ok(calls) => calls,
+            Err(e) => return error_for_users(self.id, users, e),
+        };
+
+        let results = caller.call(self.contract, calls, self.snapshot).await;
+
+        user_addresses
+            .into_iter()
+            .zip(results)
+            .map(|((user_id, address), result)| {
+                let evaluated = result
+                    .and_then(|data| self.call.decode(&data))
+                    .map_err(|e| e.to_string())
+                    .and_then(|token| self.evaluate(token, address));
+
+                match evaluated {
+                    Ok((access, amount)) => ReqUserAccess {
+                        requirement_id: self.id,
+                        user_id,
+                        access: Some(access),
+                        amount,
+                        usd_amount: None,
+                        warning: None,
+                        error: None,
+                    },
+                    Err(e) => ReqUserAccess {
+                        requirement_id: self.id,
+                        user_id,
+                        access: None,
+                        amount: None,
+                        usd_amount: None,
+                        warning: None,
+                        error: Some(e),
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&Requirement> for ContractRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let missing = |field: &str| CheckableError::MissingField(field.into());
+
+        let chain = req
+            .chain
+            .as_ref()
+            .and_then(Chain::evm)
+            .ok_or_else(|| missing("chain"))?;
+        let contract = req
+            .address
+            .as_ref()
+            .and_then(|address| address.evm())
+            .ok_or_else(|| CheckableError::MissingTokenAddress(req.id.to_string()))?;
+        let data = req.data.as_ref().ok_or_else(|| missing("data"))?;
+
+        let function = data.function.as_ref().ok_or_else(|| missing("function"))?;
+        let returns = data.returns.as_ref().ok_or_else(|| missing("returns"))?;
+        let call = ViewCall::new(function, returns)
+            .map_err(|e| CheckableError::InvalidField("function".into(), e.to_string()))?;
+
+        let operator = data.operator.ok_or_else(|| missing("operator"))?;
+
+        if !matches!(operator, Operator::Eq | Operator::Ne) && !call.returns_number() {
+            return Err(CheckableError::InvalidField(
+                "operator".into(),
+                format!("`{returns}` values can only be compared with `==` and `!=`"),
+            ));
+        }
+
+        let requirement = ContractRequirement {
+            id: req.id,
+            chain,
+            contract,
+            call,
+            args: data.args.clone().unwrap_or_default(),
+            operator,
+            value: data.value.clone().ok_or_else(|| missing("value"))?,
+            snapshot: req.snapshot,
+        };
+
+        // Arguments that don't fit the signature fail for every user alike
+        requirement.encode(Address::zero())?;
+
+        Ok(requirement)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ContractRequirement;
+    use crate::{
+        address,
+        requirements::Checkable,
+        types::{Address, Requirement, User, U256},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{contract::ContractCaller, general::ProviderError},
+        EvmChain, ProviderContext, Snapshot,
+    };
+    use std::sync::Arc;
+
+    const MEMBER: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+
+    // Every function returns a single word: 1 for the member, 0 for the
+    // others, which reads as `true`, a stake of 1 or the member's address
+    struct StaticCaller;
+
+    #[async_trait]
+    impl ContractCaller for StaticCaller {
+        async fn call(
+            &self,
+            _contract: Address,
+            calls: Vec<Vec<u8>>,
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Vec<u8>, ProviderError>> {
+            // The address of the member as the first argument
+            let member = [&[0u8; 12], address!(MEMBER).as_bytes()].concat();
+
+            calls
+                .into_iter()
+                .map(|data| {
+                    let mut word = [0u8; 32];
+
+                    if data[4..36] == member {
+                        U256::one().to_big_endian(&mut word);
+                    }
+
+                    Ok(word.to_vec())
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn contract_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(MEMBER).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new().with_caller(EvmChain::Ethereum, Arc::new(StaticCaller));
+
+        let requirement = |data: serde_json::Value| -> Requirement {
+            serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "CONTRACT",
+                "chain": "ETHEREUM",
+                "address": "0x5ba1e12693dc8f9c48aad8770482f4739beed696",
+                "data": data,
+            }))
+            .unwrap()
+        };
+        let check = |data: serde_json::Value| {
+            let ctx = ctx.clone();
+            let users = users.clone();
+
+            async move {
+                ContractRequirement::try_from(&requirement(data))
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                    .into_iter()
+                    .map(|a| a.access)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            check(serde_json::json!({
+                "function": "isMember(address)",
+                "args": ["USER_ADDRESS"],
+                "returns": "bool",
+                "operator": "==",
+                "value": "true",
+            }))
+            .await,
+            vec![Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "function": "stakedOf(address,uint256)",
+                "args": ["USER_ADDRESS", "7"],
+                "returns": "int256",
+                "operator": ">",
+                "value": "-1",
+            }))
+            .await,
+            vec![Some(true), Some(true)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "function": "scoreOf(address)",
+                "args": ["USER_ADDRESS"],
+                "returns": "uint256",
+                "operator": "<",
+                "value": "1",
+            }))
+            .await,
+            vec![Some(false), Some(true)]
+        );
+
+        for invalid in [
+            serde_json::json!({ "function": "isMember(address)", "returns": "bool" }),
+            serde_json::json!({
+                "function": "isMember(address)",
+                "args": ["USER_ADDRESS"],
+                "returns": "bool",
+                "operator": ">=",
+                "value": "true",
+            }),
+            serde_json::json!({
+                "function": "isMember(address)",
+                "args": ["not an address"],
+                "returns": "bool",
+                "operator": "==",
+                "value": "true",
+            }),
+        ] {
+            assert!(ContractRequirement::try_from(&requirement(invalid)).is_err());
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{error_for_users, missing_user_addresses},
+        Checkable,
+    },
+    types::{
+        Address, Amount, ChainAddress, EvmChain, NumberId, ReqUserAccess, Requirement, User, U256,
+    },
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use providers::{
+    evm::{ens, general::ProviderError},
+    ProviderContext,
+};
+
+/// Ownership of an ENS name: of the given name, of a subdomain of it when
+/// `subdomains` is set, or of the primary name of the user without a name.
+/// Subdomains can't be enumerated on-chain, so only the primary name of the
+/// user is checked for being one, other owned subdomains are not found.
+pub struct EnsRequirement {
+    id: NumberId,
+    name: Option<String>,
+    subdomains: bool,
+}
+
+fn no_ens() -> CheckableError {
+    CheckableError::NoSuchChain(format!("{:?}", EvmChain::Ethereum))
+}
+
+/// Addresses of an allowlist with its ENS names replaced by the addresses
+/// they resolve to, names without an address are left out. Names that fail
+/// to resolve are returned as errors, without affecting the other ones.
+pub async fn resolve_names(
+    ctx: &ProviderContext,
+    addresses: &[ChainAddress],
+) -> (Vec<ChainAddress>, Vec<CheckableError>) {
+    let mut resolved: Vec<ChainAddress> = addresses
+        .iter()
+        .filter(|address| address.ens().is_none())
+        .cloned()
+        .collect();
+    let names: Vec<&str> = addresses.iter().filter_map(ChainAddress::ens).collect();
+
+    if names.is_empty() {
+        return (resolved, vec![]);
+    }
+
+    let Some(ens) = ctx.ens() else {
+        let errors = names
+            .into_iter()
+            .map(|name| CheckableError::EnsResolution(name.into(), no_ens().to_string()))
+            .collect();
+
+        return (resolved, errors);
+    };
+
+    let mut errors = vec![];
+    let addresses = join_all(names.iter().map(|name| ens.resolve(name))).await;
+
+    for (name, address) in names.into_iter().zip(addresses) {
+        match address {
+            Ok(address) => resolved.extend(address.map(ChainAddress::Evm)),
+            Err(e) => errors.push(CheckableError::EnsResolution(name.into(), e.to_string())),
+        }
+    }
+
+    (resolved, errors)
+}
+
+#[async_trait]
+impl Checkable for EnsRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let user_addresses: Vec<(NumberId, Address)> = users
+            .iter()
+            .flat_map(|u| u.evm_addresses().map(|address| (u.id, address)))
+            .collect();
+
+        if user_addresses.is_empty() {
+            return missing_user_addresses(self.id, users);
+        }
+
+        let Some(ens) = ctx.ens() else {
+            return error_for_users(self.id, users, no_ens());
+        };
+
+        let owns = |address: Address| {
+            let ens = ens.clone();
+
+            async move {
+                let name = match (&self.name, self.subdomains) {
+                    (Some(name), false) => Some(name.clone()),
+                    (name, _) => ens.primary_name(address).await?.filter(|primary| {
+                        name.as_ref()
+                            .is_none_or(|name| primary.ends_with(&format!(".{name}")))
+                    }),
+                };
+
+                match name {
+                    Some(name) => Ok(ens.owner(&name).await? == Some(address)),
+                    None => Ok::<_, ProviderError>(false),
+                }
+            }
+        };
+
+        let owned = join_all(user_addresses.iter().map(|(_, address)| owns(*address))).await;
+
+        user_addresses
+            .into_iter()
+            .zip(owned)
+            .map(|((user_id, _), owned)| match owned {
+                Ok(access) => ReqUserAccess {
+                    requirement_id: self.id,
+                    user_id,
+                    access: Some(access),
+                    amount: Some(Amount::new(U256::from(access as u8), 0)),
+                    usd_amount: None,
+                    warning: None,
+                    error: None,
+                },
+                Err(e) => ReqUserAccess {
+                    requirement_id: self.id,
+                    user_id,
+                    access: None,
+                    amount: None,
+                    usd_amount: None,
+                    warning: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&Requirement> for EnsRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let name = req
+            .data
+            .as_ref()
+            .and_then(|data| data.name.as_deref())
+            .map(ens::normalize);
+        let subdomains = req
+            .data
+            .as_ref()
+            .and_then(|data| data.subdomains)
+            .unwrap_or_default();
+
+        if subdomains && name.is_none() {
+            return Err(CheckableError::MissingField("name".into()));
+        }
+
+        Ok(EnsRequirement {
+            id: req.id,
+            name,
+            subdomains,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EnsRequirement;
+    use crate::{
+        address,
+        types::{Address, Requirement, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{ens::EnsResolver, general::ProviderError},
+        ProviderContext,
+    };
+    use std::sync::Arc;
+
+    const OWNER: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+
+    // The owner holds `guild.eth` and `alice.guild.eth`, the latter being
+    // their primary name, and `broken.eth` fails to resolve
+    struct StaticResolver;
+
+    #[async_trait]
+    impl EnsResolver for StaticResolver {
+        async fn resolve(&self, name: &str) -> Result<Option<Address>, ProviderError> {
+            if name == "broken.eth" {
+                return Err(ProviderError::Other("Request timed out".into()));
+            }
+
+            self.owner(name).await
+        }
+
+        async fn owner(&self, name: &str) -> Result<Option<Address>, ProviderError> {
+            Ok(matches!(name, "guild.eth" | "alice.guild.eth").then(|| address!(OWNER)))
+        }
+
+        async fn primary_name(&self, address: Address) -> Result<Option<String>, ProviderError> {
+            Ok((address == address!(OWNER)).then(|| "alice.guild.eth".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn ens_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(OWNER).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new().with_ens(Arc::new(StaticResolver));
+
+        let check = |requirement: serde_json::Value| {
+            let ctx = ctx.clone();
+            let users = users.clone();
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+
+                requirement
+                    .inner(&ctx)
+                    .await
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                    .into_iter()
+                    .map(|a| a.access)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        for data in [
+            serde_json::json!({ "name": "Guild.eth" }),
+            serde_json::json!({ "name": "guild.eth", "subdomains": true }),
+            serde_json::json!({}),
+        ] {
+            assert_eq!(
+                check(serde_json::json!({ "id": 0, "type": "ENS", "data": data })).await,
+                vec![Some(true), Some(false)]
+            );
+        }
+
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 1,
+                "type": "ENS",
+                "data": { "name": "other.eth", "subdomains": true },
+            }))
+            .await,
+            vec![Some(false), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 2,
+                "type": "ALLOWLIST",
+                "data": { "addresses": ["guild.eth", "nobody.eth"] },
+            }))
+            .await,
+            vec![Some(true), Some(false)]
+        );
+
+        let errors = |requirement: serde_json::Value, ctx: ProviderContext| {
+            let users = users.clone();
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+
+                requirement
+                    .inner(&ctx)
+                    .await
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                    .into_iter()
+                    .map(|a| (a.access, a.error))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // Only the users missing from the list are affected by the names
+        // that failed to resolve
+        assert_eq!(
+            errors(
+                serde_json::json!({
+                    "id": 3,
+                    "type": "ALLOWLIST",
+                    "data": { "addresses": ["guild.eth", "broken.eth"] },
+                }),
+                ctx.clone(),
+            )
+            .await,
+            vec![
+                (Some(true), None),
+                (
+                    None,
+                    Some("Failed to resolve ENS name `broken.eth`: Request timed out".into())
+                ),
+            ]
+        );
+        assert_eq!(
+            errors(
+                serde_json::json!({
+                    "id": 4,
+                    "type": "ALLOWLIST",
+                    "data": { "addresses": ["guild.eth"] },
+                }),
+                ProviderContext::new(),
+            )
+            .await[0]
+                .1
+                .as_deref(),
+            Some("Failed to resolve ENS name `guild.eth`: Chain `Ethereum` is not supported")
+        );
+        assert!(EnsRequirement::try_from(
+            &serde_json::from_value::<Requirement>(serde_json::json!({
+                "id": 5,
+                "type": "ENS",
+                "data": { "subdomains": true },
+            }))
+            .unwrap()
+        )
+        .is_err());
+    }
+}
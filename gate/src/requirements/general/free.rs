@@ -1,8 +1,9 @@
 use crate::{
     requirements::{errors::CheckableError, Checkable},
-    types::{NumberId, ReqUserAccess, Requirement, User},
+    types::{Amount, NumberId, ReqUserAccess, Requirement, User, U256},
 };
 use async_trait::async_trait;
+use providers::ProviderContext;
 
 pub struct FreeRequirement {
     id: NumberId,
@@ -10,7 +11,7 @@ pub struct FreeRequirement {
 
 #[async_trait]
 impl Checkable for FreeRequirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
+    async fn check(&self, _ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
         users
             .iter()
             .flat_map(|u| u.addresses.iter().cloned().map(|address| (u.id, address)))
@@ -18,7 +19,8 @@ impl Checkable for FreeRequirement {
                 requirement_id: self.id,
                 user_id,
                 access: Some(true),
-                amount: Some(1.0),
+                amount: Some(Amount::new(U256::one(), 0)),
+                usd_amount: None,
                 warning: None,
                 error: None,
             })
@@ -41,25 +43,26 @@ mod test {
         requirements::{general::free::FreeRequirement, Checkable},
         types::User,
     };
+    use providers::ProviderContext;
 
     #[tokio::test]
     async fn free_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")],
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3")],
+            addresses: vec![address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3").into()],
             platform_users: None,
         }];
 
         let req = FreeRequirement { id: 0 };
 
         assert_eq!(
-            req.check(&users_1)
+            req.check(&ProviderContext::new(), &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -67,7 +70,7 @@ mod test {
             vec![true]
         );
         assert_ne!(
-            req.check(&users_2)
+            req.check(&ProviderContext::new(), &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
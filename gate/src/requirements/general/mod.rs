@@ -1,4 +1,9 @@
+pub mod activity;
 pub mod allowlist;
 pub mod coin;
+pub mod contract;
+pub mod ens;
 pub mod free;
+pub mod poap;
+pub mod safe;
 pub mod token;
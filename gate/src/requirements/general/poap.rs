@@ -0,0 +1,406 @@
+use crate::{
+    address,
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        Address, Amount, AmountLimits, EvmChain, NumberId, ReqUserAccess, Requirement, User, U256,
+    },
+};
+use async_trait::async_trait;
+use providers::{
+    evm::{
+        contract::{ContractCaller, ViewCall},
+        general::ProviderError,
+    },
+    ProviderContext,
+};
+
+lazy_static::lazy_static! {
+    static ref POAP: Address = address!("0x22C1f6050E56d2876009903609a2cC3fEf83B415");
+    static ref BALANCE_OF: ViewCall =
+        ViewCall::new("balanceOf(address)", "uint256").expect("This should be fine");
+    static ref TOKEN_OF_OWNER_BY_INDEX: ViewCall =
+        ViewCall::new("tokenOfOwnerByIndex(address,uint256)", "uint256")
+            .expect("This should be fine");
+    static ref TOKEN_EVENT: ViewCall =
+        ViewCall::new("tokenEvent(uint256)", "uint256").expect("This should be fine");
+}
+
+// Tokens of the addresses enumerated in a single batch
+const TOKENS_PER_PAGE: u64 = 500;
+
+/// Number of POAPs the users hold from any of the events, counted by
+/// enumerating their tokens on Gnosis. Without a maximum amount, the tokens
+/// of an address are only enumerated until it has enough POAPs for the
+/// requirement, so its amount is then a lower bound of the POAPs it holds.
+pub struct PoapRequirement {
+    id: NumberId,
+    event_ids: Vec<U256>,
+    data: Option<AmountLimits>,
+}
+
+// Results of calling the function with each list of arguments
+async fn call_each(
+    caller: &(dyn ContractCaller + Send + Sync),
+    call: &ViewCall,
+    args: Vec<Vec<String>>,
+) -> Vec<Result<U256, ProviderError>> {
+    let calls = match args
+        .iter()
+        .map(|args| call.encode(args))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(calls) => calls,
+        Err(e) => {
+            return args
+                .iter()
+                .map(|_| Err(ProviderError::Other(e.to_string())))
+                .collect()
+        }
+    };
+
+    caller
+        .call(*POAP, calls, None)
+        .await
+        .into_iter()
+        .map(|data| {
+            call.decode(&data?)?
+                .into_uint()
+                .ok_or_else(|| ProviderError::Other("Invalid uint256 return data".into()))
+        })
+        .collect()
+}
+
+impl PoapRequirement {
+    // Number of POAPs after which more can't change the access, none when
+    // the requirement has a maximum amount
+    fn enough(&self) -> Option<Amount> {
+        match &self.data {
+            Some(AmountLimits {
+                max_amount: Some(_),
+                ..
+            }) => None,
+            Some(AmountLimits {
+                min_amount: Some(min_amount),
+                ..
+            }) if !min_amount.is_zero() => Some(*min_amount),
+            _ => Some(Amount::new(U256::one(), 0)),
+        }
+    }
+
+    async fn count(
+        &self,
+        caller: &(dyn ContractCaller + Send + Sync),
+        user_addresses: &[Address],
+    ) -> Vec<Result<Amount, ProviderError>> {
+        let balances = call_each(
+            caller,
+            &BALANCE_OF,
+            user_addresses
+                .iter()
+                .map(|address| vec![format!("{address:#x}")])
+                .collect(),
+        )
+        .await;
+
+        let totals: Vec<u64> = balances
+            .iter()
+            .map(|balance| balance.as_ref().map_or(0, |b| b.low_u64()))
+            .collect();
+        let mut counts: Vec<Result<u64, ProviderError>> = balances
+            .into_iter()
+            .map(|balance| balance.map(|_| 0))
+            .collect();
+
+        let enough = self.enough();
+        let counting = |count: &Result<u64, ProviderError>| match count {
+            Ok(count) => enough.map_or(true, |enough| Amount::new(U256::from(*count), 0) < enough),
+            Err(_) => false,
+        };
+
+        let mut start = 0;
+
+        loop {
+            // Next page of the tokens of the addresses still counted, along
+            // with the index of the address they belong to
+            let end = start + TOKENS_PER_PAGE;
+            let (owners, args): (Vec<usize>, Vec<Vec<String>>) = totals
+                .iter()
+                .enumerate()
+                .filter(|&(idx, _)| counting(&counts[idx]))
+                .flat_map(|(idx, &total)| {
+                    (start..total.min(end)).map(move |i| {
+                        (
+                            idx,
+                            vec![format!("{:#x}", user_addresses[idx]), i.to_string()],
+                        )
+                    })
+                })
+                .unzip();
+
+            if owners.is_empty() {
+                break;
+            }
+
+            let token_ids = call_each(caller, &TOKEN_OF_OWNER_BY_INDEX, args).await;
+            let events = call_each(
+                caller,
+                &TOKEN_EVENT,
+                token_ids
+                    .iter()
+                    .map(|token_id| vec![token_id.as_ref().map_or("0".into(), |id| id.to_string())])
+                    .collect(),
+            )
+            .await;
+
+            for ((owner, token_id), event) in owners.into_iter().zip(token_ids).zip(events) {
+                let Ok(count) = &mut counts[owner] else {
+                    continue;
+                };
+
+                match token_id.and(event) {
+                    Ok(event) if self.event_ids.contains(&event) => *count += 1,
+                    Ok(_) => (),
+                    Err(e) => counts[owner] = Err(e),
+                }
+            }
+
+            start = end;
+        }
+
+        counts
+            .into_iter()
+            .map(|count| count.map(|count| Amount::new(U256::from(count), 0)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Checkable for PoapRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) = match user_addresses(self.id, users, User::evm_addresses) {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
+
+        let Some(caller) = ctx.caller(EvmChain::Gnosis) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(format!("{:?}", EvmChain::Gnosis)),
+            );
+        };
+
+        let counts = self.count(caller.as_ref(), &user_addresses).await;
+
+        balance_accesses(self.id, &user_ids, counts, &self.data)
+    }
+}
+
+impl TryFrom<&Requirement> for PoapRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let event_ids = req
+            .data
+            .as_ref()
+            .and_then(|data| data.event_ids.clone())
+            .filter(|event_ids| !event_ids.is_empty())
+            .ok_or_else(|| CheckableError::MissingField("eventIds".into()))?;
+
+        Ok(PoapRequirement {
+            id: req.id,
+            event_ids: event_ids.into_iter().map(U256::from).collect(),
+            data: AmountLimits::from_req(req)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BALANCE_OF, TOKENS_PER_PAGE, TOKEN_EVENT, TOKEN_OF_OWNER_BY_INDEX};
+    use crate::{
+        address,
+        requirements::check_access,
+        types::{Address, Requirement, User, U256},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{contract::ContractCaller, general::ProviderError},
+        EvmChain, ProviderContext, Snapshot,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    const COLLECTOR: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+    const WHALE: &str = "0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503";
+
+    // The collector holds tokens 100, 101 and 102 from events 7, 7 and 8,
+    // the whale 2000 tokens from 100 onwards, and the tokens enumerated are
+    // counted
+    #[derive(Default)]
+    struct StaticPoap {
+        enumerated: AtomicUsize,
+    }
+
+    fn word(value: U256) -> Vec<u8> {
+        let mut word = [0u8; 32];
+        value.to_big_endian(&mut word);
+        word.to_vec()
+    }
+
+    #[async_trait]
+    impl ContractCaller for StaticPoap {
+        async fn call(
+            &self,
+            _contract: Address,
+            calls: Vec<Vec<u8>>,
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Vec<u8>, ProviderError>> {
+            let owner = |address: &str| word(U256::from_big_endian(address!(address).as_bytes()));
+            let selector = |call: &super::ViewCall, args: &[&str]| {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                call.encode(&args).unwrap()[..4].to_vec()
+            };
+            let zero = format!("{:#x}", Address::zero());
+            let argument =
+                |data: &[u8], idx: usize| U256::from_big_endian(&data[4 + 32 * idx..36 + 32 * idx]);
+
+            calls
+                .into_iter()
+                .map(|data| {
+                    let value = match data[..4].to_vec() {
+                        s if s == selector(&BALANCE_OF, &[&zero]) => match data[4..36].to_vec() {
+                            address if address == owner(COLLECTOR) => 3,
+                            address if address == owner(WHALE) => 2000,
+                            _ => 0,
+                        },
+                        s if s == selector(&TOKEN_OF_OWNER_BY_INDEX, &[&zero, "0"]) => {
+                            self.enumerated.fetch_add(1, Ordering::Relaxed);
+
+                            100 + argument(&data, 1).as_u64()
+                        }
+                        s if s == selector(&TOKEN_EVENT, &["0"]) => {
+                            match argument(&data, 0).as_u64() {
+                                102 => 8,
+                                _ => 7,
+                            }
+                        }
+                        _ => return Err(ProviderError::Other("execution reverted".into())),
+                    };
+
+                    Ok(word(U256::from(value)))
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn poap_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(COLLECTOR).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx =
+            ProviderContext::new().with_caller(EvmChain::Gnosis, Arc::new(StaticPoap::default()));
+
+        let check = |data: serde_json::Value| {
+            let requirement: Requirement = serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "POAP",
+                "data": data,
+            }))
+            .unwrap();
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                check_access(&ctx, &users, &[requirement], "0", true)
+                    .await
+                    .accesses
+                    .into_iter()
+                    .map(|a| a.access)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            check(serde_json::json!({ "eventIds": [8] })).await,
+            vec![Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({ "eventIds": [7, 8], "minAmount": "3" })).await,
+            vec![Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({ "eventIds": [7], "minAmount": "3" })).await,
+            vec![Some(false), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({ "eventIds": [9] })).await,
+            vec![Some(false), Some(false)]
+        );
+
+        // The tokens of the whale are only enumerated until it has enough
+        // POAPs, unless the requirement has a maximum amount, and its amount
+        // only counts the tokens enumerated
+        let whale = vec![User {
+            id: 0,
+            addresses: vec![address!(WHALE).into()],
+            platform_users: None,
+        }];
+        let enumerated = |data: serde_json::Value| {
+            let requirement: Requirement = serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "POAP",
+                "data": data,
+            }))
+            .unwrap();
+            let whale = whale.clone();
+
+            async move {
+                let poap = Arc::new(StaticPoap::default());
+                let ctx = ProviderContext::new().with_caller(EvmChain::Gnosis, poap.clone());
+                let access = check_access(&ctx, &whale, &[requirement], "0", true)
+                    .await
+                    .accesses
+                    .remove(0);
+                let amount = access
+                    .detailed
+                    .and_then(|detailed| detailed[0].amount)
+                    .map(|amount| amount.raw().low_u64());
+
+                (
+                    access.access,
+                    amount,
+                    poap.enumerated.load(Ordering::Relaxed),
+                )
+            }
+        };
+
+        assert_eq!(
+            enumerated(serde_json::json!({ "eventIds": [7], "minAmount": "10" })).await,
+            (
+                Some(true),
+                Some(TOKENS_PER_PAGE - 1),
+                TOKENS_PER_PAGE as usize
+            )
+        );
+        assert_eq!(
+            enumerated(serde_json::json!({ "eventIds": [7], "maxAmount": "3000" })).await,
+            (Some(true), Some(1999), 2000)
+        );
+    }
+}
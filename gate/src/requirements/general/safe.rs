@@ -0,0 +1,302 @@
+use crate::{
+    requirements::{errors::CheckableError, utils::missing_user_addresses, Checkable},
+    types::{
+        Address, Amount, Chain, ChainAddress, EvmChain, NumberId, ReqUserAccess, Requirement,
+        Snapshot, User, U256,
+    },
+};
+use async_trait::async_trait;
+use providers::{
+    evm::{
+        contract::{Token, ViewCall},
+        general::ProviderError,
+    },
+    ProviderContext,
+};
+
+lazy_static::lazy_static! {
+    static ref GET_OWNERS: ViewCall =
+        ViewCall::new("getOwners()", "address[]").expect("This should be fine");
+    static ref GET_THRESHOLD: ViewCall =
+        ViewCall::new("getThreshold()", "uint256").expect("This should be fine");
+}
+
+/// Ownership of a Safe: being one of its owners, or holding enough of the
+/// owner addresses to reach its threshold alone when `threshold` is set.
+pub struct SafeRequirement {
+    id: NumberId,
+    chain: EvmChain,
+    safe: Address,
+    threshold: bool,
+    snapshot: Option<Snapshot>,
+}
+
+impl SafeRequirement {
+    async fn owners_and_threshold(
+        &self,
+        ctx: &ProviderContext,
+    ) -> Result<(Vec<Address>, U256), String> {
+        let Some(caller) = ctx.caller(self.chain) else {
+            return Err(CheckableError::NoSuchChain(format!("{:?}", self.chain)).to_string());
+        };
+
+        let calls = vec![
+            GET_OWNERS.encode(&[]).map_err(|e| e.to_string())?,
+            GET_THRESHOLD.encode(&[]).map_err(|e| e.to_string())?,
+        ];
+        let mut results = caller
+            .call(self.safe, calls, self.snapshot)
+            .await
+            .into_iter();
+
+        let mut decode = |call: &ViewCall| {
+            results
+                .next()
+                .ok_or_else(|| ProviderError::Other("Missing return data".into()))
+                .and_then(|data| call.decode(&data?))
+                .map_err(|e| e.to_string())
+        };
+
+        let owners = match decode(&GET_OWNERS)? {
+            Token::Array(owners) => owners.into_iter().filter_map(Token::into_address).collect(),
+            _ => return Err("Invalid owners of Safe".into()),
+        };
+        let threshold = decode(&GET_THRESHOLD)?
+            .into_uint()
+            .ok_or("Invalid threshold of Safe")?;
+
+        Ok((owners, threshold))
+    }
+}
+
+#[async_trait]
+impl Checkable for SafeRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        if users.iter().all(|u| u.evm_addresses().next().is_none()) {
+            return missing_user_addresses(self.id, users);
+        }
+
+        let (owners, threshold) = match self.owners_and_threshold(ctx).await {
+            Ok(result) => result,
+            Err(e) => {
+                return users
+                    .iter()
+                    .map(|u| ReqUserAccess {
+                        requirement_id: self.id,
+                        user_id: u.id,
+                        access: None,
+                        amount: None,
+                        usd_amount: None,
+                        warning: None,
+                        error: Some(e.clone()),
+                    })
+                    .collect()
+            }
+        };
+
+        let required = if self.threshold {
+            threshold.max(U256::one())
+        } else {
+            U256::one()
+        };
+
+        users
+            .iter()
+            .map(|u| {
+                let owned = u.evm_addresses().filter(|a| owners.contains(a)).count();
+
+                ReqUserAccess {
+                    requirement_id: self.id,
+                    user_id: u.id,
+                    access: Some(U256::from(owned) >= required),
+                    amount: Some(Amount::new(U256::from(owned), 0)),
+                    usd_amount: None,
+                    warning: None,
+                    error: None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&Requirement> for SafeRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let Some(chain) = req.chain.as_ref().and_then(Chain::evm) else {
+            return Err(CheckableError::MissingField("chain".into()));
+        };
+        let Some(safe) = req.address.as_ref().and_then(ChainAddress::evm) else {
+            return Err(CheckableError::MissingTokenAddress(req.id.to_string()));
+        };
+
+        Ok(SafeRequirement {
+            id: req.id,
+            chain,
+            safe,
+            threshold: req
+                .data
+                .as_ref()
+                .and_then(|data| data.threshold)
+                .unwrap_or_default(),
+            snapshot: req.snapshot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        address,
+        types::{Address, Requirement, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{contract::ContractCaller, general::ProviderError, safe::SafeQuerier},
+        EvmChain, MemoryQuerier, ProviderContext, Snapshot,
+    };
+    use std::sync::Arc;
+
+    const SAFE: &str = "0x849D52316331967b6fF1198e5E32A0eB168D039d";
+    const TOKEN: &str = "0x3C65D35A8190294d39013287B246117eBf6615Bd";
+    const OWNERS: [&str; 3] = [
+        "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE",
+        "0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3",
+        "0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503",
+    ];
+
+    // A 2 out of 3 Safe answering `getOwners` and `getThreshold` in order
+    struct StaticSafe;
+
+    fn word(bytes: &[u8]) -> Vec<u8> {
+        let mut word = vec![0u8; 32 - bytes.len()];
+        word.extend_from_slice(bytes);
+        word
+    }
+
+    #[async_trait]
+    impl ContractCaller for StaticSafe {
+        async fn call(
+            &self,
+            _contract: Address,
+            _calls: Vec<Vec<u8>>,
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Vec<u8>, ProviderError>> {
+            // Offset and length of the array followed by its items
+            let owners = [word(&[32]), word(&[OWNERS.len() as u8])]
+                .into_iter()
+                .chain(OWNERS.iter().map(|owner| word(address!(owner).as_bytes())))
+                .collect::<Vec<_>>()
+                .concat();
+
+            vec![Ok(owners), Ok(word(&[2]))]
+        }
+    }
+
+    #[async_trait]
+    impl SafeQuerier for StaticSafe {
+        async fn get_safes(&self, owner: Address) -> Result<Vec<Address>, ProviderError> {
+            Ok(match OWNERS.iter().any(|o| address!(o) == owner) {
+                true => vec![address!(SAFE)],
+                false => vec![],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn safe_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(OWNERS[0]).into(), address!(OWNERS[1]).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!(OWNERS[2]).into()],
+                platform_users: None,
+            },
+            User {
+                id: 2,
+                addresses: vec![address!("0x0000000000000000000000000000000000000001").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new()
+            .with_querier(
+                EvmChain::Ethereum,
+                Arc::new(MemoryQuerier::new().with_fungible_balance(
+                    address!(TOKEN),
+                    address!(SAFE),
+                    "1000".parse().unwrap(),
+                )),
+            )
+            .with_caller(EvmChain::Ethereum, Arc::new(StaticSafe))
+            .with_safes(EvmChain::Ethereum, Arc::new(StaticSafe));
+
+        let check = |requirement: serde_json::Value| {
+            let ctx = ctx.clone();
+            let users = users.clone();
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+                let mut accesses = vec![None; users.len()];
+
+                // Users have access through any of their addresses
+                for access in requirement
+                    .inner(&ctx)
+                    .await
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                {
+                    if let Some(a) = access.access {
+                        *accesses[access.user_id as usize].get_or_insert(false) |= a;
+                    }
+                }
+
+                accesses
+            }
+        };
+
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 0,
+                "type": "SAFE",
+                "chain": "ETHEREUM",
+                "address": SAFE,
+            }))
+            .await,
+            vec![Some(true), Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 1,
+                "type": "SAFE",
+                "chain": "ETHEREUM",
+                "address": SAFE,
+                "data": { "threshold": true },
+            }))
+            .await,
+            vec![Some(true), Some(false), Some(false)]
+        );
+
+        let erc20 = |include_safes: bool| {
+            serde_json::json!({
+                "id": 2,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "minAmount": "1", "includeSafes": include_safes },
+            })
+        };
+        assert_eq!(
+            check(erc20(false)).await,
+            vec![Some(false), Some(false), Some(false)]
+        );
+        assert_eq!(
+            check(erc20(true)).await,
+            vec![Some(true), Some(true), Some(false)]
+        );
+    }
+}
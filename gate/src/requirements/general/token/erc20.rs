@@ -1,81 +1,75 @@
 use crate::{
-    requirements::{errors::CheckableError, utils::check_if_in_range, Checkable},
-    types::{Address, AmountLimits, EvmChain, NumberId, ReqUserAccess, Requirement, User},
+    requirements::{
+        errors::CheckableError,
+        utils::{
+            balance_accesses, error_for_users, usd_accesses, usd_price, user_addresses,
+            with_holders, Holders,
+        },
+        Checkable,
+    },
+    types::{
+        Address, AmountLimits, Chain, ChainAddress, EvmChain, NumberId, ReqUserAccess, Requirement,
+        Snapshot, User,
+    },
 };
 use async_trait::async_trait;
-use providers::{evm::general::PROVIDERS, BalanceQuerier};
+use providers::ProviderContext;
 
 pub struct Erc20Requirement {
     id: NumberId,
     address: Address,
     data: Option<AmountLimits>,
+    usd: Option<AmountLimits>,
     chain: EvmChain,
+    snapshot: Option<Snapshot>,
+    holders: Holders,
 }
 
 #[async_trait]
 impl Checkable for Erc20Requirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
-        let user_addresses: Vec<Address> = users
-            .iter()
-            .flat_map(|u| u.addresses.iter().cloned())
-            .collect();
-
-        if user_addresses.is_empty() {
-            return users
-                .iter()
-                .map(|u| ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id: u.id,
-                    access: None,
-                    amount: None,
-                    warning: None,
-                    error: Some(CheckableError::MissingUserAddress(u.id.to_string()).to_string()),
-                })
-                .collect();
-        }
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (users, failed) =
+            match with_holders(ctx, self.id, self.chain, self.address, users, self.holders).await {
+                Ok(result) => result,
+                Err(e) => return error_for_users(self.id, users, e),
+            };
 
-        let provider = PROVIDERS
-            .get(&(self.chain as u8))
-            .expect("This should be fine");
+        let (user_ids, user_addresses) = match user_addresses(self.id, &users, User::evm_addresses)
+        {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
 
-        provider
-            .get_fungible_balance(self.address, &user_addresses)
-            .await
-            .iter()
-            .enumerate()
-            .map(|(idx, b)| {
-                let mut error = None;
-                let mut amount = None;
-
-                match b {
-                    Ok(v) => amount = Some(*v),
-                    Err(e) => error = Some(e.to_string()),
-                };
-
-                let user_id = users
-                    .iter()
-                    .find(|u| u.addresses.contains(&user_addresses[idx]))
-                    .unwrap()
-                    .id;
-
-                ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id,
-                    access: if error.is_none() {
-                        Some(check_if_in_range(
-                            amount.expect("This should be fine"),
-                            &self.data,
-                            false,
-                        ))
-                    } else {
-                        None
-                    },
-                    amount: if error.is_none() { amount } else { None },
-                    warning: None,
-                    error,
-                }
-            })
-            .collect()
+        let Some(provider) = ctx.get(self.chain) else {
+            return error_for_users(
+                self.id,
+                &users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let balances = provider
+            .get_fungible_balance(self.address, &user_addresses, self.snapshot)
+            .await;
+
+        // Balances are compared in USD when the requirement has USD limits
+        let mut accesses = match self.usd {
+            Some(_) => match usd_price(ctx, self.chain, self.address).await {
+                Ok(price) => usd_accesses(self.id, &user_ids, balances, price, &self.usd),
+                Err(e) => return error_for_users(self.id, &users, e),
+            },
+            None => balance_accesses(self.id, &user_ids, balances, &self.data),
+        };
+        accesses.extend(failed);
+
+        accesses
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        match self.usd {
+            Some(_) => &self.usd,
+            None => &self.data,
+        }
     }
 }
 
@@ -83,28 +77,23 @@ impl TryFrom<&Requirement> for Erc20Requirement {
     type Error = CheckableError;
 
     fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
-        match req.chain {
-            Some(chain) => {
-                if PROVIDERS.get(&(chain as u8)).is_none() {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::NoSuchChain(format!("{chain:?}")).to_string(),
-                    ));
-                }
+        match req.chain.as_ref().and_then(Chain::evm) {
+            Some(chain) => match req.address.as_ref().and_then(ChainAddress::evm) {
+                Some(address) => {
+                    let res = Erc20Requirement {
+                        id: req.id,
+                        address,
+                        data: AmountLimits::from_req(req)?,
+                        usd: AmountLimits::usd_from_req(req)?,
+                        chain,
+                        snapshot: req.snapshot,
+                        holders: Holders::from_req(req),
+                    };
 
-                match req.address {
-                    Some(address) => {
-                        let res = Erc20Requirement {
-                            id: req.id,
-                            address,
-                            data: AmountLimits::from_req(req),
-                            chain,
-                        };
-
-                        Ok(res)
-                    }
-                    None => Err(CheckableError::MissingTokenAddress(req.id.to_string())),
+                    Ok(res)
                 }
-            }
+                None => Err(CheckableError::MissingTokenAddress(req.id.to_string())),
+            },
             None => Err(CheckableError::MissingField("chain".into())),
         }
     }
@@ -114,36 +103,72 @@ impl TryFrom<&Requirement> for Erc20Requirement {
 mod test {
     use crate::{
         address,
-        requirements::{general::token::erc20::Erc20Requirement, Checkable},
-        types::{AmountLimits, EvmChain, User},
+        requirements::{
+            check_access, general::token::erc20::Erc20Requirement, utils::Holders, Checkable,
+        },
+        types::{Address, Amount, AmountLimits, EvmChain, Requirement, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{general::ProviderError, price::PriceQuerier},
+        MemoryQuerier, ProviderContext,
     };
+    use std::sync::Arc;
+
+    const TOKEN: &str = "0x3C65D35A8190294d39013287B246117eBf6615Bd";
+
+    // The token is worth $2.5 and the native coin $2000
+    struct StaticPrices;
+
+    #[async_trait]
+    impl PriceQuerier for StaticPrices {
+        async fn get_usd_price(&self, token: Address) -> Result<Amount, ProviderError> {
+            match token {
+                token if token == address!(TOKEN) => Ok("2.5".parse().unwrap()),
+                token if token.is_zero() => Ok("2000".parse().unwrap()),
+                _ => Err(ProviderError::Other("No feed".into())),
+            }
+        }
+    }
 
     #[tokio::test]
     async fn erc20_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3")],
+            addresses: vec![address!("0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503")],
+            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
             platform_users: None,
         }];
 
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Goerli,
+            Arc::new(MemoryQuerier::new().with_fungible_balance(
+                address!("0x3C65D35A8190294d39013287B246117eBf6615Bd"),
+                address!("0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3"),
+                "1000".parse().unwrap(),
+            )),
+        );
+
         let req = Erc20Requirement {
             id: 0,
             chain: EvmChain::Goerli,
+            snapshot: None,
+            holders: Holders::default(),
+            usd: None,
             address: address!("0x3C65D35A8190294d39013287B246117eBf6615Bd"),
             data: Some(AmountLimits {
-                min_amount: Some(420.69),
+                min_amount: Some("420.69".parse().unwrap()),
                 max_amount: None,
             }),
         };
 
         assert_eq!(
-            req.check(&users_1)
+            req.check(&ctx, &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -151,7 +176,7 @@ mod test {
             vec![true]
         );
         assert_ne!(
-            req.check(&users_2)
+            req.check(&ctx, &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -159,4 +184,189 @@ mod test {
             vec![true]
         );
     }
+
+    #[tokio::test]
+    async fn usd_check() {
+        let holder = address!("0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3");
+        let users = vec![User {
+            id: 0,
+            addresses: vec![holder.into()],
+            platform_users: None,
+        }];
+        let ctx = ProviderContext::new()
+            .with_querier(
+                EvmChain::Ethereum,
+                Arc::new(
+                    MemoryQuerier::new()
+                        .with_fungible_balance(address!(TOKEN), holder, "200".parse().unwrap())
+                        .with_native_balance(holder, "0.2".parse().unwrap()),
+                ),
+            )
+            .with_prices(EvmChain::Ethereum, Arc::new(StaticPrices));
+
+        let check = |requirement: serde_json::Value| {
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+                let accesses = requirement
+                    .inner(&ctx)
+                    .await
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await;
+
+                (
+                    accesses[0].access,
+                    accesses[0].amount.map(|a| a.to_string()),
+                    accesses[0].usd_amount.map(|a| a.to_string()),
+                )
+            }
+        };
+
+        // $500 worth of tokens and $400 worth of the native coin, the amounts
+        // staying the balances
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 0,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "usdMinAmount": "500" },
+            }))
+            .await,
+            (Some(true), Some("200".into()), Some("500".into()))
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 1,
+                "type": "COIN",
+                "chain": "ETHEREUM",
+                "data": { "usdMinAmount": "500" },
+            }))
+            .await,
+            (Some(false), Some("0.2".into()), Some("400".into()))
+        );
+        assert_eq!(
+            check(serde_json::json!({
+                "id": 2,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": "0x0000000000000000000000000000000000000001",
+                "data": { "usdMaxAmount": "500" },
+            }))
+            .await,
+            (None, None, None)
+        );
+
+        // The USD values of the addresses of a user are summed
+        let other = address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503");
+        let ctx = ctx.clone().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(
+                MemoryQuerier::new()
+                    .with_fungible_balance(address!(TOKEN), holder, "200".parse().unwrap())
+                    .with_fungible_balance(address!(TOKEN), other, "100".parse().unwrap()),
+            ),
+        );
+        let users = vec![User {
+            id: 0,
+            addresses: vec![holder.into(), other.into()],
+            platform_users: None,
+        }];
+        let summed = |aggregation: &str| {
+            let requirement: Requirement = serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "usdMinAmount": "600", "aggregation": aggregation },
+            }))
+            .unwrap();
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                let access = check_access(&ctx, &users, &[requirement], "0", true)
+                    .await
+                    .accesses
+                    .remove(0);
+                let detailed = access.detailed.unwrap().remove(0);
+
+                (
+                    access.access,
+                    detailed.amount.map(|a| a.to_string()),
+                    detailed.usd_amount.map(|a| a.to_string()),
+                )
+            }
+        };
+
+        assert_eq!(
+            summed("SUM").await,
+            (Some(true), Some("300".into()), Some("750".into()))
+        );
+        assert_eq!(
+            summed("ANY").await,
+            (Some(false), Some("300".into()), Some("750".into()))
+        );
+
+        // USD limits replace the token amount limits and use current prices
+        let invalid = |requirement: serde_json::Value| {
+            let ctx = ctx.clone();
+
+            async move {
+                let requirement: Requirement = serde_json::from_value(requirement).unwrap();
+
+                requirement.inner(&ctx).await.err().map(|e| e.to_string())
+            }
+        };
+
+        assert_eq!(
+            invalid(serde_json::json!({
+                "id": 3,
+                "type": "COIN",
+                "chain": "ETHEREUM",
+                "data": { "minAmount": "0.1", "usdMinAmount": "500" },
+            }))
+            .await
+            .as_deref(),
+            Some("Invalid field `minAmount`: can't be combined with USD limits")
+        );
+        assert_eq!(
+            invalid(serde_json::json!({
+                "id": 4,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "usdMinAmount": "500" },
+                "snapshot": { "block": 100 },
+            }))
+            .await
+            .as_deref(),
+            Some("Invalid field `snapshot`: USD limits are checked with current prices")
+        );
+        assert_eq!(
+            invalid(serde_json::json!({
+                "id": 5,
+                "type": "ERC20",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "usdMinAmount": "five hundred" },
+            }))
+            .await
+            .as_deref(),
+            Some("Invalid field `usdMinAmount`: Invalid amount `five hundred`")
+        );
+        assert_eq!(
+            invalid(serde_json::json!({
+                "id": 6,
+                "type": "ERC721",
+                "chain": "ETHEREUM",
+                "address": TOKEN,
+                "data": { "usdMaxAmount": "500" },
+            }))
+            .await
+            .as_deref(),
+            Some("Invalid field `usdMaxAmount`: USD limits are only supported by COIN and ERC20 requirements")
+        );
+    }
 }
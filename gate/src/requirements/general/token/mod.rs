@@ -1,5 +1,9 @@
 mod erc20;
+mod multichain;
 mod nft;
+mod votes;
 
 pub use erc20::Erc20Requirement;
+pub use multichain::MultichainErc20Requirement;
 pub use nft::*;
+pub use votes::VotingPowerRequirement;
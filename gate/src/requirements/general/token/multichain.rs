@@ -0,0 +1,239 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{check_if_in_range, error_for_users, missing_user_addresses},
+        Checkable,
+    },
+    types::{
+        Address, Amount, AmountLimits, ChainToken, NumberId, ReqUserAccess, Requirement, Snapshot,
+        User,
+    },
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use providers::ProviderContext;
+
+/// Combined balance of the users in ERC20 tokens deployed on several chains,
+/// like a stablecoin held across Ethereum and its rollups.
+pub struct MultichainErc20Requirement {
+    id: NumberId,
+    tokens: Vec<ChainToken>,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for MultichainErc20Requirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses): (Vec<NumberId>, Vec<Address>) = users
+            .iter()
+            .flat_map(|u| u.evm_addresses().map(move |address| (u.id, address)))
+            .unzip();
+
+        if user_addresses.is_empty() {
+            return missing_user_addresses(self.id, users);
+        }
+
+        let mut providers = vec![];
+
+        for token in self.tokens.iter() {
+            let Some(provider) = ctx.get(token.chain) else {
+                return error_for_users(
+                    self.id,
+                    users,
+                    CheckableError::NoSuchChain(format!("{:?}", token.chain)),
+                );
+            };
+
+            providers.push(provider);
+        }
+
+        let balances = join_all(self.tokens.iter().zip(providers.iter()).map(
+            |(token, provider)| {
+                provider.get_fungible_balance(token.address, &user_addresses, self.snapshot)
+            },
+        ))
+        .await;
+
+        users
+            .iter()
+            .map(|user| {
+                let mut total = Ok(Amount::default());
+
+                // Balances are normalized by the decimals of each token
+                for balances in balances.iter() {
+                    for (balance, _) in balances
+                        .iter()
+                        .zip(user_ids.iter())
+                        .filter(|(_, &user_id)| user_id == user.id)
+                    {
+                        total = match (total, balance) {
+                            (Ok(total), Ok(amount)) => Ok(total.saturating_add(*amount)),
+                            (Ok(_), Err(e)) => Err(e.to_string()),
+                            (Err(e), _) => Err(e),
+                        };
+                    }
+                }
+
+                match total {
+                    Ok(amount) => ReqUserAccess {
+                        requirement_id: self.id,
+                        user_id: user.id,
+                        access: Some(check_if_in_range(amount, &self.data, false)),
+                        amount: Some(amount),
+                        usd_amount: None,
+                        warning: None,
+                        error: None,
+                    },
+                    Err(e) => ReqUserAccess {
+                        requirement_id: self.id,
+                        user_id: user.id,
+                        access: None,
+                        amount: None,
+                        usd_amount: None,
+                        warning: None,
+                        error: Some(e),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for MultichainErc20Requirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let tokens = req
+            .data
+            .as_ref()
+            .and_then(|data| data.tokens.clone())
+            .filter(|tokens| !tokens.is_empty())
+            .ok_or_else(|| CheckableError::MissingField("tokens".into()))?;
+
+        // Block numbers differ between chains, timestamps are resolved to the
+        // blocks of each chain
+        if let Some(Snapshot::Block(_)) = req.snapshot {
+            return Err(CheckableError::InvalidField(
+                "snapshot".into(),
+                "only timestamps apply to every chain".into(),
+            ));
+        }
+
+        Ok(MultichainErc20Requirement {
+            id: req.id,
+            tokens,
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        address,
+        types::{EvmChain, Requirement, RequirementType, User},
+    };
+    use providers::{MemoryQuerier, ProviderContext};
+    use std::sync::Arc;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    const USDC_POLYGON: &str = "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359";
+
+    #[tokio::test]
+    async fn multichain_erc20_check() {
+        let holder = address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE");
+        let other = address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503");
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![holder.into(), other.into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x14DDFE8EA7FFc338015627D160ccAf99e8F16Dd3").into()],
+                platform_users: None,
+            },
+        ];
+
+        // 60 USDC on Ethereum split between the addresses and 40.5 on Polygon
+        let ctx = ProviderContext::new()
+            .with_querier(
+                EvmChain::Ethereum,
+                Arc::new(
+                    MemoryQuerier::new()
+                        .with_fungible_balance(address!(USDC), holder, "50".parse().unwrap())
+                        .with_fungible_balance(address!(USDC), other, "10".parse().unwrap()),
+                ),
+            )
+            .with_querier(
+                EvmChain::Polygon,
+                Arc::new(MemoryQuerier::new().with_fungible_balance(
+                    address!(USDC_POLYGON),
+                    holder,
+                    "40.5".parse().unwrap(),
+                )),
+            );
+
+        let requirement = |value: serde_json::Value| -> Requirement {
+            let requirement: Requirement = serde_json::from_value(value).unwrap();
+            assert_eq!(requirement.typ, RequirementType::MultichainErc20);
+
+            requirement
+        };
+        let tokens = serde_json::json!([
+            { "chain": "ETHEREUM", "address": USDC },
+            { "chain": "POLYGON", "address": USDC_POLYGON },
+        ]);
+
+        let accesses = requirement(serde_json::json!({
+            "id": 0,
+            "type": "MULTICHAIN_ERC20",
+            "data": { "tokens": tokens, "minAmount": "100" },
+        }))
+        .inner(&ctx)
+        .await
+        .unwrap()
+        .check(&ctx, &users)
+        .await;
+
+        assert_eq!(
+            accesses
+                .iter()
+                .map(|a| (a.user_id, a.access))
+                .collect::<Vec<_>>(),
+            vec![(0, Some(true)), (1, Some(false))]
+        );
+        assert_eq!(accesses[0].amount, Some("100.5".parse().unwrap()));
+
+        let unsupported = requirement(serde_json::json!({
+            "id": 1,
+            "type": "MULTICHAIN_ERC20",
+            "data": {
+                "tokens": [{ "chain": "ARBITRUM", "address": USDC }],
+                "minAmount": "100",
+            },
+        }))
+        .inner(&ctx)
+        .await
+        .unwrap()
+        .check(&ctx, &users)
+        .await;
+        assert!(unsupported.iter().all(|a| a.error.is_some()));
+
+        assert!(requirement(serde_json::json!({
+            "id": 2,
+            "type": "MULTICHAIN_ERC20",
+            "data": { "tokens": [] },
+        }))
+        .inner(&ctx)
+        .await
+        .is_err());
+    }
+}
@@ -1,83 +1,62 @@
 use crate::{
     requirements::{
-        errors::CheckableError, general::token::nft::NftData, utils::check_if_in_range, Checkable,
+        errors::CheckableError,
+        general::token::nft::NftData,
+        utils::{balance_accesses, error_for_users, user_addresses, with_holders, Holders},
+        Checkable,
+    },
+    types::{
+        Address, AmountLimits, Chain, ChainAddress, EvmChain, NumberId, ReqUserAccess, Requirement,
+        Snapshot, User,
     },
-    types::{Address, AmountLimits, EvmChain, NumberId, ReqUserAccess, Requirement, User},
 };
 use async_trait::async_trait;
-use providers::{evm::general::PROVIDERS, BalanceQuerier};
+use providers::ProviderContext;
 
 pub struct Erc1155Requirement {
     id: NumberId,
     address: Address,
     data: NftData,
     chain: EvmChain,
+    snapshot: Option<Snapshot>,
+    holders: Holders,
 }
 
 #[async_trait]
 impl Checkable for Erc1155Requirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
-        let user_addresses: Vec<Address> = users
-            .iter()
-            .flat_map(|u| u.addresses.iter().cloned())
-            .collect();
-
-        if user_addresses.is_empty() {
-            return users
-                .iter()
-                .map(|u| ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id: u.id,
-                    access: None,
-                    amount: None,
-                    warning: None,
-                    error: Some(CheckableError::MissingUserAddress(u.id.to_string()).to_string()),
-                })
-                .collect();
-        }
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (users, failed) =
+            match with_holders(ctx, self.id, self.chain, self.address, users, self.holders).await {
+                Ok(result) => result,
+                Err(e) => return error_for_users(self.id, users, e),
+            };
+
+        let (user_ids, user_addresses) = match user_addresses(self.id, &users, User::evm_addresses)
+        {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
 
-        let provider = PROVIDERS
-            .get(&(self.chain as u8))
-            .expect("This should be fine");
-
-        provider
-            .get_special_balance(self.address, self.data.id, &user_addresses)
-            .await
-            .iter()
-            .enumerate()
-            .map(|(idx, b)| {
-                let mut error = None;
-                let mut amount = None;
-
-                match b {
-                    Ok(v) => amount = Some(*v),
-                    Err(e) => error = Some(e.to_string()),
-                };
+        let Some(provider) = ctx.get(self.chain) else {
+            return error_for_users(
+                self.id,
+                &users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
 
-                let user_id = users
-                    .iter()
-                    .find(|u| u.addresses.contains(&user_addresses[idx]))
-                    .unwrap()
-                    .id;
-
-                ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id,
-                    access: if error.is_none() {
-                        Some(check_if_in_range(
-                            amount.expect("This should be fine"),
-                            &self.data.limits,
-                            false,
-                        ))
-                    } else {
-                        None
-                    },
-                    amount: if error.is_none() { amount } else { None },
-                    warning: None,
-                    error,
-                }
-            })
-            .collect()
+        let balances = provider
+            .get_special_balance(self.address, self.data.id, &user_addresses, self.snapshot)
+            .await;
+
+        let mut accesses = balance_accesses(self.id, &user_ids, balances, &self.data.limits);
+        accesses.extend(failed);
+
+        accesses
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data.limits
     }
 }
 
@@ -85,36 +64,28 @@ impl TryFrom<&Requirement> for Erc1155Requirement {
     type Error = CheckableError;
 
     fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
-        match req.chain {
+        match req.chain.as_ref().and_then(Chain::evm) {
             Some(chain) => {
-                if PROVIDERS.get(&(chain as u8)).is_none() {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::NoSuchChain(format!("{chain:?}")).to_string(),
-                    ));
-                }
-
                 let Some(data) = &req.data else {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::MissingField("data".into()).to_string(),
-                    ));
+                    return Err(CheckableError::MissingField("data".into()));
                 };
 
                 if data.id.is_none() {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::MissingField("id".into()).to_string(),
-                    ));
+                    return Err(CheckableError::MissingField("id".into()));
                 };
 
-                match req.address {
+                match req.address.as_ref().and_then(ChainAddress::evm) {
                     Some(address) => {
                         let res = Erc1155Requirement {
                             id: req.id,
                             address,
                             data: NftData {
                                 id: data.id,
-                                limits: AmountLimits::from_req(req),
+                                limits: AmountLimits::from_req(req)?,
                             },
                             chain,
+                            snapshot: req.snapshot,
+                            holders: Holders::from_req(req),
                         };
 
                         Ok(res)
@@ -133,51 +104,63 @@ mod test {
         address,
         requirements::{
             general::token::nft::erc1155::{Erc1155Requirement, NftData},
+            utils::Holders,
             Checkable,
         },
         types::{AmountLimits, EvmChain, User, U256},
     };
+    use providers::{MemoryQuerier, ProviderContext};
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn erc1155_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0x283d678711daa088640c86a1ad3f12c00ec1252e")],
+            addresses: vec![address!("0x283d678711daa088640c86a1ad3f12c00ec1252e").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")],
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
             platform_users: None,
         }];
 
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(MemoryQuerier::new().with_special_balance(
+                address!("0x76be3b62873462d2142405439777e971754e8e77"),
+                Some(U256::from_dec_str("10527").unwrap()),
+                address!("0x283d678711daa088640c86a1ad3f12c00ec1252e"),
+                "5".parse().unwrap(),
+            )),
+        );
+
         let req = Erc1155Requirement {
             id: 0,
             chain: EvmChain::Ethereum,
+            snapshot: None,
+            holders: Holders::default(),
             address: address!("0x76be3b62873462d2142405439777e971754e8e77"),
             data: NftData {
                 id: Some(U256::from_dec_str("10527").unwrap()),
                 limits: Some(AmountLimits {
-                    min_amount: Some(3.0),
+                    min_amount: Some("3".parse().unwrap()),
                     max_amount: None,
                 }),
             },
         };
 
         assert_eq!(
-            req.check(&users_1)
+            req.check(&ctx, &users_1)
                 .await
                 .iter()
-                .map(|a| {
-                    a.amount;
-                    a.access.unwrap_or_default()
-                })
+                .map(|a| a.access.unwrap_or_default())
                 .collect::<Vec<bool>>(),
             vec![true]
         );
         assert_ne!(
-            req.check(&users_2)
+            req.check(&ctx, &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -1,83 +1,62 @@
 use crate::{
     requirements::{
-        errors::CheckableError, general::token::nft::NftData, utils::check_if_in_range, Checkable,
+        errors::CheckableError,
+        general::token::nft::NftData,
+        utils::{balance_accesses, error_for_users, user_addresses, with_holders, Holders},
+        Checkable,
+    },
+    types::{
+        Address, AmountLimits, Chain, ChainAddress, EvmChain, NumberId, ReqUserAccess, Requirement,
+        Snapshot, User,
     },
-    types::{Address, AmountLimits, EvmChain, NumberId, ReqUserAccess, Requirement, User},
 };
 use async_trait::async_trait;
-use providers::{evm::general::PROVIDERS, BalanceQuerier};
+use providers::ProviderContext;
 
 pub struct Erc721Requirement {
     id: NumberId,
     address: Address,
     data: NftData,
     chain: EvmChain,
+    snapshot: Option<Snapshot>,
+    holders: Holders,
 }
 
 #[async_trait]
 impl Checkable for Erc721Requirement {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess> {
-        let user_addresses: Vec<Address> = users
-            .iter()
-            .flat_map(|u| u.addresses.iter().cloned())
-            .collect();
-
-        if user_addresses.is_empty() {
-            return users
-                .iter()
-                .map(|u| ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id: u.id,
-                    access: None,
-                    amount: None,
-                    warning: None,
-                    error: Some(CheckableError::MissingUserAddress(u.id.to_string()).to_string()),
-                })
-                .collect();
-        }
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (users, failed) =
+            match with_holders(ctx, self.id, self.chain, self.address, users, self.holders).await {
+                Ok(result) => result,
+                Err(e) => return error_for_users(self.id, users, e),
+            };
+
+        let (user_ids, user_addresses) = match user_addresses(self.id, &users, User::evm_addresses)
+        {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
 
-        let provider = PROVIDERS
-            .get(&(self.chain as u8))
-            .expect("This should be fine");
-
-        provider
-            .get_non_fungible_balance(self.address, self.data.id, &user_addresses)
-            .await
-            .iter()
-            .enumerate()
-            .map(|(idx, b)| {
-                let mut error = None;
-                let mut amount = None;
-
-                match b {
-                    Ok(v) => amount = Some(*v),
-                    Err(e) => error = Some(e.to_string()),
-                };
+        let Some(provider) = ctx.get(self.chain) else {
+            return error_for_users(
+                self.id,
+                &users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
 
-                let user_id = users
-                    .iter()
-                    .find(|u| u.addresses.contains(&user_addresses[idx]))
-                    .unwrap()
-                    .id;
-
-                ReqUserAccess {
-                    requirement_id: self.id,
-                    user_id,
-                    access: if error.is_none() {
-                        Some(check_if_in_range(
-                            amount.expect("This should be fine"),
-                            &self.data.limits,
-                            false,
-                        ))
-                    } else {
-                        None
-                    },
-                    amount: if error.is_none() { amount } else { None },
-                    warning: None,
-                    error,
-                }
-            })
-            .collect()
+        let balances = provider
+            .get_non_fungible_balance(self.address, self.data.id, &user_addresses, self.snapshot)
+            .await;
+
+        let mut accesses = balance_accesses(self.id, &user_ids, balances, &self.data.limits);
+        accesses.extend(failed);
+
+        accesses
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data.limits
     }
 }
 
@@ -85,30 +64,24 @@ impl TryFrom<&Requirement> for Erc721Requirement {
     type Error = CheckableError;
 
     fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
-        match req.chain {
+        match req.chain.as_ref().and_then(Chain::evm) {
             Some(chain) => {
-                if PROVIDERS.get(&(chain as u8)).is_none() {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::NoSuchChain(format!("{chain:?}")).to_string(),
-                    ));
-                }
-
                 let Some(data) = &req.data else {
-                    return Err(CheckableError::NoSuchChain(
-                        CheckableError::MissingField("data".into()).to_string(),
-                    ));
+                    return Err(CheckableError::MissingField("data".into()));
                 };
 
-                match req.address {
+                match req.address.as_ref().and_then(ChainAddress::evm) {
                     Some(address) => {
                         let res = Erc721Requirement {
                             id: req.id,
                             address,
                             data: NftData {
                                 id: data.id,
-                                limits: AmountLimits::from_req(req),
+                                limits: AmountLimits::from_req(req)?,
                             },
                             chain,
+                            snapshot: req.snapshot,
+                            holders: Holders::from_req(req),
                         };
 
                         Ok(res)
@@ -127,42 +100,62 @@ mod test {
         address,
         requirements::{
             general::token::nft::erc721::{Erc721Requirement, NftData},
+            utils::Holders,
             Checkable,
         },
         types::{EvmChain, User, U256},
     };
+    use providers::{MemoryQuerier, ProviderContext};
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn erc721_check() {
         let users_1 = vec![User {
             id: 0,
-            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")],
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
             platform_users: None,
         }];
 
         let users_2 = vec![User {
             id: 0,
-            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503")],
+            addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
             platform_users: None,
         }];
 
+        let token = address!("0x57f1887a8bf19b14fc0df6fd9b2acc9af147ea85");
+        let token_id = U256::from_dec_str(
+            "61313325075603536901663283754390960556726744542208800735045237225934362163454",
+        )
+        .unwrap();
+        let owner = address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE");
+
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(
+                MemoryQuerier::new()
+                    .with_non_fungible_balance(token, Some(token_id), owner, "1".parse().unwrap())
+                    .with_non_fungible_balance(token, None, owner, "2".parse().unwrap()),
+            ),
+        );
+
         let req1 = Erc721Requirement {
             id: 0,
             chain: EvmChain::Ethereum,
-            address: address!("0x57f1887a8bf19b14fc0df6fd9b2acc9af147ea85"),
+            snapshot: None,
+            holders: Holders::default(),
+            address: token,
             data: NftData {
-                id: Some(U256::from_dec_str(
-                    "61313325075603536901663283754390960556726744542208800735045237225934362163454",
-                )
-                .unwrap()),
-                limits: None
+                id: Some(token_id),
+                limits: None,
             },
         };
 
         let req2 = Erc721Requirement {
             id: 0,
             chain: EvmChain::Ethereum,
-            address: address!("0x57f1887a8bf19b14fc0df6fd9b2acc9af147ea85"),
+            snapshot: None,
+            holders: Holders::default(),
+            address: token,
             data: NftData {
                 id: None,
                 limits: None,
@@ -170,7 +163,7 @@ mod test {
         };
 
         assert_eq!(
-            req1.check(&users_1)
+            req1.check(&ctx, &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -178,7 +171,7 @@ mod test {
             vec![true]
         );
         assert_ne!(
-            req1.check(&users_2)
+            req1.check(&ctx, &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -187,7 +180,7 @@ mod test {
         );
 
         assert_eq!(
-            req2.check(&users_1)
+            req2.check(&ctx, &users_1)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -195,7 +188,7 @@ mod test {
             vec![true]
         );
         assert_ne!(
-            req2.check(&users_2)
+            req2.check(&ctx, &users_2)
                 .await
                 .iter()
                 .map(|a| a.access.unwrap_or_default())
@@ -0,0 +1,387 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        Address, Amount, AmountLimits, Chain, ChainAddress, EvmChain, NumberId, ReqUserAccess,
+        Requirement, Snapshot, User,
+    },
+};
+use async_trait::async_trait;
+use providers::{
+    evm::{
+        contract::{ContractCaller, Token, ViewCall},
+        general::ProviderError,
+        multicall,
+    },
+    ProviderContext,
+};
+
+lazy_static::lazy_static! {
+    // OpenZeppelin ERC20Votes
+    static ref GET_VOTES: ViewCall =
+        ViewCall::new("getVotes(address)", "uint256").expect("This should be fine");
+    static ref GET_PAST_VOTES: ViewCall =
+        ViewCall::new("getPastVotes(address,uint256)", "uint256").expect("This should be fine");
+    // ERC-6372
+    static ref CLOCK_MODE: ViewCall =
+        ViewCall::new("CLOCK_MODE()", "string").expect("This should be fine");
+    // Compound COMP and its forks
+    static ref GET_CURRENT_VOTES: ViewCall =
+        ViewCall::new("getCurrentVotes(address)", "uint96").expect("This should be fine");
+    static ref GET_PRIOR_VOTES: ViewCall =
+        ViewCall::new("getPriorVotes(address,uint256)", "uint96").expect("This should be fine");
+}
+
+/// Voting power of the users in a governance token, including the votes
+/// delegated to them.
+pub struct VotingPowerRequirement {
+    id: NumberId,
+    address: Address,
+    data: Option<AmountLimits>,
+    chain: EvmChain,
+    snapshot: Option<Snapshot>,
+}
+
+/// Unit of the timepoints of the checkpoints of a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Clock {
+    BlockNumber,
+    Timestamp,
+}
+
+impl VotingPowerRequirement {
+    // Tokens predating ERC-6372 don't have `CLOCK_MODE`, their checkpoints
+    // are by block number
+    async fn clock(
+        &self,
+        caller: &(dyn ContractCaller + Send + Sync),
+    ) -> Result<Clock, ProviderError> {
+        let mode = caller
+            .call(
+                self.address,
+                vec![CLOCK_MODE.encode(&[]).expect("This should be fine")],
+                None,
+            )
+            .await
+            .pop()
+            .ok_or_else(|| ProviderError::Other("Missing return data".into()))
+            .and_then(|data| CLOCK_MODE.decode(&data?));
+
+        match mode {
+            Ok(Token::String(mode)) if mode.split('&').any(|p| p == "mode=timestamp") => {
+                Ok(Clock::Timestamp)
+            }
+            Ok(_) => Ok(Clock::BlockNumber),
+            Err(e) if multicall::is_call_failure(&e) => Ok(Clock::BlockNumber),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Votes at a snapshot in the unit of the clock of the token are read from
+    // its checkpoints, which doesn't need an archive node, other snapshots
+    // from the state at the snapshot
+    async fn votes(
+        &self,
+        caller: &(dyn ContractCaller + Send + Sync),
+        current: &ViewCall,
+        past: &ViewCall,
+        clock: Clock,
+        user_addresses: &[Address],
+    ) -> Vec<Result<Token, ProviderError>> {
+        let (call, snapshot, timepoint) = match (self.snapshot, clock) {
+            (Some(Snapshot::Block(timepoint)), Clock::BlockNumber)
+            | (Some(Snapshot::Timestamp(timepoint)), Clock::Timestamp) => {
+                (past, None, Some(timepoint))
+            }
+            (snapshot, _) => (current, snapshot, None),
+        };
+
+        let calls = user_addresses
+            .iter()
+            .map(|address| {
+                let mut args = vec![format!("{address:#x}")];
+
+                if let Some(timepoint) = timepoint {
+                    args.push(timepoint.to_string());
+                }
+
+                call.encode(&args)
+            })
+            .collect::<Result<Vec<_>, _>>();
+
+        let calls = match calls {
+            Ok(calls) => calls,
+            Err(e) => {
+                return user_addresses
+                    .iter()
+                    .map(|_| Err(ProviderError::Other(e.to_string())))
+                    .collect()
+            }
+        };
+
+        caller
+            .call(self.address, calls, snapshot)
+            .await
+            .into_iter()
+            .map(|data| call.decode(&data?))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Checkable for VotingPowerRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) = match user_addresses(self.id, users, User::evm_addresses) {
+            Ok(result) => result,
+            Err(accesses) => return accesses,
+        };
+
+        let Some(caller) = ctx.caller(self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(format!("{:?}", self.chain)),
+            );
+        };
+
+        let clock = async {
+            match self.snapshot {
+                Some(_) => self.clock(caller.as_ref()).await,
+                None => Ok(Clock::BlockNumber),
+            }
+        };
+
+        let (decimals, clock) = match futures::try_join!(caller.decimals(self.address), clock) {
+            Ok(result) => result,
+            Err(e) => {
+                let balances = user_addresses
+                    .iter()
+                    .map(|_| Err::<Amount, _>(e.to_string()))
+                    .collect();
+
+                return balance_accesses(self.id, &user_ids, balances, &self.data);
+            }
+        };
+
+        let mut votes = self
+            .votes(
+                caller.as_ref(),
+                &GET_VOTES,
+                &GET_PAST_VOTES,
+                clock,
+                &user_addresses,
+            )
+            .await;
+
+        // Tokens without the ERC20Votes functions revert, so the Compound
+        // ones, whose checkpoints are by block number, are tried for those
+        let failed: Vec<usize> = (0..votes.len()).filter(|&i| votes[i].is_err()).collect();
+
+        if !failed.is_empty() {
+            let addresses: Vec<Address> = failed.iter().map(|&i| user_addresses[i]).collect();
+            let compound = self
+                .votes(
+                    caller.as_ref(),
+                    &GET_CURRENT_VOTES,
+                    &GET_PRIOR_VOTES,
+                    Clock::BlockNumber,
+                    &addresses,
+                )
+                .await;
+
+            for (i, result) in failed.into_iter().zip(compound) {
+                if result.is_ok() {
+                    votes[i] = result;
+                }
+            }
+        }
+
+        let balances = votes
+            .into_iter()
+            .map(|token| match token?.into_uint() {
+                Some(votes) => Ok(Amount::new(votes, decimals)),
+                None => Err(ProviderError::Other("Invalid votes".into())),
+            })
+            .collect();
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for VotingPowerRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        let Some(chain) = req.chain.as_ref().and_then(Chain::evm) else {
+            return Err(CheckableError::MissingField("chain".into()));
+        };
+        let Some(address) = req.address.as_ref().and_then(ChainAddress::evm) else {
+            return Err(CheckableError::MissingTokenAddress(req.id.to_string()));
+        };
+
+        Ok(VotingPowerRequirement {
+            id: req.id,
+            address,
+            data: AmountLimits::from_req(req)?,
+            chain,
+            snapshot: req.snapshot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{VotingPowerRequirement, CLOCK_MODE, GET_CURRENT_VOTES, GET_PAST_VOTES, GET_VOTES};
+    use crate::{
+        address,
+        requirements::Checkable,
+        types::{Address, Requirement, User, U256},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{contract::ContractCaller, general::ProviderError},
+        EvmChain, ProviderContext, Snapshot,
+    };
+    use std::sync::Arc;
+
+    const VOTER: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+    const ERC20_VOTES: &str = "0xc00e94Cb662C3520282E6f5717214004A7f26888";
+    const COMPOUND: &str = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984";
+    const TIMESTAMP: &str = "0x9f8F72aA9304c8B593d555F12eF6589cC3A579A2";
+    const SNAPSHOT_TIME: u64 = 1_700_000_000;
+
+    // The voter has 5 votes now and had 1 in the past, in both kinds of
+    // tokens, other addresses have none. The ERC20Votes functions revert
+    // on the Compound-style token. The voter has 1 vote now in the token
+    // with a timestamp clock and had 3 at the only checkpoint of the token.
+    struct StaticGovernor;
+
+    #[async_trait]
+    impl ContractCaller for StaticGovernor {
+        async fn call(
+            &self,
+            contract: Address,
+            calls: Vec<Vec<u8>>,
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Vec<u8>, ProviderError>> {
+            let selector =
+                |call: &super::ViewCall, args: &[String]| call.encode(args).unwrap()[..4].to_vec();
+            let voter = [&[0u8; 12], address!(VOTER).as_bytes()].concat();
+            let word = |value: U256| {
+                let mut word = [0u8; 32];
+                value.to_big_endian(&mut word);
+                Ok(word.to_vec())
+            };
+            let votes = |data: &[u8], amount: u64| match data[4..36] == voter {
+                true => word(U256::exp10(18) * amount),
+                false => word(U256::zero()),
+            };
+            // ABI encoding of a string shorter than a word
+            let string = |value: &str| {
+                let mut data = vec![0u8; 64];
+                data[31] = 32;
+                data[63] = value.len() as u8;
+                data.extend(value.bytes().chain(std::iter::repeat(0)).take(32));
+                Ok(data)
+            };
+            let compound = contract == address!(COMPOUND);
+            let timestamp = contract == address!(TIMESTAMP);
+            let zero = vec![format!("{:#x}", Address::zero())];
+            let past = vec![zero[0].clone(), "0".into()];
+
+            calls
+                .into_iter()
+                .map(|data| match data[..4].to_vec() {
+                    s if s == selector(&CLOCK_MODE, &[]) && timestamp => string("mode=timestamp"),
+                    s if s == selector(&GET_PAST_VOTES, &past) && timestamp => {
+                        match U256::from_big_endian(&data[36..68]) == SNAPSHOT_TIME.into() {
+                            true => votes(&data, 3),
+                            false => Err(ProviderError::Other("execution reverted".into())),
+                        }
+                    }
+                    s if s == selector(&GET_VOTES, &zero) && timestamp => votes(&data, 1),
+                    s if s == selector(&GET_VOTES, &zero) && !compound => votes(&data, 5),
+                    s if s == selector(&GET_PAST_VOTES, &past) && !compound => votes(&data, 1),
+                    s if s == selector(&GET_CURRENT_VOTES, &zero) && compound => votes(&data, 5),
+                    _ => Err(ProviderError::Other("execution reverted".into())),
+                })
+                .collect()
+        }
+
+        async fn decimals(&self, _token: Address) -> Result<u8, ProviderError> {
+            Ok(18)
+        }
+    }
+
+    #[tokio::test]
+    async fn voting_power_check() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(VOTER).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new().with_caller(EvmChain::Ethereum, Arc::new(StaticGovernor));
+
+        let check = |token: &str, snapshot: Option<serde_json::Value>| {
+            let requirement: Requirement = serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": "VOTING_POWER",
+                "chain": "ETHEREUM",
+                "address": token,
+                "data": { "minAmount": "2" },
+                "snapshot": snapshot,
+            }))
+            .unwrap();
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                VotingPowerRequirement::try_from(&requirement)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+                    .into_iter()
+                    .map(|a| a.access)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            check(ERC20_VOTES, None).await,
+            vec![Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(ERC20_VOTES, Some(serde_json::json!({ "block": 100 }))).await,
+            vec![Some(false), Some(false)]
+        );
+        assert_eq!(check(COMPOUND, None).await, vec![Some(true), Some(false)]);
+
+        // Timestamp snapshots of tokens with a timestamp clock are read from
+        // the checkpoints, block ones from the state at the block
+        assert_eq!(
+            check(
+                TIMESTAMP,
+                Some(serde_json::json!({ "timestamp": SNAPSHOT_TIME }))
+            )
+            .await,
+            vec![Some(true), Some(false)]
+        );
+        assert_eq!(
+            check(TIMESTAMP, Some(serde_json::json!({ "block": 100 }))).await,
+            vec![Some(false), Some(false)]
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use crate::types::{
-    Access, CheckAccessResult, DetailedAccess, NumberId, ReqUserAccess, Requirement,
+    Access, AmountLimits, CheckAccessResult, DetailedAccess, NumberId, ReqUserAccess, Requirement,
     RequirementError, User,
 };
 use async_trait::async_trait;
+use providers::{transport::retry::count_retries, ProviderContext};
 use requiem::LogicTree;
 use std::{
     collections::HashMap,
@@ -10,16 +11,28 @@ use std::{
     sync::{Arc, RwLock},
 };
 
+pub mod cosmos;
 pub mod errors;
 pub mod general;
+pub mod solana;
+pub mod substrate;
 mod utils;
 
+use utils::aggregate;
+
 #[async_trait]
 pub trait Checkable {
-    async fn check(&self, users: &[User]) -> Vec<ReqUserAccess>;
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess>;
+
+    /// Limits the amounts of the addresses of a user are checked against
+    /// once summed, the USD ones for requirements with USD limits.
+    fn limits(&self) -> &Option<AmountLimits> {
+        &None
+    }
 }
 
 pub async fn check_access(
+    ctx: &ProviderContext,
     users: &[User],
     requirements: &[Requirement],
     logic: &str,
@@ -32,40 +45,52 @@ pub async fn check_access(
     let error_for_user = Arc::new(RwLock::new(
         HashMap::<NumberId, Vec<RequirementError>>::new(),
     ));
-    let acc_per_req = Arc::new(RwLock::new(Vec::<Vec<ReqUserAccess>>::new()));
     let user_ids = users.iter().map(|user| user.id);
 
-    futures::future::join_all(requirements.iter().map(|req| async {
+    // Accesses of the users in the order of the requirements, as the
+    // terminals of the logic are their indices
+    let acc_per_req = futures::future::join_all(requirements.iter().map(|req| async {
         let req_errors = Arc::clone(&req_errors);
 
-        let accesses = match req.inner() {
-            Ok(checkable) => checkable.check(users).await,
+        let (accesses, checkable) = match req.inner(ctx).await {
+            Ok(checkable) => {
+                let (mut accesses, retries) = count_retries(checkable.check(ctx, users)).await;
+
+                // Retried requests can be shared by several users, so all
+                // of them are warned
+                if retries > 0 {
+                    let warning = format!("Retries of failed requests: {retries}");
+
+                    for access in accesses.iter_mut() {
+                        access.warning.get_or_insert_with(|| warning.clone());
+                    }
+                }
+
+                (accesses, Some(checkable))
+            }
             Err(e) => {
                 req_errors.write().unwrap().push(RequirementError {
                     requirement_id: req.id,
                     msg: e.to_string(),
                 });
 
-                users
+                let accesses = users
                     .iter()
                     .map(|u| ReqUserAccess {
                         requirement_id: req.id,
                         user_id: u.id,
                         access: None,
                         amount: None,
+                        usd_amount: None,
                         warning: None,
                         error: Some(e.to_string()),
                     })
-                    .collect()
+                    .collect();
+
+                (accesses, None)
             }
         };
 
-        if send_details {
-            // Calling unwrap is fine here, read the documentation of the
-            // write function for details.
-            acc_per_req.write().unwrap().push(accesses.clone());
-        }
-
         for a in accesses.iter() {
             if let Some(warning) = &a.warning {
                 let warnings = Arc::clone(&warning_for_user);
@@ -105,6 +130,10 @@ pub async fn check_access(
                     .insert(a.user_id, user_errors.clone());
             }
         }
+
+        let limits = checkable.as_ref().map_or(&None, |c| c.limits());
+
+        aggregate(req, users, accesses, limits)
     }))
     .await;
 
@@ -115,15 +144,12 @@ pub async fn check_access(
     CheckAccessResult {
         accesses: user_ids
             .map(|id| {
-                let acc_per_req = Arc::clone(&acc_per_req);
                 let has_access = match LogicTree::from_str(logic) {
                     Ok(tree) => {
                         let mut terminals = HashMap::new();
                         let mut error = false;
 
                         for (idx, value) in acc_per_req
-                            .read()
-                            .unwrap()
                             .iter()
                             .map(|req_accesses| {
                                 req_accesses
@@ -164,34 +190,19 @@ pub async fn check_access(
                 let errors = error_for_user.read().unwrap().get(&id).cloned();
 
                 let detailed = if send_details {
-                    // Calling unwrap is fine here, read the documentation of
-                    // the lock function for details.
                     let inner = acc_per_req
-                        .read()
-                        .unwrap()
                         .iter()
                         .map(|reqs| {
-                            let mut filtered = reqs.iter().filter(|user| user.user_id == id);
-
-                            let access = filtered
-                                .clone()
-                                .map(|f| f.access.unwrap_or_default())
-                                .reduce(|a, b| a || b)
-                                .unwrap_or_default();
-
-                            let amount = filtered
-                                .clone()
-                                .map(|f| f.amount.unwrap_or_default())
-                                .reduce(|a, b| a + b)
-                                .unwrap_or_default();
-
-                            let requirement_id =
-                                filtered.next().expect("This should be fine").requirement_id;
+                            let access = reqs
+                                .iter()
+                                .find(|a| a.user_id == id)
+                                .expect("This should be fine");
 
                             DetailedAccess {
-                                requirement_id,
-                                access: Some(access),
-                                amount: Some(amount),
+                                requirement_id: access.requirement_id,
+                                access: Some(access.access.unwrap_or_default()),
+                                amount: Some(access.amount.unwrap_or_default()),
+                                usd_amount: access.usd_amount,
                             }
                         })
                         .collect();
@@ -219,3 +230,231 @@ pub async fn check_access(
         },
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::check_access;
+    use crate::{
+        address,
+        types::{EvmChain, Requirement, RequirementData, RequirementType, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{general::ProviderError, safe::SafeQuerier},
+        transport::retry::{BackendRetry, ErrorClass, RetryPolicy},
+        Address, MemoryQuerier, ProviderContext,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    // Owns no Safes, but only answers after a failed attempt
+    #[derive(Default)]
+    struct FlakySafes {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SafeQuerier for FlakySafes {
+        async fn get_safes(&self, _: Address) -> Result<Vec<Address>, ProviderError> {
+            let policy = RetryPolicy {
+                max_attempts: 3,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            };
+            let retry = BackendRetry {
+                transport: policy,
+                rate_limited: policy,
+                server_error: policy,
+            };
+
+            retry
+                .run(
+                    "test",
+                    |_| Some(ErrorClass::Transport),
+                    || async {
+                        match self.calls.fetch_add(1, Ordering::SeqCst) {
+                            0 => Err(ProviderError::Other("transient".into())),
+                            _ => Ok(vec![]),
+                        }
+                    },
+                )
+                .await
+                .0
+        }
+    }
+
+    #[tokio::test]
+    async fn check_access_with_context() {
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(MemoryQuerier::new().with_native_balance(
+                address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"),
+                "2".parse().unwrap(),
+            )),
+        );
+
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503").into()],
+                platform_users: None,
+            },
+        ];
+
+        let requirement = |chain: EvmChain| Requirement {
+            id: 0,
+            typ: RequirementType::Coin,
+            address: None,
+            data: Some(RequirementData {
+                id: None,
+                addresses: None,
+                min_amount: Some("1".into()),
+                max_amount: None,
+                denom: None,
+                balance_kind: None,
+                pallet: None,
+                name: None,
+                subdomains: None,
+                function: None,
+                args: None,
+                returns: None,
+                operator: None,
+                value: None,
+                threshold: None,
+                include_safes: None,
+                include_delegations: None,
+                event_ids: None,
+                tokens: None,
+                aggregation: None,
+                usd_min_amount: None,
+                usd_max_amount: None,
+            }),
+            chain: Some(chain.into()),
+            snapshot: None,
+        };
+
+        let result =
+            check_access(&ctx, &users, &[requirement(EvmChain::Ethereum)], "0", true).await;
+
+        assert_eq!(
+            result.accesses.iter().map(|a| a.access).collect::<Vec<_>>(),
+            vec![Some(true), Some(false)]
+        );
+        assert!(result.errors.is_none());
+
+        let result = check_access(&ctx, &users, &[requirement(EvmChain::Polygon)], "0", true).await;
+
+        assert!(result.accesses.iter().all(|a| a.access.is_none()));
+        assert_eq!(
+            result.errors.unwrap()[0].msg,
+            "Chain `Polygon` is not supported"
+        );
+    }
+
+    #[tokio::test]
+    async fn aggregation() {
+        let (first, second) = (
+            address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"),
+            address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503"),
+        );
+        let ctx = ProviderContext::new().with_querier(
+            EvmChain::Ethereum,
+            Arc::new(
+                MemoryQuerier::new()
+                    .with_native_balance(first, "0.6".parse().unwrap())
+                    .with_native_balance(second, "0.5".parse().unwrap()),
+            ),
+        );
+        let users = vec![User {
+            id: 0,
+            addresses: vec![first.into(), second.into()],
+            platform_users: None,
+        }];
+
+        let requirement = |id: u64, aggregation: &str| -> Requirement {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "type": "COIN",
+                "chain": "ETHEREUM",
+                "data": { "minAmount": "1", "aggregation": aggregation },
+            }))
+            .unwrap()
+        };
+        let requirements = [requirement(0, "ANY"), requirement(1, "SUM")];
+
+        let result = check_access(&ctx, &users, &requirements, "0", false).await;
+        assert_eq!(result.accesses[0].access, Some(false));
+
+        let result = check_access(&ctx, &users, &requirements, "1", false).await;
+        assert_eq!(result.accesses[0].access, Some(true));
+
+        let result = check_access(&ctx, &users, &requirements, "0 OR 1", true).await;
+        assert_eq!(result.accesses[0].access, Some(true));
+        assert_eq!(
+            result.accesses[0]
+                .detailed
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|d| (d.requirement_id, d.access, d.amount.unwrap().to_string()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, Some(false), "1.1".to_string()),
+                (1, Some(true), "1.1".to_string())
+            ]
+        );
+
+        // Only balances can be summed
+        for typ in ["SAFE", "CONTRACT", "WALLET_AGE"] {
+            let requirement: Requirement = serde_json::from_value(serde_json::json!({
+                "id": 0,
+                "type": typ,
+                "chain": "ETHEREUM",
+                "data": { "minAmount": "1", "aggregation": "SUM" },
+            }))
+            .unwrap();
+
+            let result = check_access(&ctx, &users, &[requirement], "0", true).await;
+
+            assert_eq!(result.accesses[0].access, None);
+            assert_eq!(
+                result.errors.unwrap()[0].msg,
+                "Invalid field `aggregation`: SUM is only supported by balance requirements"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_in_warnings() {
+        let ctx = ProviderContext::new()
+            .with_querier(EvmChain::Ethereum, Arc::new(MemoryQuerier::new()))
+            .with_safes(EvmChain::Ethereum, Arc::new(FlakySafes::default()));
+        let users = vec![User {
+            id: 0,
+            addresses: vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into()],
+            platform_users: None,
+        }];
+        let requirement: Requirement = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "type": "COIN",
+            "chain": "ETHEREUM",
+            "data": { "includeSafes": true },
+        }))
+        .unwrap();
+
+        let result = check_access(&ctx, &users, &[requirement], "0", false).await;
+
+        assert_eq!(result.accesses[0].access, Some(false));
+        assert_eq!(
+            result.accesses[0].warnings.as_ref().unwrap()[0].msg,
+            "Retries of failed requests: 1"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+mod nft;
+mod sol;
+mod spl;
+
+pub use nft::SolanaNftRequirement;
+pub use sol::SolRequirement;
+pub use spl::SplRequirement;
+
+#[cfg(test)]
+mod test {
+    use super::{SolRequirement, SplRequirement};
+    use crate::{
+        address,
+        requirements::Checkable,
+        types::{Amount, Requirement, RequirementType, SolanaAddress, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        solana::SolanaError, solana_address, BalanceQuerier, ProviderContext, Snapshot,
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    // Querier answering every kind of balance from the same map
+    struct StaticQuerier(HashMap<SolanaAddress, Amount>);
+
+    impl StaticQuerier {
+        fn balances(&self, user_addresses: &[SolanaAddress]) -> Vec<Result<Amount, SolanaError>> {
+            user_addresses
+                .iter()
+                .map(|ua| Ok(self.0.get(ua).copied().unwrap_or_default()))
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl BalanceQuerier for StaticQuerier {
+        type Address = SolanaAddress;
+        type Id = SolanaAddress;
+        type Balance = Amount;
+        type Chain = ();
+        type Error = SolanaError;
+
+        async fn get_native_balance(
+            &self,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(user_addresses)
+        }
+
+        async fn get_fungible_balance(
+            &self,
+            _token_address: Self::Address,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(user_addresses)
+        }
+
+        async fn get_non_fungible_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(user_addresses)
+        }
+
+        async fn get_special_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.balances(user_addresses)
+        }
+    }
+
+    #[tokio::test]
+    async fn solana_check() {
+        let holder = solana_address!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM");
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![
+                    address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE").into(),
+                    holder.into(),
+                ],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![
+                    solana_address!("4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T").into(),
+                ],
+                platform_users: None,
+            },
+        ];
+
+        let ctx = ProviderContext::new().with_solana(Arc::new(StaticQuerier(HashMap::from([(
+            holder,
+            "2.5".parse().unwrap(),
+        )]))));
+
+        let requirement: Requirement = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "type": "SOL",
+            "data": { "minAmount": "1" },
+        }))
+        .unwrap();
+
+        assert_eq!(requirement.typ, RequirementType::Sol);
+
+        let accesses = SolRequirement::try_from(&requirement)
+            .unwrap()
+            .check(&ctx, &users)
+            .await;
+
+        assert_eq!(
+            accesses
+                .iter()
+                .map(|a| (a.user_id, a.access))
+                .collect::<Vec<_>>(),
+            vec![(0, Some(true)), (1, Some(false))]
+        );
+
+        let requirement: Requirement = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "type": "SPL",
+            "address": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "data": { "minAmount": "3" },
+        }))
+        .unwrap();
+        let spl = SplRequirement::try_from(&requirement).unwrap();
+
+        assert!(spl
+            .check(&ctx, &users)
+            .await
+            .iter()
+            .all(|a| a.access == Some(false)));
+        assert!(spl
+            .check(&ProviderContext::new(), &users)
+            .await
+            .iter()
+            .all(|a| a.error.as_deref() == Some("Chain `Solana` is not supported")));
+    }
+}
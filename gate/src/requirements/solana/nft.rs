@@ -0,0 +1,62 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        AmountLimits, ChainAddress, NumberId, ReqUserAccess, Requirement, Snapshot, SolanaAddress,
+        User,
+    },
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+/// Ownership of NFTs of a verified Metaplex collection.
+pub struct SolanaNftRequirement {
+    id: NumberId,
+    collection: SolanaAddress,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SolanaNftRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::solana_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.solana() else {
+            return error_for_users(self.id, users, CheckableError::NoSuchChain("Solana".into()));
+        };
+
+        let balances = provider
+            .get_non_fungible_balance(self.collection, None, &user_addresses, self.snapshot)
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SolanaNftRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        match req.address.as_ref().and_then(ChainAddress::solana) {
+            Some(collection) => Ok(SolanaNftRequirement {
+                id: req.id,
+                collection,
+                data: AmountLimits::from_req(req)?,
+                snapshot: req.snapshot,
+            }),
+            None => Err(CheckableError::MissingTokenAddress(req.id.to_string())),
+        }
+    }
+}
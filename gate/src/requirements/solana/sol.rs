@@ -0,0 +1,53 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{AmountLimits, NumberId, ReqUserAccess, Requirement, Snapshot, User},
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+pub struct SolRequirement {
+    id: NumberId,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SolRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::solana_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.solana() else {
+            return error_for_users(self.id, users, CheckableError::NoSuchChain("Solana".into()));
+        };
+
+        let balances = provider
+            .get_native_balance(&user_addresses, self.snapshot)
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SolRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(SolRequirement {
+            id: req.id,
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
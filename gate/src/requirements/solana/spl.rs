@@ -0,0 +1,61 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{
+        AmountLimits, ChainAddress, NumberId, ReqUserAccess, Requirement, Snapshot, SolanaAddress,
+        User,
+    },
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+pub struct SplRequirement {
+    id: NumberId,
+    mint: SolanaAddress,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SplRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::solana_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.solana() else {
+            return error_for_users(self.id, users, CheckableError::NoSuchChain("Solana".into()));
+        };
+
+        let balances = provider
+            .get_fungible_balance(self.mint, &user_addresses, self.snapshot)
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SplRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        match req.address.as_ref().and_then(ChainAddress::solana) {
+            Some(mint) => Ok(SplRequirement {
+                id: req.id,
+                mint,
+                data: AmountLimits::from_req(req)?,
+                snapshot: req.snapshot,
+            }),
+            None => Err(CheckableError::MissingTokenAddress(req.id.to_string())),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        substrate::{chain_name, pallet_id},
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{AmountLimits, NumberId, ReqUserAccess, Requirement, Snapshot, User},
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+/// Balance of an asset of the `assets` pallet.
+pub struct SubstrateAssetRequirement {
+    id: NumberId,
+    chain: String,
+    asset_id: u32,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SubstrateAssetRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::substrate_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.substrate(&self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(self.chain.clone()),
+            );
+        };
+
+        let balances = provider
+            .get_asset_balance(self.asset_id, &user_addresses, self.snapshot)
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SubstrateAssetRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(SubstrateAssetRequirement {
+            id: req.id,
+            chain: chain_name(req)?,
+            asset_id: pallet_id(req)?,
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
@@ -0,0 +1,187 @@
+mod asset;
+mod native;
+mod nft;
+
+use crate::{
+    requirements::errors::CheckableError,
+    types::{Chain, Requirement, U256},
+};
+
+pub use asset::SubstrateAssetRequirement;
+pub use native::SubstrateRequirement;
+pub use nft::SubstrateNftRequirement;
+
+fn chain_name(req: &Requirement) -> Result<String, CheckableError> {
+    req.chain
+        .as_ref()
+        .and_then(Chain::id)
+        .map(str::to_string)
+        .ok_or_else(|| CheckableError::MissingField("chain".into()))
+}
+
+// Ids of assets and collections are `u32` in the pallets
+fn pallet_id(req: &Requirement) -> Result<u32, CheckableError> {
+    req.data
+        .as_ref()
+        .and_then(|data| data.id)
+        .filter(|id| *id <= U256::from(u32::MAX))
+        .map(|id| id.as_u32())
+        .ok_or_else(|| CheckableError::MissingField("id".into()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SubstrateAssetRequirement, SubstrateNftRequirement, SubstrateRequirement};
+    use crate::{
+        requirements::Checkable,
+        types::{
+            Amount, NativeBalance, NftPallet, ReqUserAccess, Requirement, SubstrateAddress, User,
+        },
+    };
+    use async_trait::async_trait;
+    use providers::{
+        substrate::{SubstrateError, SubstrateQuerier},
+        substrate_address, ProviderContext, Snapshot,
+    };
+    use std::sync::Arc;
+
+    // Querier where every account holds 10 free and 2 reserved units, 3 of
+    // asset 1984 and a single item of collection 7 of the `nfts` pallet
+    struct StaticQuerier;
+
+    fn balances(
+        user_addresses: &[SubstrateAddress],
+        amount: &str,
+    ) -> Vec<Result<Amount, SubstrateError>> {
+        user_addresses
+            .iter()
+            .map(|_| Ok(amount.parse().unwrap()))
+            .collect()
+    }
+
+    #[async_trait]
+    impl SubstrateQuerier for StaticQuerier {
+        async fn get_native_balance(
+            &self,
+            kind: NativeBalance,
+            user_addresses: &[SubstrateAddress],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Amount, SubstrateError>> {
+            let amount = match kind {
+                NativeBalance::Free => "10",
+                NativeBalance::Reserved => "2",
+                NativeBalance::Total => "12",
+            };
+
+            balances(user_addresses, amount)
+        }
+
+        async fn get_asset_balance(
+            &self,
+            asset_id: u32,
+            user_addresses: &[SubstrateAddress],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Amount, SubstrateError>> {
+            balances(user_addresses, if asset_id == 1984 { "3" } else { "0" })
+        }
+
+        async fn get_nft_balance(
+            &self,
+            pallet: NftPallet,
+            collection_id: u32,
+            user_addresses: &[SubstrateAddress],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Amount, SubstrateError>> {
+            let owned = pallet == NftPallet::Nfts && collection_id == 7;
+
+            balances(user_addresses, if owned { "1" } else { "0" })
+        }
+    }
+
+    #[tokio::test]
+    async fn substrate_check() {
+        let users = vec![User {
+            id: 0,
+            addresses: vec![
+                substrate_address!("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5").into(),
+            ],
+            platform_users: None,
+        }];
+        let ctx = ProviderContext::new().with_substrate("polkadot", Arc::new(StaticQuerier));
+
+        let requirement =
+            |value: serde_json::Value| -> Requirement { serde_json::from_value(value).unwrap() };
+        let access = |accesses: Vec<ReqUserAccess>| accesses[0].access;
+
+        let native = requirement(serde_json::json!({
+            "id": 0,
+            "type": "SUBSTRATE",
+            "chain": "polkadot",
+            "data": { "minAmount": "11", "balanceKind": "free" },
+        }));
+        assert_eq!(
+            access(
+                SubstrateRequirement::try_from(&native)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+            ),
+            Some(false)
+        );
+
+        let total = requirement(serde_json::json!({
+            "id": 1,
+            "type": "SUBSTRATE",
+            "chain": "polkadot",
+            "data": { "minAmount": "11" },
+        }));
+        assert_eq!(
+            access(
+                SubstrateRequirement::try_from(&total)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+            ),
+            Some(true)
+        );
+
+        let asset = requirement(serde_json::json!({
+            "id": 2,
+            "type": "SUBSTRATE_ASSET",
+            "chain": "polkadot",
+            "data": { "id": "0x7c0", "minAmount": "3" },
+        }));
+        assert_eq!(
+            access(
+                SubstrateAssetRequirement::try_from(&asset)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+            ),
+            Some(true)
+        );
+
+        let nft = requirement(serde_json::json!({
+            "id": 3,
+            "type": "SUBSTRATE_NFT",
+            "chain": "polkadot",
+            "data": { "id": "0x7", "pallet": "uniques" },
+        }));
+        assert_eq!(
+            access(
+                SubstrateNftRequirement::try_from(&nft)
+                    .unwrap()
+                    .check(&ctx, &users)
+                    .await
+            ),
+            Some(false)
+        );
+
+        let missing_id = requirement(serde_json::json!({
+            "id": 4,
+            "type": "SUBSTRATE_ASSET",
+            "chain": "polkadot",
+        }));
+        assert!(SubstrateAssetRequirement::try_from(&missing_id).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        substrate::chain_name,
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{AmountLimits, NativeBalance, NumberId, ReqUserAccess, Requirement, Snapshot, User},
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+/// Native balance of a Substrate chain, free, reserved or both.
+pub struct SubstrateRequirement {
+    id: NumberId,
+    chain: String,
+    kind: NativeBalance,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SubstrateRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::substrate_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.substrate(&self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(self.chain.clone()),
+            );
+        };
+
+        let balances = provider
+            .get_native_balance(self.kind, &user_addresses, self.snapshot)
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SubstrateRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(SubstrateRequirement {
+            id: req.id,
+            chain: chain_name(req)?,
+            kind: req
+                .data
+                .as_ref()
+                .and_then(|data| data.balance_kind)
+                .unwrap_or_default(),
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
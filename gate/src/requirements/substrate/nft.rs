@@ -0,0 +1,74 @@
+use crate::{
+    requirements::{
+        errors::CheckableError,
+        substrate::{chain_name, pallet_id},
+        utils::{balance_accesses, error_for_users, user_addresses},
+        Checkable,
+    },
+    types::{AmountLimits, NftPallet, NumberId, ReqUserAccess, Requirement, Snapshot, User},
+};
+use async_trait::async_trait;
+use providers::ProviderContext;
+
+/// Ownership of items of a collection of the `nfts` or `uniques` pallet.
+pub struct SubstrateNftRequirement {
+    id: NumberId,
+    chain: String,
+    pallet: NftPallet,
+    collection_id: u32,
+    data: Option<AmountLimits>,
+    snapshot: Option<Snapshot>,
+}
+
+#[async_trait]
+impl Checkable for SubstrateNftRequirement {
+    async fn check(&self, ctx: &ProviderContext, users: &[User]) -> Vec<ReqUserAccess> {
+        let (user_ids, user_addresses) =
+            match user_addresses(self.id, users, User::substrate_addresses) {
+                Ok(result) => result,
+                Err(accesses) => return accesses,
+            };
+
+        let Some(provider) = ctx.substrate(&self.chain) else {
+            return error_for_users(
+                self.id,
+                users,
+                CheckableError::NoSuchChain(self.chain.clone()),
+            );
+        };
+
+        let balances = provider
+            .get_nft_balance(
+                self.pallet,
+                self.collection_id,
+                &user_addresses,
+                self.snapshot,
+            )
+            .await;
+
+        balance_accesses(self.id, &user_ids, balances, &self.data)
+    }
+
+    fn limits(&self) -> &Option<AmountLimits> {
+        &self.data
+    }
+}
+
+impl TryFrom<&Requirement> for SubstrateNftRequirement {
+    type Error = CheckableError;
+
+    fn try_from(req: &Requirement) -> Result<Self, Self::Error> {
+        Ok(SubstrateNftRequirement {
+            id: req.id,
+            chain: chain_name(req)?,
+            pallet: req
+                .data
+                .as_ref()
+                .and_then(|data| data.pallet)
+                .unwrap_or_default(),
+            collection_id: pallet_id(req)?,
+            data: AmountLimits::from_req(req)?,
+            snapshot: req.snapshot,
+        })
+    }
+}
@@ -1,13 +1,337 @@
-use crate::types::{Amount, AmountLimits};
+use crate::{
+    requirements::errors::CheckableError,
+    types::{
+        Address, Aggregation, Amount, AmountLimits, ChainAddress, EvmChain, NumberId,
+        ReqUserAccess, Requirement, User, U256,
+    },
+};
+use futures::future::join_all;
+use providers::ProviderContext;
+use std::{borrow::Cow, fmt::Display, future::Future};
+
+pub fn error_for_users(
+    requirement_id: NumberId,
+    users: &[User],
+    error: CheckableError,
+) -> Vec<ReqUserAccess> {
+    users
+        .iter()
+        .map(|u| ReqUserAccess {
+            requirement_id,
+            user_id: u.id,
+            access: None,
+            amount: None,
+            usd_amount: None,
+            warning: None,
+            error: Some(error.to_string()),
+        })
+        .collect()
+}
+
+/// Addresses besides the linked ones whose holdings count as held by the
+/// users.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Holders {
+    pub safes: bool,
+    pub delegations: bool,
+}
+
+impl Holders {
+    pub fn from_req(req: &Requirement) -> Self {
+        let data = req.data.as_ref();
+
+        Self {
+            safes: data.and_then(|data| data.include_safes).unwrap_or_default(),
+            delegations: data
+                .and_then(|data| data.include_delegations)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// Users along with the Safes their addresses own on the chain and the vaults
+// that delegated the rights for the contract to them. Users whose lookups
+// failed are still checked with the rest of their addresses, the failures
+// being returned as errors for them alone.
+pub async fn with_holders<'a>(
+    ctx: &ProviderContext,
+    requirement_id: NumberId,
+    chain: EvmChain,
+    contract: Address,
+    users: &'a [User],
+    holders: Holders,
+) -> Result<(Cow<'a, [User]>, Vec<ReqUserAccess>), CheckableError> {
+    let mut users = Cow::Borrowed(users);
+    let mut failed = vec![];
+
+    if holders.safes {
+        let querier = ctx
+            .safes(chain)
+            .ok_or_else(|| CheckableError::NoSafeService(format!("{chain:?}")))?;
+
+        failed.extend(
+            extend_users(
+                &mut users,
+                |owner| querier.get_safes(owner),
+                CheckableError::SafeLookup,
+            )
+            .await,
+        );
+    }
+
+    // Vaults of Safes count as well, as Safes can be delegates
+    if holders.delegations {
+        let querier = ctx
+            .delegations(chain)
+            .ok_or_else(|| CheckableError::NoSuchChain(format!("{chain:?}")))?;
+
+        failed.extend(
+            extend_users(
+                &mut users,
+                |delegate| querier.get_vaults(delegate, contract),
+                CheckableError::DelegationLookup,
+            )
+            .await,
+        );
+    }
+
+    let failed = failed
+        .into_iter()
+        .map(|(user_id, e)| ReqUserAccess {
+            requirement_id,
+            user_id,
+            access: None,
+            amount: None,
+            usd_amount: None,
+            warning: None,
+            error: Some(e.to_string()),
+        })
+        .collect();
+
+    Ok((users, failed))
+}
+
+// Adds the addresses found for each EVM address of the users to the owner
+// of the address, returning the lookups that failed along with their owner
+async fn extend_users<F, Fut, E>(
+    users: &mut Cow<'_, [User]>,
+    lookup: F,
+    error: fn(String, String) -> CheckableError,
+) -> Vec<(NumberId, CheckableError)>
+where
+    F: Fn(Address) -> Fut,
+    Fut: Future<Output = Result<Vec<Address>, E>>,
+    E: Display,
+{
+    let addresses: Vec<(usize, Address)> = users
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, u)| u.evm_addresses().map(move |address| (idx, address)))
+        .collect();
+    let found = join_all(addresses.iter().map(|(_, address)| lookup(*address))).await;
+
+    let users = users.to_mut();
+    let mut failed = vec![];
+
+    for ((idx, address), found) in addresses.into_iter().zip(found) {
+        let found = match found {
+            Ok(found) => found,
+            Err(e) => {
+                failed.push((users[idx].id, error(format!("{address:#x}"), e.to_string())));
+                continue;
+            }
+        };
+
+        for address in found.into_iter().map(ChainAddress::Evm) {
+            if !users[idx].addresses.contains(&address) {
+                users[idx].addresses.push(address);
+            }
+        }
+    }
+
+    failed
+}
+
+pub fn missing_user_addresses(requirement_id: NumberId, users: &[User]) -> Vec<ReqUserAccess> {
+    users
+        .iter()
+        .map(|u| ReqUserAccess {
+            requirement_id,
+            user_id: u.id,
+            access: None,
+            amount: None,
+            usd_amount: None,
+            warning: None,
+            error: Some(CheckableError::MissingUserAddress(u.id.to_string()).to_string()),
+        })
+        .collect()
+}
+
+// Addresses of the users on a chain along with the ids of their owners, an
+// address shared by several users, like a Safe, being listed for each of
+// them. An error for each user when none of them linked an address.
+pub fn user_addresses<'a, A, I>(
+    requirement_id: NumberId,
+    users: &'a [User],
+    addresses: impl Fn(&'a User) -> I,
+) -> Result<(Vec<NumberId>, Vec<A>), Vec<ReqUserAccess>>
+where
+    I: IntoIterator<Item = A>,
+{
+    let (user_ids, user_addresses): (Vec<NumberId>, Vec<A>) = users
+        .iter()
+        .flat_map(|u| addresses(u).into_iter().map(move |address| (u.id, address)))
+        .unzip();
+
+    if user_addresses.is_empty() {
+        return Err(missing_user_addresses(requirement_id, users));
+    }
+
+    Ok((user_ids, user_addresses))
+}
+
+// Accesses of the owners of the addresses based on their balances, in the
+// order of the addresses
+pub fn balance_accesses<E: Display>(
+    requirement_id: NumberId,
+    user_ids: &[NumberId],
+    balances: Vec<Result<Amount, E>>,
+    limits: &Option<AmountLimits>,
+) -> Vec<ReqUserAccess> {
+    balances
+        .into_iter()
+        .zip(user_ids)
+        .map(|(balance, &user_id)| match balance {
+            Ok(amount) => ReqUserAccess {
+                requirement_id,
+                user_id,
+                access: Some(check_if_in_range(amount, limits, false)),
+                amount: Some(amount),
+                usd_amount: None,
+                warning: None,
+                error: None,
+            },
+            Err(e) => ReqUserAccess {
+                requirement_id,
+                user_id,
+                access: None,
+                amount: None,
+                usd_amount: None,
+                warning: None,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect()
+}
+
+// Price of the token on the chain in USD, the zero address standing for the
+// native coin
+pub async fn usd_price(
+    ctx: &ProviderContext,
+    chain: EvmChain,
+    token: Address,
+) -> Result<Amount, CheckableError> {
+    let Some(prices) = ctx.prices(chain) else {
+        return Err(CheckableError::NoSuchChain(format!("{chain:?}")));
+    };
+
+    prices
+        .get_usd_price(token)
+        .await
+        .map_err(|e| CheckableError::PriceLookup(format!("{token:#x}"), e.to_string()))
+}
+
+// Accesses of the owners of the addresses based on the value of their
+// balances at the price, which keep the balances as their amounts
+pub fn usd_accesses<E: Display>(
+    requirement_id: NumberId,
+    user_ids: &[NumberId],
+    balances: Vec<Result<Amount, E>>,
+    price: Amount,
+    limits: &Option<AmountLimits>,
+) -> Vec<ReqUserAccess> {
+    balance_accesses(requirement_id, user_ids, balances, limits)
+        .into_iter()
+        .map(|access| {
+            let usd_amount = access.amount.map(|amount| {
+                amount
+                    .checked_mul(price)
+                    .unwrap_or(Amount::new(U256::MAX, 0))
+            });
+
+            ReqUserAccess {
+                access: usd_amount.map(|value| check_if_in_range(value, limits, false)),
+                usd_amount,
+                ..access
+            }
+        })
+        .collect()
+}
+
+// A single access for each user out of the accesses of their addresses,
+// summed amounts being checked against the limits of the requirement
+pub fn aggregate(
+    req: &Requirement,
+    users: &[User],
+    accesses: Vec<ReqUserAccess>,
+    limits: &Option<AmountLimits>,
+) -> Vec<ReqUserAccess> {
+    let aggregation = req
+        .data
+        .as_ref()
+        .and_then(|data| data.aggregation)
+        .unwrap_or_default();
+
+    users
+        .iter()
+        .map(|user| {
+            let accesses: Vec<&ReqUserAccess> =
+                accesses.iter().filter(|a| a.user_id == user.id).collect();
+
+            let amount = accesses
+                .iter()
+                .filter_map(|a| a.amount)
+                .reduce(Amount::saturating_add);
+            let usd_amount = accesses
+                .iter()
+                .filter_map(|a| a.usd_amount)
+                .reduce(Amount::saturating_add);
+            let failed = accesses.iter().any(|a| a.access.is_none());
+            let any = match accesses.iter().any(|a| a.access == Some(true)) {
+                true => Some(true),
+                false if failed || accesses.is_empty() => None,
+                false => Some(false),
+            };
+
+            // Requirements without amounts can only be checked per address,
+            // the USD values are checked instead of the amounts if any
+            let access = match (aggregation, usd_amount.or(amount)) {
+                (Aggregation::Sum, _) if failed => None,
+                (Aggregation::Sum, Some(value)) if accesses.iter().all(|a| a.amount.is_some()) => {
+                    Some(check_if_in_range(value, limits, false))
+                }
+                _ => any,
+            };
+
+            ReqUserAccess {
+                requirement_id: req.id,
+                user_id: user.id,
+                access,
+                amount,
+                usd_amount,
+                warning: accesses.iter().find_map(|a| a.warning.clone()),
+                error: accesses.iter().find_map(|a| a.error.clone()),
+            }
+        })
+        .collect()
+}
 
 pub fn check_if_in_range(amount: Amount, limits: &Option<AmountLimits>, equal_max: bool) -> bool {
     match limits {
         Some(limits) => {
-            let min_amount = limits.min_amount.unwrap_or_default();
-            let min_ok = if min_amount > 0.0 {
-                amount >= min_amount
-            } else {
-                amount > 0.0
+            let min_ok = match limits.min_amount {
+                Some(min_amount) if !min_amount.is_zero() => amount >= min_amount,
+                _ => !amount.is_zero(),
             };
 
             match limits.max_amount {
@@ -24,6 +348,179 @@ pub fn check_if_in_range(amount: Amount, limits: &Option<AmountLimits>, equal_ma
             }
         }
 
-        None => amount > 0.0,
+        None => !amount.is_zero(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{with_holders, Holders};
+    use crate::{
+        address,
+        types::{Address, EvmChain, User},
+    };
+    use async_trait::async_trait;
+    use providers::{
+        evm::{delegation::DelegationQuerier, general::ProviderError, safe::SafeQuerier},
+        ProviderContext,
+    };
+    use std::sync::Arc;
+
+    const OWNER: &str = "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE";
+    const SAFE: &str = "0x849D52316331967b6fF1198e5E32A0eB168D039d";
+    const VAULT: &str = "0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503";
+    const TOKEN: &str = "0x3C65D35A8190294d39013287B246117eBf6615Bd";
+
+    const FAILING: &str = "0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3";
+
+    // The owner owns a Safe, to which a vault delegated the rights for the
+    // token, and the Safes of another address can't be looked up
+    struct StaticHolders;
+
+    #[async_trait]
+    impl SafeQuerier for StaticHolders {
+        async fn get_safes(&self, owner: Address) -> Result<Vec<Address>, ProviderError> {
+            if owner == address!(FAILING) {
+                return Err(ProviderError::Other("Service unavailable".into()));
+            }
+
+            Ok(match owner == address!(OWNER) {
+                true => vec![address!(SAFE)],
+                false => vec![],
+            })
+        }
+    }
+
+    #[async_trait]
+    impl DelegationQuerier for StaticHolders {
+        async fn get_vaults(
+            &self,
+            delegate: Address,
+            contract: Address,
+        ) -> Result<Vec<Address>, ProviderError> {
+            Ok(
+                match delegate == address!(SAFE) && contract == address!(TOKEN) {
+                    true => vec![address!(VAULT)],
+                    false => vec![],
+                },
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn holders() {
+        let users = vec![
+            User {
+                id: 0,
+                addresses: vec![address!(OWNER).into()],
+                platform_users: None,
+            },
+            User {
+                id: 1,
+                addresses: vec![address!(FAILING).into()],
+                platform_users: None,
+            },
+        ];
+        let ctx = ProviderContext::new()
+            .with_safes(EvmChain::Ethereum, Arc::new(StaticHolders))
+            .with_delegations(EvmChain::Ethereum, Arc::new(StaticHolders));
+
+        let addresses = |safes: bool, delegations: bool, contract: &'static str| {
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                let holders = Holders { safes, delegations };
+                let (users, _) = with_holders(
+                    &ctx,
+                    0,
+                    EvmChain::Ethereum,
+                    address!(contract),
+                    &users,
+                    holders,
+                )
+                .await
+                .unwrap();
+
+                users[0].evm_addresses().collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(addresses(false, true, TOKEN).await, vec![address!(OWNER)]);
+        assert_eq!(
+            addresses(true, false, TOKEN).await,
+            vec![address!(OWNER), address!(SAFE)]
+        );
+        assert_eq!(
+            addresses(true, true, TOKEN).await,
+            vec![address!(OWNER), address!(SAFE), address!(VAULT)]
+        );
+        assert_eq!(
+            addresses(true, true, SAFE).await,
+            vec![address!(OWNER), address!(SAFE)]
+        );
+
+        // Only the user whose lookup failed gets an error
+        let (_, failed) = with_holders(
+            &ctx,
+            0,
+            EvmChain::Ethereum,
+            address!(TOKEN),
+            &users,
+            Holders {
+                safes: true,
+                delegations: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            failed
+                .iter()
+                .map(|a| (a.user_id, a.error.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![(
+                1,
+                Some(
+                    "Failed to find the Safes of `0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3`: \
+                    Service unavailable"
+                )
+            )]
+        );
+
+        let missing = |chain: EvmChain, holders: Holders| {
+            let (ctx, users) = (ctx.clone(), users.clone());
+
+            async move {
+                with_holders(&ctx, 0, chain, address!(TOKEN), &users, holders)
+                    .await
+                    .err()
+                    .map(|e| e.to_string())
+            }
+        };
+
+        assert_eq!(
+            missing(
+                EvmChain::Polygon,
+                Holders {
+                    safes: false,
+                    delegations: true,
+                },
+            )
+            .await
+            .as_deref(),
+            Some("Chain `Polygon` is not supported")
+        );
+        assert_eq!(
+            missing(
+                EvmChain::Polygon,
+                Holders {
+                    safes: true,
+                    delegations: false,
+                },
+            )
+            .await
+            .as_deref(),
+            Some("No Safe Transaction Service configured for chain `Polygon`")
+        );
     }
 }
@@ -0,0 +1,156 @@
+use super::{Address, CosmosAddress, SolanaAddress, SubstrateAddress};
+use providers::evm::ens;
+use serde::{de::Error, Deserialize, Deserializer};
+use std::str::FromStr;
+
+/// Address of an account on any of the supported chains, parsed from its
+/// usual text form: hex for EVM chains, bech32 for Cosmos chains, SS58 for
+/// Substrate chains and base58 for Solana. ENS names are kept as they are
+/// and resolved when requirements are checked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChainAddress {
+    Evm(Address),
+    Solana(SolanaAddress),
+    Cosmos(CosmosAddress),
+    Substrate(SubstrateAddress),
+    Ens(String),
+}
+
+impl ChainAddress {
+    pub fn evm(&self) -> Option<Address> {
+        match self {
+            Self::Evm(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn solana(&self) -> Option<SolanaAddress> {
+        match self {
+            Self::Solana(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn cosmos(&self) -> Option<&CosmosAddress> {
+        match self {
+            Self::Cosmos(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn substrate(&self) -> Option<SubstrateAddress> {
+        match self {
+            Self::Substrate(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn ens(&self) -> Option<&str> {
+        match self {
+            Self::Ens(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl From<Address> for ChainAddress {
+    fn from(address: Address) -> Self {
+        Self::Evm(address)
+    }
+}
+
+impl From<SolanaAddress> for ChainAddress {
+    fn from(address: SolanaAddress) -> Self {
+        Self::Solana(address)
+    }
+}
+
+impl From<CosmosAddress> for ChainAddress {
+    fn from(address: CosmosAddress) -> Self {
+        Self::Cosmos(address)
+    }
+}
+
+impl From<SubstrateAddress> for ChainAddress {
+    fn from(address: SubstrateAddress) -> Self {
+        Self::Substrate(address)
+    }
+}
+
+impl FromStr for ChainAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.to_lowercase().ends_with(".eth") {
+            Some(Self::Ens(ens::normalize(s)))
+        } else if s.starts_with("0x") {
+            Address::from_str(s).map(Self::Evm).ok()
+        } else if let Ok(address) = CosmosAddress::from_str(s) {
+            Some(Self::Cosmos(address))
+        } else if let Ok(address) = SubstrateAddress::from_str(s) {
+            // Checked before Solana, as SS58 is base58 with a checksum
+            Some(Self::Substrate(address))
+        } else {
+            SolanaAddress::from_str(s).map(Self::Solana).ok()
+        }
+        .ok_or_else(|| format!("Invalid address `{s}`"))
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChainAddress;
+    use crate::address;
+    use providers::{cosmos_address, solana_address, substrate_address};
+
+    #[test]
+    fn chain_address_parse() {
+        assert_eq!(
+            serde_json::from_str::<ChainAddress>("\"0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE\"")
+                .unwrap(),
+            ChainAddress::Evm(address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"))
+        );
+        assert_eq!(
+            serde_json::from_str::<ChainAddress>(
+                "\"9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM\""
+            )
+            .unwrap(),
+            ChainAddress::Solana(solana_address!(
+                "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"
+            ))
+        );
+        assert_eq!(
+            "cosmos1qnufjmd8vwm6j6d3q28wxqr4d8408f34f99rlj"
+                .parse::<ChainAddress>()
+                .unwrap(),
+            ChainAddress::Cosmos(cosmos_address!(
+                "cosmos1qnufjmd8vwm6j6d3q28wxqr4d8408f34f99rlj"
+            ))
+        );
+        assert!("cosmos1qnufjmd8vwm6j6d3q28wxqr4d8408f34f99rla"
+            .parse::<ChainAddress>()
+            .is_err());
+        assert_eq!(
+            "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+                .parse::<ChainAddress>()
+                .unwrap(),
+            ChainAddress::Substrate(substrate_address!(
+                "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+            ))
+        );
+        assert_eq!(
+            "Vitalik.eth".parse::<ChainAddress>().unwrap(),
+            ChainAddress::Ens("vitalik.eth".into())
+        );
+        assert!("0x1234".parse::<ChainAddress>().is_err());
+        assert!("not an address".parse::<ChainAddress>().is_err());
+    }
+}
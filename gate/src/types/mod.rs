@@ -1,16 +1,23 @@
+use crate::requirements::errors::CheckableError;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
+mod address;
 mod platform;
 mod requirement;
 mod user;
+pub use address::*;
 pub use platform::*;
 pub use requirement::*;
 pub use user::*;
 
-pub use providers::{Address, EvmChain, U256};
+pub use providers::{
+    cosmos::CosmosAddress,
+    solana::SolanaAddress,
+    substrate::{NativeBalance, NftPallet, SubstrateAddress},
+    Address, Amount, EvmChain, Snapshot, U256,
+};
 pub type NumberId = u64;
-pub type Amount = f64;
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +25,8 @@ pub struct DetailedAccess {
     pub requirement_id: NumberId,
     pub access: Option<bool>,
     pub amount: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usd_amount: Option<Amount>,
 }
 
 #[derive(Serialize, Debug)]
@@ -61,22 +70,61 @@ pub struct AmountLimits {
 }
 
 impl AmountLimits {
-    pub fn from_req(req: &Requirement) -> Option<Self> {
-        let get_inner = |field: &Option<String>| match field {
-            Some(value) => match value.parse::<Amount>() {
-                Ok(v) => Some(v),
-                Err(_) => None,
-            },
-            None => None,
+    pub fn from_req(req: &Requirement) -> Result<Option<Self>, CheckableError> {
+        req.data
+            .as_ref()
+            .map(|data| {
+                Ok(Self {
+                    min_amount: parse_limit("minAmount", &data.min_amount)?,
+                    max_amount: parse_limit("maxAmount", &data.max_amount)?,
+                })
+            })
+            .transpose()
+    }
+
+    /// Limits of the value of the balances in USD, `None` when the
+    /// requirement has neither of them. They replace the limits of the
+    /// token amount and are checked with current prices, so they can't be
+    /// combined with either of those or with a snapshot.
+    pub fn usd_from_req(req: &Requirement) -> Result<Option<Self>, CheckableError> {
+        let Some(data) = &req.data else {
+            return Ok(None);
         };
 
-        req.data.as_ref().map(|data| Self {
-            min_amount: get_inner(&data.min_amount),
-            max_amount: get_inner(&data.max_amount),
-        })
+        let limits = Self {
+            min_amount: parse_limit("usdMinAmount", &data.usd_min_amount)?,
+            max_amount: parse_limit("usdMaxAmount", &data.usd_max_amount)?,
+        };
+
+        if limits.min_amount.is_none() && limits.max_amount.is_none() {
+            return Ok(None);
+        }
+
+        let conflict = match (&data.min_amount, &data.max_amount, &req.snapshot) {
+            (Some(_), _, _) => Some(("minAmount", "can't be combined with USD limits")),
+            (_, Some(_), _) => Some(("maxAmount", "can't be combined with USD limits")),
+            (_, _, Some(_)) => Some(("snapshot", "USD limits are checked with current prices")),
+            _ => None,
+        };
+
+        match conflict {
+            Some((field, reason)) => Err(CheckableError::InvalidField(field.into(), reason.into())),
+            None => Ok(Some(limits)),
+        }
     }
 }
 
+fn parse_limit(field: &str, value: &Option<String>) -> Result<Option<Amount>, CheckableError> {
+    value
+        .as_ref()
+        .map(|value| {
+            value
+                .parse::<Amount>()
+                .map_err(|e| CheckableError::InvalidField(field.into(), e.to_string()))
+        })
+        .transpose()
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Role {
     pub id: Option<NumberId>,
@@ -90,4 +138,5 @@ pub struct CheckRolesOfMembersRequest {
     pub users: Vec<User>,
     pub roles: Vec<Role>,
     pub send_details: Option<bool>,
+    pub no_cache: Option<bool>,
 }
@@ -1,17 +1,30 @@
 use crate::{
     requirements::{
+        cosmos::CosmosRequirement,
         errors::CheckableError,
         general::{
+            activity::{TxCountRequirement, WalletAgeRequirement},
             allowlist::AllowListRequirement,
             coin::CoinRequirement,
+            contract::ContractRequirement,
+            ens::EnsRequirement,
             free::FreeRequirement,
-            token::{Erc1155Requirement, Erc20Requirement, Erc721Requirement},
+            poap::PoapRequirement,
+            safe::SafeRequirement,
+            token::{
+                Erc1155Requirement, Erc20Requirement, Erc721Requirement,
+                MultichainErc20Requirement, VotingPowerRequirement,
+            },
         },
+        solana::{SolRequirement, SolanaNftRequirement, SplRequirement},
+        substrate::{SubstrateAssetRequirement, SubstrateNftRequirement, SubstrateRequirement},
         Checkable,
     },
-    types::{Address, EvmChain, NumberId, U256},
+    types::{Address, ChainAddress, EvmChain, NativeBalance, NftPallet, NumberId, Snapshot, U256},
 };
-use serde::{Deserialize, Serialize};
+use providers::ProviderContext;
+use serde::{de::Error, Deserialize, Deserializer};
+use std::{cmp::Ordering, fmt};
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -22,15 +35,198 @@ pub enum RequirementType {
     Coin,
     Allowlist,
     Free,
+    Sol,
+    Spl,
+    SolanaNft,
+    Cosmos,
+    Substrate,
+    SubstrateAsset,
+    SubstrateNft,
+    Ens,
+    Contract,
+    TxCount,
+    WalletAge,
+    Safe,
+    VotingPower,
+    Poap,
+    MultichainErc20,
+}
+
+impl RequirementType {
+    /// Whether the amounts of the requirement are balances, which can be
+    /// summed across the addresses of a user.
+    pub fn is_balance(&self) -> bool {
+        use RequirementType::*;
+
+        matches!(
+            self,
+            Erc20
+                | Erc721
+                | Erc1155
+                | Coin
+                | Sol
+                | Spl
+                | SolanaNft
+                | Cosmos
+                | Substrate
+                | SubstrateAsset
+                | SubstrateNft
+                | VotingPower
+                | MultichainErc20
+        )
+    }
+}
+
+/// Chain of a requirement, either an EVM chain or the id of another kind of
+/// chain, like `osmosis-1` for a Cosmos chain or `polkadot` for a Substrate
+/// chain. EVM chains are given by their name or by their chain id as a
+/// number, and names in upper snake case are always taken for EVM chains so
+/// that a misspelled one is rejected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Chain {
+    Evm(EvmChain),
+    Id(String),
+}
+
+impl<'de> Deserialize<'de> for Chain {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ChainRef {
+            Id(u64),
+            Name(String),
+        }
+
+        let unknown =
+            |chain: &dyn fmt::Display| D::Error::custom(format!("Unknown chain `{chain}`"));
+
+        match ChainRef::deserialize(deserializer)? {
+            ChainRef::Id(id) => EvmChain::from_id(id)
+                .map(Self::Evm)
+                .ok_or_else(|| unknown(&id)),
+            ChainRef::Name(name)
+                if name
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') =>
+            {
+                EvmChain::from_name(&name)
+                    .map(Self::Evm)
+                    .ok_or_else(|| unknown(&name))
+            }
+            ChainRef::Name(id) => Ok(Self::Id(id)),
+        }
+    }
+}
+
+impl Chain {
+    pub fn evm(&self) -> Option<EvmChain> {
+        match self {
+            Self::Evm(chain) => Some(*chain),
+            Self::Id(_) => None,
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Evm(_) => None,
+            Self::Id(id) => Some(id),
+        }
+    }
+}
+
+impl From<EvmChain> for Chain {
+    fn from(chain: EvmChain) -> Self {
+        Self::Evm(chain)
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(chain) => write!(f, "{chain:?}"),
+            Self::Id(id) => f.write_str(id),
+        }
+    }
+}
+
+/// How the accesses of the addresses of a user are combined.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Aggregation {
+    /// Access through any single address.
+    #[default]
+    Any,
+    /// Access based on the sum of the amounts held by all the addresses,
+    /// compared against the limits of the requirement.
+    Sum,
+}
+
+/// ERC20 token deployed on one of the chains of a multichain requirement.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChainToken {
+    pub chain: EvmChain,
+    pub address: Address,
+}
+
+/// Comparison of the value returned by a contract call with the value of
+/// the requirement.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Gte,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Lte,
+}
+
+impl Operator {
+    pub fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Gt => ordering.is_gt(),
+            Self::Gte => ordering.is_ge(),
+            Self::Lt => ordering.is_lt(),
+            Self::Lte => ordering.is_le(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RequirementData {
     pub id: Option<U256>,
-    pub addresses: Option<Vec<Address>>,
+    pub addresses: Option<Vec<ChainAddress>>,
     pub min_amount: Option<String>,
     pub max_amount: Option<String>,
+    pub denom: Option<String>,
+    pub balance_kind: Option<NativeBalance>,
+    pub pallet: Option<NftPallet>,
+    pub name: Option<String>,
+    pub subdomains: Option<bool>,
+    pub function: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub returns: Option<String>,
+    pub operator: Option<Operator>,
+    pub value: Option<String>,
+    pub threshold: Option<bool>,
+    pub include_safes: Option<bool>,
+    pub include_delegations: Option<bool>,
+    pub event_ids: Option<Vec<u64>>,
+    pub tokens: Option<Vec<ChainToken>>,
+    pub aggregation: Option<Aggregation>,
+    pub usd_min_amount: Option<String>,
+    pub usd_max_amount: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -39,29 +235,106 @@ pub struct Requirement {
     pub id: NumberId,
     #[serde(rename(deserialize = "type"))]
     pub typ: RequirementType,
-    pub address: Option<Address>,
+    pub address: Option<ChainAddress>,
     pub data: Option<RequirementData>,
-    pub chain: Option<EvmChain>,
+    pub chain: Option<Chain>,
+    pub snapshot: Option<Snapshot>,
 }
 
 impl Requirement {
-    pub fn inner(&self) -> Result<Box<dyn Checkable>, CheckableError> {
+    /// The checkable form of the requirement, validating its fields and
+    /// resolving the ENS names of allowlists before any user is checked.
+    pub async fn inner(&self, ctx: &ProviderContext) -> Result<Box<dyn Checkable>, CheckableError> {
         use RequirementType::*;
 
+        if let Some(chain) = &self.chain {
+            let supported = match chain {
+                Chain::Evm(chain) => ctx.get(*chain).is_some(),
+                Chain::Id(id) => ctx.cosmos(id).is_some() || ctx.substrate(id).is_some(),
+            };
+
+            if !matches!(self.typ, Free | Allowlist) && !supported {
+                return Err(CheckableError::NoSuchChain(chain.to_string()));
+            }
+        }
+
+        let aggregation = self.data.as_ref().and_then(|data| data.aggregation);
+
+        if aggregation == Some(Aggregation::Sum) && !self.typ.is_balance() {
+            return Err(CheckableError::InvalidField(
+                "aggregation".into(),
+                "SUM is only supported by balance requirements".into(),
+            ));
+        }
+
+        let usd_field = self.data.as_ref().and_then(|data| {
+            match (&data.usd_min_amount, &data.usd_max_amount) {
+                (Some(_), _) => Some("usdMinAmount"),
+                (_, Some(_)) => Some("usdMaxAmount"),
+                _ => None,
+            }
+        });
+
+        if let Some(field) = usd_field.filter(|_| !matches!(self.typ, Coin | Erc20)) {
+            return Err(CheckableError::InvalidField(
+                field.into(),
+                "USD limits are only supported by COIN and ERC20 requirements".into(),
+            ));
+        }
+
         Ok(match self.typ {
             Free => Box::new(FreeRequirement::try_from(self)?),
-            Allowlist => Box::new(AllowListRequirement::try_from(self)?),
+            Allowlist => Box::new(
+                AllowListRequirement::try_from(self)?
+                    .resolve_names(ctx)
+                    .await,
+            ),
             Coin => Box::new(CoinRequirement::try_from(self)?),
             Erc20 => Box::new(Erc20Requirement::try_from(self)?),
             Erc721 => Box::new(Erc721Requirement::try_from(self)?),
             Erc1155 => Box::new(Erc1155Requirement::try_from(self)?),
+            Sol => Box::new(SolRequirement::try_from(self)?),
+            Spl => Box::new(SplRequirement::try_from(self)?),
+            SolanaNft => Box::new(SolanaNftRequirement::try_from(self)?),
+            Cosmos => Box::new(CosmosRequirement::try_from(self)?),
+            Substrate => Box::new(SubstrateRequirement::try_from(self)?),
+            SubstrateAsset => Box::new(SubstrateAssetRequirement::try_from(self)?),
+            SubstrateNft => Box::new(SubstrateNftRequirement::try_from(self)?),
+            Ens => Box::new(EnsRequirement::try_from(self)?),
+            Contract => Box::new(ContractRequirement::try_from(self)?),
+            TxCount => Box::new(TxCountRequirement::try_from(self)?),
+            WalletAge => Box::new(WalletAgeRequirement::try_from(self)?),
+            Safe => Box::new(SafeRequirement::try_from(self)?),
+            VotingPower => Box::new(VotingPowerRequirement::try_from(self)?),
+            Poap => Box::new(PoapRequirement::try_from(self)?),
+            MultichainErc20 => Box::new(MultichainErc20Requirement::try_from(self)?),
         })
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct RequirementError {
-    pub requirement_id: NumberId,
-    pub msg: String,
+#[cfg(test)]
+mod test {
+    use super::Chain;
+    use crate::types::EvmChain;
+
+    #[test]
+    fn chain_deserialize() {
+        let chain = |value: serde_json::Value| serde_json::from_value::<Chain>(value);
+
+        assert_eq!(
+            chain(serde_json::json!("POLYGON")).unwrap(),
+            Chain::Evm(EvmChain::Polygon)
+        );
+        assert_eq!(
+            chain(serde_json::json!(137)).unwrap(),
+            Chain::Evm(EvmChain::Polygon)
+        );
+        assert_eq!(
+            chain(serde_json::json!("osmosis-1")).unwrap(),
+            Chain::Id("osmosis-1".into())
+        );
+        assert!(chain(serde_json::json!("POLYGN")).is_err());
+        assert!(chain(serde_json::json!("137")).is_err());
+        assert!(chain(serde_json::json!(31337)).is_err());
+    }
 }
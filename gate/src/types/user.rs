@@ -1,20 +1,44 @@
-use super::{Address, Amount, NumberId, PlatformUser};
+use super::{
+    Address, Amount, ChainAddress, CosmosAddress, NumberId, PlatformUser, SolanaAddress,
+    SubstrateAddress,
+};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: NumberId,
-    pub addresses: Vec<Address>,
+    pub addresses: Vec<ChainAddress>,
     pub platform_users: Option<Vec<PlatformUser>>,
 }
 
+impl User {
+    pub fn evm_addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.addresses.iter().filter_map(ChainAddress::evm)
+    }
+
+    pub fn solana_addresses(&self) -> impl Iterator<Item = SolanaAddress> + '_ {
+        self.addresses.iter().filter_map(ChainAddress::solana)
+    }
+
+    pub fn cosmos_addresses(&self) -> impl Iterator<Item = &CosmosAddress> + '_ {
+        self.addresses.iter().filter_map(ChainAddress::cosmos)
+    }
+
+    pub fn substrate_addresses(&self) -> impl Iterator<Item = SubstrateAddress> + '_ {
+        self.addresses.iter().filter_map(ChainAddress::substrate)
+    }
+}
+
 #[derive(Clone)]
 pub struct ReqUserAccess {
     pub requirement_id: NumberId,
     pub user_id: NumberId,
     pub access: Option<bool>,
     pub amount: Option<Amount>,
+    // Value of the amount for requirements with USD limits, which are
+    // checked against it instead of the amount
+    pub usd_amount: Option<Amount>,
     pub warning: Option<String>,
     pub error: Option<String>,
 }
@@ -0,0 +1,257 @@
+use crate::U256;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{cmp::Ordering, fmt, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("Invalid amount `{0}`")]
+    Invalid(String),
+    #[error("Amount `{0}` is out of range")]
+    OutOfRange(String),
+}
+
+/// A token amount stored as raw integer units together with the number of
+/// decimals of the token, so values are never rounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Amount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl Amount {
+    pub fn new(raw: U256, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Raw units of the amount expressed with `decimals` decimals, truncating
+    /// when decimals are dropped and `None` on overflow.
+    pub fn rescale(&self, decimals: u8) -> Option<U256> {
+        match decimals.cmp(&self.decimals) {
+            Ordering::Equal => Some(self.raw),
+            Ordering::Greater => pow10(decimals - self.decimals)
+                .and_then(|multiplier| self.raw.checked_mul(multiplier)),
+            Ordering::Less => Some(
+                pow10(self.decimals - decimals)
+                    .map(|divisor| self.raw / divisor)
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let decimals = self.decimals.max(other.decimals);
+        let raw = self
+            .rescale(decimals)?
+            .checked_add(other.rescale(decimals)?)?;
+
+        Some(Self { raw, decimals })
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        self.checked_add(other).unwrap_or(Self {
+            raw: U256::MAX,
+            decimals: self.decimals.max(other.decimals),
+        })
+    }
+
+    /// Product of the amounts, like a balance and a price, `None` on
+    /// overflow.
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Some(Self {
+            raw: self.raw.checked_mul(other.raw)?,
+            decimals: self.decimals.checked_add(other.decimals)?,
+        })
+    }
+}
+
+fn pow10(exponent: u8) -> Option<U256> {
+    U256::from(10).checked_pow(U256::from(exponent))
+}
+
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let decimals = self.decimals.max(other.decimals);
+
+        // Only the amount with fewer decimals is scaled up, and if that
+        // overflows it is necessarily larger than the other one
+        match (self.rescale(decimals), other.rescale(decimals)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (None, _) => Ordering::Greater,
+            (_, None) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Amount {}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || AmountError::Invalid(s.to_string());
+        let out_of_range = || AmountError::OutOfRange(s.to_string());
+
+        // Scientific notation like `1e18` or `2.5E-3`
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                (mantissa, exponent.parse::<i64>().map_err(|_| invalid())?)
+            }
+            None => (s, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        // Zero is in range whatever the exponent
+        if integer.chars().chain(fraction.chars()).all(|c| c == '0') {
+            return Ok(Self::default());
+        }
+
+        // A positive exponent beyond the decimals of the mantissa appends
+        // zeros to the raw units instead
+        let decimals = (fraction.len() as i64).saturating_sub(exponent);
+        let zeros = usize::try_from(-decimals.min(0)).map_err(|_| out_of_range())?;
+        let decimals = u8::try_from(decimals.max(0)).map_err(|_| out_of_range())?;
+
+        if zeros > 78 {
+            return Err(out_of_range());
+        }
+
+        let raw = U256::from_dec_str(&format!("{integer}{fraction}{}", "0".repeat(zeros)))
+            .map_err(|_| out_of_range())?;
+
+        Ok(Self { raw, decimals })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.decimals as usize;
+        let digits = format!("{:0>width$}", self.raw.to_string(), width = decimals + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+
+        if fraction.is_empty() {
+            write!(f, "{integer}")
+        } else {
+            write!(f, "{integer}.{fraction}")
+        }
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Amount", 3)?;
+        state.serialize_field("raw", &self.raw.to_string())?;
+        state.serialize_field("decimals", &self.decimals)?;
+        state.serialize_field("formatted", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Amount;
+    use crate::U256;
+
+    #[test]
+    fn amount_parse_and_format() {
+        let amount: Amount = "420.690".parse().unwrap();
+
+        assert_eq!(amount.raw(), U256::from(420690));
+        assert_eq!(amount.decimals(), 3);
+        assert_eq!(amount.to_string(), "420.69");
+        assert_eq!(
+            Amount::new(U256::from(5), 18).to_string(),
+            "0.000000000000000005"
+        );
+        assert_eq!(Amount::new(U256::from(1000), 0).to_string(), "1000");
+        assert!("".parse::<Amount>().is_err());
+        assert!("-1".parse::<Amount>().is_err());
+        assert!("1.2.3".parse::<Amount>().is_err());
+        assert!("1e".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn amount_parse_scientific() {
+        let amount: Amount = "1e18".parse().unwrap();
+
+        assert_eq!(amount.raw(), U256::exp10(18));
+        assert_eq!(amount.decimals(), 0);
+        assert_eq!("2.5E-3".parse::<Amount>().unwrap().to_string(), "0.0025");
+        assert_eq!("1.25e1".parse::<Amount>().unwrap().to_string(), "12.5");
+        assert!("1e100".parse::<Amount>().is_err());
+        assert!("0e100".parse::<Amount>().unwrap().is_zero());
+        assert!("0.00e-300".parse::<Amount>().unwrap().is_zero());
+    }
+
+    #[test]
+    fn amount_exact_comparison() {
+        let limit: Amount = "1000000.000000000000000001".parse().unwrap();
+        let exact = Amount::new(U256::from_dec_str("1000000000000000000000001").unwrap(), 18);
+        let below = Amount::new(U256::from_dec_str("1000000000000000000000000").unwrap(), 18);
+
+        assert!(exact >= limit);
+        assert!(below < limit);
+        assert_eq!(
+            Amount::new(U256::from(10), 1),
+            Amount::new(U256::from(1), 0)
+        );
+        assert!(Amount::new(U256::MAX, 0) > Amount::new(U256::MAX, 18));
+    }
+
+    #[test]
+    fn amount_add() {
+        let a: Amount = "60.5".parse().unwrap();
+        let b: Amount = "49.55".parse().unwrap();
+
+        assert_eq!(a.saturating_add(b).to_string(), "110.05");
+        assert!(Amount::new(U256::MAX, 0)
+            .checked_add(Amount::new(U256::one(), 0))
+            .is_none());
+    }
+
+    #[test]
+    fn amount_mul() {
+        let balance: Amount = "2.5".parse().unwrap();
+        let price = Amount::new(U256::from(185_050_000_000u64), 8);
+
+        assert_eq!(balance.checked_mul(price).unwrap().to_string(), "4626.25");
+        assert!(Amount::new(U256::MAX, 0)
+            .checked_mul(Amount::new(U256::from(2), 0))
+            .is_none());
+    }
+}
@@ -0,0 +1,546 @@
+use crate::{BalanceQuerier, Snapshot};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Returned for the addresses the wrapped querier gave no result for.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("No balance returned for the address")]
+pub struct MissingBalance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BalanceKind {
+    Native,
+    Fungible,
+    NonFungible,
+    Special,
+}
+
+type CacheKey<Q> = (
+    <Q as BalanceQuerier>::Chain,
+    BalanceKind,
+    Option<<Q as BalanceQuerier>::Address>,
+    Option<<Q as BalanceQuerier>::Id>,
+    Option<Snapshot>,
+    <Q as BalanceQuerier>::Address,
+);
+
+/// Values kept for a limited time, evicting the oldest ones once the size
+/// limit is reached.
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    max_size: usize,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    pub fn new(ttl: Duration, max_size: usize) -> Self {
+        Self {
+            ttl,
+            max_size,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.max_size > 0
+    }
+
+    pub fn size(&self) -> usize {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Keeps only the entries whose key satisfies `keep`.
+    pub fn retain<F: FnMut(&K) -> bool>(&self, mut keep: F) {
+        self.entries.lock().unwrap().retain(|key, _| keep(key));
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(inserted_at, _)| inserted_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= self.max_size {
+            entries.retain(|_, (inserted_at, _)| inserted_at.elapsed() < self.ttl);
+        }
+
+        // Evicting a tenth of the oldest entries at once keeps a full cache
+        // from sorting its entries on every insert
+        if entries.len() >= self.max_size {
+            let mut by_age: Vec<(Instant, K)> = entries
+                .iter()
+                .map(|(key, (inserted_at, _))| (*inserted_at, key.clone()))
+                .collect();
+            by_age.sort_by_key(|(inserted_at, _)| *inserted_at);
+
+            for (_, key) in by_age.into_iter().take(self.max_size / 10 + 1) {
+                entries.remove(&key);
+            }
+        }
+
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+}
+
+/// Balances fetched by the queriers of every chain, shared so that the size
+/// limit and the statistics are global.
+pub struct BalanceCache<Q: BalanceQuerier> {
+    entries: TtlCache<CacheKey<Q>, Q::Balance>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<Q> BalanceCache<Q>
+where
+    Q: BalanceQuerier,
+    Q::Address: Clone + Eq + Hash,
+    Q::Id: Clone + Eq + Hash,
+    Q::Balance: Clone,
+    Q::Chain: Clone + Eq + Hash,
+{
+    pub fn new(ttl: Duration, max_size: usize) -> Self {
+        Self {
+            entries: TtlCache::new(ttl, max_size),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.entries.is_enabled()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.entries.size(),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    fn get(&self, key: &CacheKey<Q>) -> Option<Q::Balance> {
+        let value = self.entries.get(key);
+
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        value
+    }
+
+    fn insert(&self, key: CacheKey<Q>, balance: Q::Balance) {
+        self.entries.insert(key, balance);
+    }
+}
+
+/// Querier answering from a [`BalanceCache`] and only forwarding the
+/// addresses missing from it to the wrapped querier. A refreshing querier
+/// forwards every address and only updates the cache.
+pub struct CachedQuerier<Q: BalanceQuerier> {
+    inner: Q,
+    chain: Q::Chain,
+    cache: Arc<BalanceCache<Q>>,
+    refresh: bool,
+}
+
+impl<Q> CachedQuerier<Q>
+where
+    Q: BalanceQuerier,
+    Q::Address: Clone + Eq + Hash,
+    Q::Id: Clone + Eq + Hash,
+    Q::Balance: Clone,
+    Q::Chain: Clone + Eq + Hash,
+    Q::Error: From<MissingBalance>,
+{
+    pub fn new(inner: Q, chain: Q::Chain, cache: Arc<BalanceCache<Q>>) -> Self {
+        Self {
+            inner,
+            chain,
+            cache,
+            refresh: false,
+        }
+    }
+
+    pub fn refreshing(mut self) -> Self {
+        self.refresh = true;
+        self
+    }
+
+    pub fn inner(&self) -> &Q {
+        &self.inner
+    }
+
+    pub fn cache(&self) -> &BalanceCache<Q> {
+        &self.cache
+    }
+
+    async fn cached<F, Fut>(
+        &self,
+        kind: BalanceKind,
+        token_address: Option<Q::Address>,
+        token_id: Option<Q::Id>,
+        user_addresses: &[Q::Address],
+        snapshot: Option<Snapshot>,
+        fetch: F,
+    ) -> Vec<Result<Q::Balance, Q::Error>>
+    where
+        F: FnOnce(Vec<Q::Address>) -> Fut,
+        Fut: Future<Output = Vec<Result<Q::Balance, Q::Error>>>,
+    {
+        if !self.cache.is_enabled() {
+            return fetch(user_addresses.to_vec()).await;
+        }
+
+        let key = |address: &Q::Address| {
+            (
+                self.chain.clone(),
+                kind,
+                token_address.clone(),
+                token_id.clone(),
+                snapshot,
+                address.clone(),
+            )
+        };
+
+        let mut results: Vec<Option<Result<Q::Balance, Q::Error>>> = user_addresses
+            .iter()
+            .map(|address| match self.refresh {
+                true => None,
+                false => self.cache.get(&key(address)).map(Ok),
+            })
+            .collect();
+
+        let missing: Vec<usize> = (0..results.len())
+            .filter(|idx| results[*idx].is_none())
+            .collect();
+
+        if !missing.is_empty() {
+            let fetched = fetch(
+                missing
+                    .iter()
+                    .map(|idx| user_addresses[*idx].clone())
+                    .collect(),
+            )
+            .await;
+
+            for (idx, result) in missing.into_iter().zip(fetched) {
+                if let Ok(balance) = &result {
+                    self.cache
+                        .insert(key(&user_addresses[idx]), balance.clone());
+                }
+
+                results[idx] = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(MissingBalance.into())))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<Q> BalanceQuerier for CachedQuerier<Q>
+where
+    Q: BalanceQuerier + Send + Sync,
+    Q::Address: Clone + Eq + Hash + Send + Sync,
+    Q::Id: Clone + Eq + Hash + Send + Sync,
+    Q::Balance: Clone + Send + Sync,
+    Q::Chain: Clone + Eq + Hash + Send + Sync,
+    Q::Error: From<MissingBalance> + Send,
+{
+    type Address = Q::Address;
+    type Id = Q::Id;
+    type Balance = Q::Balance;
+    type Chain = Q::Chain;
+    type Error = Q::Error;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        self.cached(
+            BalanceKind::Native,
+            None,
+            None,
+            user_addresses,
+            snapshot,
+            |missing| async move { self.inner.get_native_balance(&missing, snapshot).await },
+        )
+        .await
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        let token = token_address.clone();
+
+        self.cached(
+            BalanceKind::Fungible,
+            Some(token_address),
+            None,
+            user_addresses,
+            snapshot,
+            |missing| async move {
+                self.inner
+                    .get_fungible_balance(token, &missing, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        let (token, id) = (token_address.clone(), token_id.clone());
+
+        self.cached(
+            BalanceKind::NonFungible,
+            Some(token_address),
+            token_id,
+            user_addresses,
+            snapshot,
+            |missing| async move {
+                self.inner
+                    .get_non_fungible_balance(token, id, &missing, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+
+    async fn get_special_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        let (token, id) = (token_address.clone(), token_id.clone());
+
+        self.cached(
+            BalanceKind::Special,
+            Some(token_address),
+            token_id,
+            user_addresses,
+            snapshot,
+            |missing| async move {
+                self.inner
+                    .get_special_balance(token, id, &missing, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BalanceCache, CacheStats, CachedQuerier, MissingBalance};
+    use crate::{BalanceQuerier, Snapshot};
+    use async_trait::async_trait;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    // Answers with the address itself and counts the addresses queried
+    #[derive(Default)]
+    struct CountingQuerier {
+        queried: AtomicUsize,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct TestError;
+
+    impl From<MissingBalance> for TestError {
+        fn from(_: MissingBalance) -> Self {
+            TestError
+        }
+    }
+
+    #[async_trait]
+    impl BalanceQuerier for CountingQuerier {
+        type Address = u64;
+        type Id = u64;
+        type Balance = u64;
+        type Chain = u8;
+        type Error = TestError;
+
+        async fn get_native_balance(
+            &self,
+            user_addresses: &[u64],
+            _: Option<Snapshot>,
+        ) -> Vec<Result<u64, TestError>> {
+            self.queried
+                .fetch_add(user_addresses.len(), Ordering::Relaxed);
+
+            user_addresses.iter().map(|a| Ok(*a)).collect()
+        }
+
+        async fn get_fungible_balance(
+            &self,
+            token_address: u64,
+            user_addresses: &[u64],
+            _: Option<Snapshot>,
+        ) -> Vec<Result<u64, TestError>> {
+            self.queried
+                .fetch_add(user_addresses.len(), Ordering::Relaxed);
+
+            user_addresses
+                .iter()
+                .map(|a| Ok(token_address + a))
+                .collect()
+        }
+
+        async fn get_non_fungible_balance(
+            &self,
+            _: u64,
+            _: Option<u64>,
+            _: &[u64],
+            _: Option<Snapshot>,
+        ) -> Vec<Result<u64, TestError>> {
+            vec![]
+        }
+
+        async fn get_special_balance(
+            &self,
+            _: u64,
+            _: Option<u64>,
+            user_addresses: &[u64],
+            _: Option<Snapshot>,
+        ) -> Vec<Result<u64, TestError>> {
+            user_addresses.iter().map(|_| Err(TestError)).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_querier() {
+        let cache = Arc::new(BalanceCache::new(Duration::from_secs(60), 100));
+        let querier = CachedQuerier::new(CountingQuerier::default(), 0, Arc::clone(&cache));
+
+        assert_eq!(
+            querier.get_native_balance(&[1, 2], None).await,
+            vec![Ok(1), Ok(2)]
+        );
+        assert_eq!(
+            querier.get_native_balance(&[2, 3], None).await,
+            vec![Ok(2), Ok(3)]
+        );
+        assert_eq!(
+            querier.get_fungible_balance(10, &[2], None).await,
+            vec![Ok(12)]
+        );
+        assert_eq!(querier.inner().queried.load(Ordering::Relaxed), 4);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 4,
+                size: 4
+            }
+        );
+
+        // Errors are not cached
+        querier.get_special_balance(10, None, &[1], None).await;
+        assert_eq!(cache.stats().size, 4);
+
+        // Addresses left unanswered fail instead of the whole batch
+        assert_eq!(
+            querier
+                .get_non_fungible_balance(10, None, &[1, 2], None)
+                .await,
+            vec![Err(TestError), Err(TestError)]
+        );
+
+        // Refreshing queriers fetch every address and update the cache
+        let refreshing =
+            CachedQuerier::new(CountingQuerier::default(), 0, Arc::clone(&cache)).refreshing();
+
+        refreshing.get_native_balance(&[1, 4], None).await;
+        assert_eq!(refreshing.inner().queried.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.stats().size, 5);
+
+        querier.get_native_balance(&[1, 4], None).await;
+        assert_eq!(querier.inner().queried.load(Ordering::Relaxed), 4);
+
+        // Balances at a snapshot are cached separately from the latest ones
+        querier
+            .get_native_balance(&[1], Some(Snapshot::Block(1)))
+            .await;
+        assert_eq!(querier.inner().queried.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn cache_eviction() {
+        let cache = BalanceCache::<CountingQuerier>::new(Duration::from_secs(60), 10);
+
+        for address in 0..25 {
+            cache.insert(
+                (0, super::BalanceKind::Native, None, None, None, address),
+                address,
+            );
+        }
+
+        assert!(cache.stats().size <= 10);
+        assert!(cache
+            .get(&(0, super::BalanceKind::Native, None, None, None, 24))
+            .is_some());
+    }
+}
@@ -0,0 +1,468 @@
+use crate::{evm::BalancyProvider, transport::retry::RetryConfig, Address, EvmChain};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error("Chain `{0}` is configured more than once")]
+    DuplicateChain(String),
+    #[error("Invalid RPC url for chain `{0}`")]
+    InvalidRpcUrl(String),
+    #[error("No RPC url configured for chain `{0}`")]
+    MissingRpcUrl(String),
+    #[error("Environment variable `{0}` not found")]
+    MissingEnvVar(String),
+    #[error("Request limits of chain `{0}` must be positive")]
+    InvalidLimit(String),
+    #[error("Chain `{0}` is not supported by Balancy")]
+    BalancyNotSupported(String),
+}
+
+/// Role of Balancy in answering the ERC20 and ERC721 balances of a chain.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BalancyMode {
+    Primary,
+    Fallback,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChainConfig {
+    pub chain: EvmChain,
+    pub rpc_urls: Vec<String>,
+    pub multicall: Address,
+    pub balancy_mode: Option<BalancyMode>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_reprobe_interval_secs")]
+    pub reprobe_interval_secs: u64,
+    pub max_concurrent_requests: Option<usize>,
+    pub requests_per_second: Option<u32>,
+    pub safe_service_url: Option<String>,
+    // Chainlink USD feeds keyed by token, the zero address for the native coin
+    #[serde(default)]
+    pub price_feeds: HashMap<Address, Address>,
+    pub feed_registry: Option<Address>,
+    #[serde(default = "default_price_max_age_secs")]
+    pub price_max_age_secs: u64,
+    // Decimals of ERC20 tokens known in advance, so that balances answered
+    // by Balancy don't depend on the RPC for them
+    #[serde(default)]
+    pub token_decimals: HashMap<Address, u8>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SolanaConfig {
+    pub rpc_urls: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_reprobe_interval_secs")]
+    pub reprobe_interval_secs: u64,
+    pub max_concurrent_requests: Option<usize>,
+    pub requests_per_second: Option<u32>,
+}
+
+/// A Cosmos SDK chain queried through its REST (LCD) API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CosmosConfig {
+    pub chain_id: String,
+    pub lcd_urls: Vec<String>,
+    pub bech32_prefix: String,
+    pub native_denom: String,
+    #[serde(default = "default_cosmos_decimals")]
+    pub native_decimals: u8,
+    // Decimals of denoms that have no metadata in the bank module
+    #[serde(default)]
+    pub denom_decimals: HashMap<String, u8>,
+    // Look up addresses of other chains under the prefix of this one, which
+    // only derives the same account on chains sharing the key derivation
+    #[serde(default)]
+    pub reprefix_addresses: bool,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// A Substrate relay chain or parachain, referred to by its name.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubstrateConfig {
+    pub name: String,
+    pub rpc_urls: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_reprobe_interval_secs")]
+    pub reprobe_interval_secs: u64,
+    pub max_concurrent_requests: Option<usize>,
+    pub requests_per_second: Option<u32>,
+}
+
+fn default_cosmos_decimals() -> u8 {
+    6
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_reprobe_interval_secs() -> u64 {
+    30
+}
+
+// Feeds update at least daily, with some slack for their heartbeats
+fn default_price_max_age_secs() -> u64 {
+    90_000
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CacheConfig {
+    pub ttl_secs: u64,
+    pub max_size: usize,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ProvidersConfig {
+    #[serde(default)]
+    pub chains: Vec<ChainConfig>,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    pub solana: Option<SolanaConfig>,
+    #[serde(default)]
+    pub cosmos: Vec<CosmosConfig>,
+    #[serde(default)]
+    pub substrate: Vec<SubstrateConfig>,
+}
+
+impl ProvidersConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let mut config: Self = toml::from_str(source)?;
+        let mut seen = HashSet::new();
+        let mut chains = Vec::new();
+
+        for mut chain in std::mem::take(&mut config.chains) {
+            let name = format!("{:?}", chain.chain);
+
+            if !seen.insert(chain.chain) {
+                return Err(ConfigError::DuplicateChain(name));
+            }
+
+            if chain.balancy_mode.is_some() && !BalancyProvider::supports(chain.chain) {
+                return Err(ConfigError::BalancyNotSupported(name));
+            }
+
+            if validate_endpoints(
+                &name,
+                &mut chain.rpc_urls,
+                chain.max_concurrent_requests,
+                chain.requests_per_second,
+            )? {
+                chains.push(chain);
+            }
+        }
+
+        config.chains = chains;
+
+        if let Some(mut solana) = config.solana.take() {
+            if validate_endpoints(
+                "Solana",
+                &mut solana.rpc_urls,
+                solana.max_concurrent_requests,
+                solana.requests_per_second,
+            )? {
+                config.solana = Some(solana);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut cosmos_chains = Vec::new();
+
+        for mut cosmos in std::mem::take(&mut config.cosmos) {
+            if !seen.insert(cosmos.chain_id.clone()) {
+                return Err(ConfigError::DuplicateChain(cosmos.chain_id.clone()));
+            }
+
+            if validate_endpoints(&cosmos.chain_id, &mut cosmos.lcd_urls, None, None)? {
+                cosmos_chains.push(cosmos);
+            }
+        }
+
+        config.cosmos = cosmos_chains;
+
+        let mut substrate_chains = Vec::new();
+
+        // Cosmos and Substrate chains are both referred to by a name
+        for mut substrate in std::mem::take(&mut config.substrate) {
+            if !seen.insert(substrate.name.clone()) {
+                return Err(ConfigError::DuplicateChain(substrate.name.clone()));
+            }
+
+            if validate_endpoints(
+                &substrate.name,
+                &mut substrate.rpc_urls,
+                substrate.max_concurrent_requests,
+                substrate.requests_per_second,
+            )? {
+                substrate_chains.push(substrate);
+            }
+        }
+
+        config.substrate = substrate_chains;
+
+        Ok(config)
+    }
+}
+
+// Urls read from unset environment variables are left out, and `false` is
+// returned when none of them are left, in which case the chain is skipped
+// so its requirements fail on their own instead of the whole config
+fn validate_endpoints(
+    name: &str,
+    rpc_urls: &mut Vec<String>,
+    max_concurrent_requests: Option<usize>,
+    requests_per_second: Option<u32>,
+) -> Result<bool, ConfigError> {
+    if max_concurrent_requests == Some(0) || requests_per_second == Some(0) {
+        return Err(ConfigError::InvalidLimit(name.to_string()));
+    }
+
+    if rpc_urls.is_empty() {
+        return Err(ConfigError::MissingRpcUrl(name.to_string()));
+    }
+
+    let mut expanded = Vec::with_capacity(rpc_urls.len());
+
+    for rpc_url in rpc_urls.iter() {
+        let rpc_url = match expand_env(rpc_url) {
+            Ok(rpc_url) => rpc_url,
+            Err(ConfigError::MissingEnvVar(variable)) => {
+                log::warn!(
+                    "Environment variable `{variable}` not found, leaving out an url of `{name}`"
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        match Url::parse(&rpc_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => expanded.push(rpc_url),
+            _ => return Err(ConfigError::InvalidRpcUrl(name.to_string())),
+        }
+    }
+
+    if expanded.is_empty() {
+        log::warn!("Skipping chain `{name}`, none of its urls are set");
+        return Ok(false);
+    }
+
+    *rpc_urls = expanded;
+
+    Ok(true)
+}
+
+// Values of the form `${NAME}` are read from the environment so secrets
+// don't have to be stored in the config file
+fn expand_env(value: &str) -> Result<String, ConfigError> {
+    match value
+        .strip_prefix("${")
+        .and_then(|name| name.strip_suffix('}'))
+    {
+        Some(name) => std::env::var(name).map_err(|_| ConfigError::MissingEnvVar(name.to_string())),
+        None => Ok(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BalancyMode, ConfigError, ProvidersConfig};
+    use crate::{address, transport::retry::RetryPolicy, Address, EvmChain};
+
+    #[test]
+    fn config_parse() {
+        let config = ProvidersConfig::parse(
+            r#"
+            [[chains]]
+            chain = "ETHEREUM"
+            rpc_urls = ["https://eth.example.com", "https://eth-backup.example.com"]
+            multicall = "0x5ba1e12693dc8f9c48aad8770482f4739beed696"
+            balancy_mode = "fallback"
+            timeout_ms = 2000
+            max_concurrent_requests = 16
+            requests_per_second = 25
+            feed_registry = "0x47Fb2585D2C56Fe188D0E6ec628a38b74fCeeeDf"
+
+            [chains.price_feeds]
+            "0x0000000000000000000000000000000000000000" = "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"
+
+            [chains.token_decimals]
+            "0x6B175474E89094C44Da98b954EedeAC495271d0F" = 18
+
+            [cache]
+            ttl_secs = 60
+            max_size = 1000
+
+            [retry.balancy.rate_limited]
+            max_attempts = 8
+
+            [solana]
+            rpc_urls = ["https://api.mainnet-beta.solana.com"]
+
+            [[cosmos]]
+            chain_id = "osmosis-1"
+            lcd_urls = ["https://lcd.osmosis.zone"]
+            bech32_prefix = "osmo"
+            native_denom = "uosmo"
+
+            [cosmos.denom_decimals]
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2" = 6
+
+            [[substrate]]
+            name = "polkadot"
+            rpc_urls = ["https://rpc.polkadot.io"]
+            requests_per_second = 10
+
+            [[chains]]
+            chain = 5
+            rpc_urls = ["http://localhost:8545"]
+            multicall = "0x77dCa2C955b15e9dE4dbBCf1246B4B85b651e50e"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.chains.len(), 2);
+        assert_eq!(config.chains[0].chain, EvmChain::Ethereum);
+        assert_eq!(config.chains[1].chain, EvmChain::Goerli);
+        assert_eq!(
+            config.chains[0].multicall,
+            address!("0x5ba1e12693dc8f9c48aad8770482f4739beed696")
+        );
+        assert_eq!(config.chains[0].rpc_urls.len(), 2);
+        assert_eq!(config.chains[0].timeout_ms, 2000);
+        assert_eq!(config.chains[0].balancy_mode, Some(BalancyMode::Fallback));
+        assert_eq!(config.chains[1].balancy_mode, None);
+        assert_eq!(config.chains[1].timeout_ms, 10_000);
+        assert_eq!(config.chains[0].max_concurrent_requests, Some(16));
+        assert_eq!(config.chains[0].requests_per_second, Some(25));
+        assert_eq!(config.chains[1].requests_per_second, None);
+        assert_eq!(
+            config.chains[0].price_feeds[&Address::zero()],
+            address!("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419")
+        );
+        assert!(config.chains[1].feed_registry.is_none());
+        assert_eq!(
+            config.chains[0].token_decimals
+                [&address!("0x6B175474E89094C44Da98b954EedeAC495271d0F")],
+            18
+        );
+        assert!(config.chains[1].token_decimals.is_empty());
+        assert_eq!(config.chains[1].price_max_age_secs, 90_000);
+        assert_eq!(config.cache.ttl_secs, 60);
+        assert_eq!(config.cache.max_size, 1000);
+        assert_eq!(config.retry.balancy.rate_limited.max_attempts, 8);
+        assert_eq!(
+            config.retry.balancy.rate_limited.initial_backoff_ms,
+            RetryPolicy::default().initial_backoff_ms
+        );
+        assert_eq!(config.retry.rpc, Default::default());
+        assert_eq!(config.solana.unwrap().timeout_ms, 10_000);
+        assert_eq!(config.cosmos[0].chain_id, "osmosis-1");
+        assert_eq!(config.cosmos[0].native_decimals, 6);
+        assert_eq!(config.cosmos[0].denom_decimals.len(), 1);
+        assert_eq!(config.substrate[0].name, "polkadot");
+        assert_eq!(config.substrate[0].requests_per_second, Some(10));
+    }
+
+    #[test]
+    fn config_validation() {
+        let chain = |chain: &str, rpc_urls: &str| {
+            format!(
+                "[[chains]]\nchain = \"{chain}\"\nrpc_urls = [{rpc_urls}]\n\
+                multicall = \"0x5ba1e12693dc8f9c48aad8770482f4739beed696\"\n"
+            )
+        };
+
+        assert!(matches!(
+            ProvidersConfig::parse(&format!(
+                "{}{}",
+                chain("ETHEREUM", "\"https://a.example.com\""),
+                chain("ETHEREUM", "\"https://b.example.com\"")
+            )),
+            Err(ConfigError::DuplicateChain(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse(&chain(
+                "POLYGON",
+                "\"https://a.example.com\", \"not a url\""
+            )),
+            Err(ConfigError::InvalidRpcUrl(_))
+        ));
+        assert!(
+            ProvidersConfig::parse(&chain("BSC", "\"${RUSTY_GATE_SURELY_UNSET_RPC}\""))
+                .unwrap()
+                .chains
+                .is_empty()
+        );
+        assert_eq!(
+            ProvidersConfig::parse(&format!(
+                "{}{}",
+                chain(
+                    "BSC",
+                    "\"${RUSTY_GATE_SURELY_UNSET_RPC}\", \"https://a.example.com\""
+                ),
+                chain("GNOSIS", "\"${RUSTY_GATE_SURELY_UNSET_RPC}\"")
+            ))
+            .unwrap()
+            .chains
+            .into_iter()
+            .map(|chain| (chain.chain, chain.rpc_urls))
+            .collect::<Vec<_>>(),
+            vec![(EvmChain::Bsc, vec!["https://a.example.com".to_string()])]
+        );
+        assert!(matches!(
+            ProvidersConfig::parse(&format!(
+                "{}requests_per_second = 0\n",
+                chain("OPTIMISM", "\"https://a.example.com\"")
+            )),
+            Err(ConfigError::InvalidLimit(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse(&format!(
+                "{}balancy_mode = \"primary\"\n",
+                chain("GOERLI", "\"https://a.example.com\"")
+            )),
+            Err(ConfigError::BalancyNotSupported(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse("[solana]\nrpc_urls = [\"ws://localhost:8900\"]"),
+            Err(ConfigError::InvalidRpcUrl(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse(
+                &"[[cosmos]]\nchain_id = \"juno-1\"\nlcd_urls = [\"https://lcd.example.com\"]\n\
+                bech32_prefix = \"juno\"\nnative_denom = \"ujuno\"\n"
+                    .repeat(2)
+            ),
+            Err(ConfigError::DuplicateChain(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse(&chain("CELO", "")),
+            Err(ConfigError::MissingRpcUrl(_))
+        ));
+        assert!(matches!(
+            ProvidersConfig::parse(&chain("NOT_A_CHAIN", "\"https://a.example.com\"")),
+            Err(ConfigError::Toml(_))
+        ));
+    }
+}
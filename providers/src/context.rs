@@ -0,0 +1,209 @@
+use crate::{
+    cosmos::CosmosError,
+    evm::{
+        activity::WalletActivity, contract::ContractCaller, delegation::DelegationQuerier,
+        ens::EnsResolver, general::ProviderError, price::PriceQuerier, safe::SafeQuerier,
+    },
+    solana::{SolanaAddress, SolanaError},
+    substrate::SubstrateQuerier,
+    Address, Amount, BalanceQuerier, EvmChain, U256,
+};
+use std::{collections::HashMap, sync::Arc};
+
+pub type EvmQuerier = dyn BalanceQuerier<
+        Address = Address,
+        Id = U256,
+        Balance = Amount,
+        Chain = EvmChain,
+        Error = ProviderError,
+    > + Send
+    + Sync;
+
+pub type SolanaQuerier = dyn BalanceQuerier<
+        Address = SolanaAddress,
+        Id = SolanaAddress,
+        Balance = Amount,
+        Chain = (),
+        Error = SolanaError,
+    > + Send
+    + Sync;
+
+// Addresses are bech32 accounts and contracts or bank denoms, and chains are
+// identified by their chain id
+pub type CosmosQuerier = dyn BalanceQuerier<
+        Address = String,
+        Id = String,
+        Balance = Amount,
+        Chain = String,
+        Error = CosmosError,
+    > + Send
+    + Sync;
+
+/// Registry of the balance queriers used for each chain when checking
+/// requirements.
+#[derive(Clone, Default)]
+pub struct ProviderContext {
+    queriers: HashMap<EvmChain, Arc<EvmQuerier>>,
+    // Queriers refreshing the cached balances instead of reading them, for
+    // the requests bypassing the cache
+    refreshing: HashMap<EvmChain, Arc<EvmQuerier>>,
+    no_cache: bool,
+    callers: HashMap<EvmChain, Arc<dyn ContractCaller + Send + Sync>>,
+    activity: HashMap<EvmChain, Arc<dyn WalletActivity + Send + Sync>>,
+    safes: HashMap<EvmChain, Arc<dyn SafeQuerier + Send + Sync>>,
+    delegations: HashMap<EvmChain, Arc<dyn DelegationQuerier + Send + Sync>>,
+    prices: HashMap<EvmChain, Arc<dyn PriceQuerier + Send + Sync>>,
+    solana: Option<Arc<SolanaQuerier>>,
+    cosmos: HashMap<String, Arc<CosmosQuerier>>,
+    substrate: HashMap<String, Arc<dyn SubstrateQuerier + Send + Sync>>,
+    ens: Option<Arc<dyn EnsResolver + Send + Sync>>,
+}
+
+impl ProviderContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_querier(mut self, chain: EvmChain, querier: Arc<EvmQuerier>) -> Self {
+        self.insert(chain, querier);
+        self
+    }
+
+    pub fn insert(&mut self, chain: EvmChain, querier: Arc<EvmQuerier>) {
+        self.queriers.insert(chain, querier);
+    }
+
+    pub fn with_refreshing_querier(mut self, chain: EvmChain, querier: Arc<EvmQuerier>) -> Self {
+        self.refreshing.insert(chain, querier);
+        self
+    }
+
+    /// The same context for a single request, fetching every balance again
+    /// instead of answering from the cache, which is still updated.
+    pub fn without_cache(&self) -> Self {
+        Self {
+            no_cache: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn get(&self, chain: EvmChain) -> Option<Arc<EvmQuerier>> {
+        match self.no_cache {
+            true => self
+                .refreshing
+                .get(&chain)
+                .or_else(|| self.queriers.get(&chain)),
+            false => self.queriers.get(&chain),
+        }
+        .cloned()
+    }
+
+    pub fn with_caller(
+        mut self,
+        chain: EvmChain,
+        caller: Arc<dyn ContractCaller + Send + Sync>,
+    ) -> Self {
+        self.callers.insert(chain, caller);
+        self
+    }
+
+    pub fn caller(&self, chain: EvmChain) -> Option<Arc<dyn ContractCaller + Send + Sync>> {
+        self.callers.get(&chain).cloned()
+    }
+
+    pub fn with_activity(
+        mut self,
+        chain: EvmChain,
+        activity: Arc<dyn WalletActivity + Send + Sync>,
+    ) -> Self {
+        self.activity.insert(chain, activity);
+        self
+    }
+
+    pub fn activity(&self, chain: EvmChain) -> Option<Arc<dyn WalletActivity + Send + Sync>> {
+        self.activity.get(&chain).cloned()
+    }
+
+    pub fn with_safes(
+        mut self,
+        chain: EvmChain,
+        safes: Arc<dyn SafeQuerier + Send + Sync>,
+    ) -> Self {
+        self.safes.insert(chain, safes);
+        self
+    }
+
+    pub fn safes(&self, chain: EvmChain) -> Option<Arc<dyn SafeQuerier + Send + Sync>> {
+        self.safes.get(&chain).cloned()
+    }
+
+    pub fn with_delegations(
+        mut self,
+        chain: EvmChain,
+        delegations: Arc<dyn DelegationQuerier + Send + Sync>,
+    ) -> Self {
+        self.delegations.insert(chain, delegations);
+        self
+    }
+
+    pub fn delegations(&self, chain: EvmChain) -> Option<Arc<dyn DelegationQuerier + Send + Sync>> {
+        self.delegations.get(&chain).cloned()
+    }
+
+    pub fn with_prices(
+        mut self,
+        chain: EvmChain,
+        prices: Arc<dyn PriceQuerier + Send + Sync>,
+    ) -> Self {
+        self.prices.insert(chain, prices);
+        self
+    }
+
+    pub fn prices(&self, chain: EvmChain) -> Option<Arc<dyn PriceQuerier + Send + Sync>> {
+        self.prices.get(&chain).cloned()
+    }
+
+    pub fn with_solana(mut self, querier: Arc<SolanaQuerier>) -> Self {
+        self.solana = Some(querier);
+        self
+    }
+
+    pub fn solana(&self) -> Option<Arc<SolanaQuerier>> {
+        self.solana.clone()
+    }
+
+    pub fn with_cosmos(mut self, chain_id: impl Into<String>, querier: Arc<CosmosQuerier>) -> Self {
+        self.cosmos.insert(chain_id.into(), querier);
+        self
+    }
+
+    pub fn cosmos(&self, chain_id: &str) -> Option<Arc<CosmosQuerier>> {
+        self.cosmos.get(chain_id).cloned()
+    }
+
+    pub fn with_substrate(
+        mut self,
+        name: impl Into<String>,
+        querier: Arc<dyn SubstrateQuerier + Send + Sync>,
+    ) -> Self {
+        self.substrate.insert(name.into(), querier);
+        self
+    }
+
+    pub fn substrate(&self, name: &str) -> Option<Arc<dyn SubstrateQuerier + Send + Sync>> {
+        self.substrate.get(name).cloned()
+    }
+
+    pub fn with_ens(mut self, resolver: Arc<dyn EnsResolver + Send + Sync>) -> Self {
+        self.ens = Some(resolver);
+        self
+    }
+
+    pub fn ens(&self) -> Option<Arc<dyn EnsResolver + Send + Sync>> {
+        self.ens.clone()
+    }
+
+    pub fn chains(&self) -> impl Iterator<Item = &EvmChain> {
+        self.queriers.keys()
+    }
+}
@@ -0,0 +1,92 @@
+mod provider;
+
+use bech32::Variant;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+pub use provider::CosmosProvider;
+
+#[derive(Error, Debug)]
+pub enum CosmosError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Invalid Cosmos address `{0}`")]
+    InvalidAddress(String),
+    #[error("Address `{0}` does not have the `{1}` prefix of the chain")]
+    ForeignAddress(String, String),
+    #[error("LCD request failed with status {0}")]
+    Status(u16),
+    #[error("Invalid LCD response: {0}")]
+    InvalidResponse(String),
+    #[error("Unknown decimals of denom `{0}`")]
+    UnknownDecimals(String),
+    #[error("Cosmos chains do not support {0}")]
+    NotSupported(&'static str),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Bech32 address of a Cosmos SDK account or contract, kept in its lowercase
+/// text form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CosmosAddress(String);
+
+impl CosmosAddress {
+    /// Human readable part of the address, e.g. `cosmos` or `osmo`.
+    pub fn prefix(&self) -> &str {
+        let (prefix, _) = self.0.rsplit_once('1').expect("This should be fine");
+
+        prefix
+    }
+
+    /// The same account with the prefix of another chain. Chains sharing the
+    /// key derivation of the Cosmos Hub derive the same account from a key.
+    pub fn with_prefix(&self, prefix: &str) -> Result<Self, CosmosError> {
+        let (_, data, _) = bech32::decode(&self.0).expect("This should be fine");
+
+        bech32::encode(prefix, data, Variant::Bech32)
+            .map(Self)
+            .map_err(|_| CosmosError::InvalidAddress(format!("{prefix}1...")))
+    }
+}
+
+impl FromStr for CosmosAddress {
+    type Err = CosmosError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match bech32::decode(s) {
+            Ok((_, _, Variant::Bech32)) => Ok(Self(s.to_lowercase())),
+            _ => Err(CosmosError::InvalidAddress(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for CosmosAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for CosmosAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for CosmosAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[macro_export]
+macro_rules! cosmos_address {
+    ($addr:expr) => {{
+        use std::str::FromStr;
+        $crate::cosmos::CosmosAddress::from_str($addr)
+            .expect(&format!("Invalid Cosmos address {}", $addr))
+    }};
+}
@@ -0,0 +1,439 @@
+use crate::{
+    config::CosmosConfig,
+    cosmos::{CosmosAddress, CosmosError},
+    transport::retry::{BackendRetry, ErrorClass, RetryConfig},
+    Amount, BalanceQuerier, Snapshot, U256,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+// Queries the state of the chain at the given height instead of the latest
+const HEIGHT_HEADER: &str = "x-cosmos-block-height";
+
+fn classify(error: &CosmosError) -> Option<ErrorClass> {
+    match error {
+        CosmosError::Status(429) => Some(ErrorClass::RateLimited),
+        CosmosError::Status(500..=599) => Some(ErrorClass::ServerError),
+        CosmosError::Reqwest(e) if e.is_timeout() || e.is_connect() => Some(ErrorClass::Transport),
+        _ => None,
+    }
+}
+
+fn invalid(what: &str) -> CosmosError {
+    CosmosError::InvalidResponse(format!("unexpected {what} response"))
+}
+
+fn fail_all<T>(user_addresses: &[String], error: CosmosError) -> Vec<Result<T, CosmosError>> {
+    user_addresses
+        .iter()
+        .map(|_| Err(CosmosError::Other(error.to_string())))
+        .collect()
+}
+
+fn parse_amount(value: &Value, what: &str) -> Result<U256, CosmosError> {
+    value
+        .as_str()
+        .and_then(|amount| U256::from_dec_str(amount).ok())
+        .ok_or_else(|| invalid(what))
+}
+
+/// Provider of bank and CW20 balances of a Cosmos SDK chain through the REST
+/// (LCD) API. Tokens are either bank denoms, like the native denom or IBC
+/// denoms, or the bech32 addresses of CW20 contracts.
+pub struct CosmosProvider {
+    lcd_urls: Vec<String>,
+    prefix: String,
+    reprefix_addresses: bool,
+    native_denom: String,
+    client: reqwest::Client,
+    retry: BackendRetry,
+    decimals: Mutex<HashMap<String, u8>>,
+}
+
+impl CosmosProvider {
+    pub fn from_config(config: &CosmosConfig, retry: &RetryConfig) -> Result<Self, CosmosError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        let mut decimals = config.denom_decimals.clone();
+        decimals.insert(config.native_denom.clone(), config.native_decimals);
+
+        Ok(Self {
+            lcd_urls: config.lcd_urls.clone(),
+            prefix: config.bech32_prefix.clone(),
+            reprefix_addresses: config.reprefix_addresses,
+            native_denom: config.native_denom.clone(),
+            client,
+            retry: retry.rpc,
+            decimals: Mutex::new(decimals),
+        })
+    }
+
+    // LCD urls are tried in order, moving on to the next one only when the
+    // retries of a transient failure run out
+    async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        height: Option<u64>,
+    ) -> Result<Value, CosmosError> {
+        let mut last_error = None;
+
+        for lcd_url in self.lcd_urls.iter() {
+            let (result, _) = self
+                .retry
+                .run("LCD request", classify, || {
+                    self.fetch(format!("{lcd_url}{path}"), query, height)
+                })
+                .await;
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) if classify(&e).is_some() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| CosmosError::Other("No LCD url configured".into())))
+    }
+
+    async fn fetch(
+        &self,
+        url: String,
+        query: &[(&str, &str)],
+        height: Option<u64>,
+    ) -> Result<Value, CosmosError> {
+        let mut request = self.client.get(url).query(query);
+
+        if let Some(height) = height {
+            request = request.header(HEIGHT_HEADER, height);
+        }
+
+        let res = request.send().await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.json().await?),
+            status => Err(CosmosError::Status(status.as_u16())),
+        }
+    }
+
+    async fn smart_query(
+        &self,
+        contract: &CosmosAddress,
+        query: Value,
+        height: Option<u64>,
+    ) -> Result<Value, CosmosError> {
+        let query = base64::encode_config(query.to_string(), base64::URL_SAFE);
+
+        self.get(
+            &format!("/cosmwasm/wasm/v1/contract/{contract}/smart/{query}"),
+            &[],
+            height,
+        )
+        .await
+        .map(|mut response| response["data"].take())
+    }
+
+    async fn bank_balance(
+        &self,
+        denom: &str,
+        owner: &CosmosAddress,
+        height: Option<u64>,
+    ) -> Result<U256, CosmosError> {
+        let response = self
+            .get(
+                &format!("/cosmos/bank/v1beta1/balances/{owner}/by_denom"),
+                &[("denom", denom)],
+                height,
+            )
+            .await?;
+
+        parse_amount(&response["balance"]["amount"], "balance")
+    }
+
+    async fn cw20_balance(
+        &self,
+        contract: &CosmosAddress,
+        owner: &CosmosAddress,
+        height: Option<u64>,
+    ) -> Result<U256, CosmosError> {
+        let data = self
+            .smart_query(
+                contract,
+                json!({ "balance": { "address": owner.to_string() } }),
+                height,
+            )
+            .await?;
+
+        parse_amount(&data["balance"], "CW20 balance")
+    }
+
+    // Decimals of bank denoms come from the config or the denom metadata of
+    // the bank module, the ones of CW20 tokens from the contract
+    async fn decimals(
+        &self,
+        token: &str,
+        contract: Option<&CosmosAddress>,
+    ) -> Result<u8, CosmosError> {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        if let Some(decimals) = self.decimals.lock().unwrap().get(token) {
+            return Ok(*decimals);
+        }
+
+        let decimals = match contract {
+            Some(contract) => self
+                .smart_query(contract, json!({ "token_info": {} }), None)
+                .await?["decimals"]
+                .as_u64()
+                .and_then(|decimals| u8::try_from(decimals).ok())
+                .ok_or_else(|| invalid("CW20 token info"))?,
+            None => {
+                let response = self
+                    .get(
+                        "/cosmos/bank/v1beta1/denoms_metadata_by_query_string",
+                        &[("denom", token)],
+                        None,
+                    )
+                    .await
+                    .map_err(|e| match e {
+                        CosmosError::Status(_) => CosmosError::UnknownDecimals(token.to_string()),
+                        e => e,
+                    })?;
+                let metadata = &response["metadata"];
+
+                metadata["denom_units"]
+                    .as_array()
+                    .and_then(|units| {
+                        units
+                            .iter()
+                            .find(|unit| unit["denom"] == metadata["display"])
+                    })
+                    .and_then(|unit| unit["exponent"].as_u64())
+                    .and_then(|exponent| u8::try_from(exponent).ok())
+                    .ok_or_else(|| CosmosError::UnknownDecimals(token.to_string()))?
+            }
+        };
+
+        self.decimals
+            .lock()
+            .unwrap()
+            .insert(token.to_string(), decimals);
+
+        Ok(decimals)
+    }
+
+    // Accounts of other chains are only looked up under the prefix of this
+    // one when the chain opted in, as chains with another key derivation
+    // would resolve them to an unrelated account
+    fn owner(&self, user_address: &str) -> Result<CosmosAddress, CosmosError> {
+        let address = user_address.parse::<CosmosAddress>()?;
+
+        if address.prefix() == self.prefix {
+            Ok(address)
+        } else if self.reprefix_addresses {
+            address.with_prefix(&self.prefix)
+        } else {
+            Err(CosmosError::ForeignAddress(
+                user_address.to_string(),
+                self.prefix.clone(),
+            ))
+        }
+    }
+
+    async fn balances(
+        &self,
+        token: &str,
+        user_addresses: &[String],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, CosmosError>> {
+        let height = match snapshot {
+            Some(Snapshot::Block(height)) => Some(height),
+            Some(Snapshot::Timestamp(_)) => {
+                return fail_all(
+                    user_addresses,
+                    CosmosError::NotSupported("timestamp snapshots"),
+                )
+            }
+            None => None,
+        };
+
+        // Denoms are never valid bech32 addresses
+        let contract = token
+            .parse::<CosmosAddress>()
+            .ok()
+            .filter(|contract| contract.prefix() == self.prefix);
+
+        let decimals = match self.decimals(token, contract.as_ref()).await {
+            Ok(decimals) => decimals,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+
+        join_all(user_addresses.iter().map(|ua| async {
+            let owner = self.owner(ua)?;
+            let balance = match &contract {
+                Some(contract) => self.cw20_balance(contract, &owner, height).await?,
+                None => self.bank_balance(token, &owner, height).await?,
+            };
+
+            Ok(Amount::new(balance, decimals))
+        }))
+        .await
+    }
+}
+
+#[async_trait]
+impl BalanceQuerier for CosmosProvider {
+    // Bech32 addresses of accounts and contracts or bank denoms
+    type Address = String;
+    type Id = String;
+    type Balance = Amount;
+    type Chain = String;
+    type Error = CosmosError;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        self.balances(&self.native_denom, user_addresses, snapshot)
+            .await
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        self.balances(&token_address, user_addresses, snapshot)
+            .await
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        _token_address: Self::Address,
+        _token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        fail_all(user_addresses, CosmosError::NotSupported("NFT balances"))
+    }
+
+    async fn get_special_balance(
+        &self,
+        _token_address: Self::Address,
+        _token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        fail_all(
+            user_addresses,
+            CosmosError::NotSupported("special balances"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CosmosProvider;
+    use crate::{
+        config::CosmosConfig, cosmos::CosmosError, test_utils::serve,
+        transport::retry::RetryConfig, BalanceQuerier, Snapshot, U256,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const CONTRACT: &str = "juno1ejpjr43ht3y56pplm5pxpusmcrk9rkkvna4tklusnnwdxpqm0zlsy03zez";
+
+    #[tokio::test]
+    async fn cosmos_balances() {
+        let users = [
+            "juno1qnufjmd8vwm6j6d3q28wxqr4d8408f34lhxccw".to_string(),
+            // The same account on the Cosmos Hub
+            "cosmos1qnufjmd8vwm6j6d3q28wxqr4d8408f34f99rlj".to_string(),
+        ];
+        let ibc = "ibc/C4CFF46FD6DE35CA4CF4CE031E643C8FDC9BA4B99AE598E9B0ED98FE3A2319F9";
+
+        let url = serve(move |path, _| {
+            match path {
+                p if p.ends_with("by_denom?denom=ujuno") => {
+                    json!({ "balance": { "denom": "ujuno", "amount": "2500000" } })
+                }
+                p if p.contains("by_denom?denom=ibc") => {
+                    json!({ "balance": { "denom": ibc, "amount": "0" } })
+                }
+                p if p.contains("denoms_metadata") && p.contains("denom=ibc") => {
+                    json!({ "metadata": {
+                    "base": ibc,
+                    "display": "atom",
+                    "denom_units": [
+                        { "denom": ibc, "exponent": 0 },
+                        { "denom": "atom", "exponent": 6 },
+                    ],
+                } })
+                }
+                p if p.contains(CONTRACT) && p.contains("/smart/eyJ0b2tlbl9pbmZvIjp7fX0") => {
+                    json!({ "data": { "name": "Neta", "symbol": "NETA", "decimals": 6 } })
+                }
+                p if p.contains(CONTRACT) => json!({ "data": { "balance": "1000000" } }),
+                _ => json!({ "code": 5, "message": "not found" }),
+            }
+            .to_string()
+        })
+        .await;
+
+        let config = CosmosConfig {
+            chain_id: "juno-1".into(),
+            lcd_urls: vec![url],
+            bech32_prefix: "juno".into(),
+            native_denom: "ujuno".into(),
+            native_decimals: 6,
+            denom_decimals: HashMap::new(),
+            reprefix_addresses: false,
+            timeout_ms: 1000,
+        };
+        let provider = CosmosProvider::from_config(&config, &RetryConfig::default()).unwrap();
+
+        let native = provider.get_native_balance(&users, None).await;
+        assert_eq!(native[0].as_ref().unwrap().to_string(), "2.5");
+        assert!(matches!(native[1], Err(CosmosError::ForeignAddress(_, _))));
+
+        let reprefixing = CosmosProvider::from_config(
+            &CosmosConfig {
+                reprefix_addresses: true,
+                ..config
+            },
+            &RetryConfig::default(),
+        )
+        .unwrap();
+        let native = reprefixing.get_native_balance(&users, None).await;
+        assert_eq!(native[1].as_ref().unwrap().to_string(), "2.5");
+
+        let cw20 = provider
+            .get_fungible_balance(CONTRACT.into(), &users[..1], Some(Snapshot::Block(1)))
+            .await;
+        assert_eq!(cw20[0].as_ref().unwrap().raw(), U256::from(1_000_000));
+        assert_eq!(cw20[0].as_ref().unwrap().to_string(), "1");
+
+        let ibc = provider
+            .get_fungible_balance(ibc.into(), &users[..1], None)
+            .await;
+        assert!(ibc[0].as_ref().unwrap().is_zero());
+
+        assert!(matches!(
+            provider
+                .get_fungible_balance("uatom".into(), &users[..1], None)
+                .await[0],
+            Err(CosmosError::Other(_))
+        ));
+        assert!(provider
+            .get_native_balance(&["not an address".into()], None)
+            .await[0]
+            .is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{
+    evm::general::{Provider, ProviderError},
+    Address, Snapshot,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use web3::types::{BlockId, BlockNumber};
+
+/// Activity of addresses on a chain, used as a cheap sybil filter.
+#[async_trait]
+pub trait WalletActivity {
+    /// Number of transactions sent from each address, its nonce.
+    async fn get_transaction_count(
+        &self,
+        user_addresses: &[Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<u64, ProviderError>>;
+
+    /// Timestamp of the first block in which each address had sent a
+    /// transaction or held a balance, `None` if it was never active.
+    async fn get_first_activity(
+        &self,
+        user_addresses: &[Address],
+    ) -> Vec<Result<Option<u64>, ProviderError>>;
+}
+
+impl Provider {
+    async fn is_active(&self, address: Address, block: u64) -> Result<bool, ProviderError> {
+        let block = Some(BlockNumber::Number(block.into()));
+        let eth = self.single.eth();
+
+        if !eth.transaction_count(address, block).await?.is_zero() {
+            return Ok(true);
+        }
+
+        Ok(!eth.balance(address, block).await?.is_zero())
+    }
+
+    // Binary search for the first block the address was active in, which
+    // needs an archive node. Addresses only become inactive again by
+    // sending their whole balance away, which raises their nonce, so the
+    // search holds apart from contracts moving funds without transactions.
+    async fn first_active_block(&self, address: Address) -> Result<Option<u64>, ProviderError> {
+        if let Some(block) = self.first_activity.get(&address) {
+            return Ok(Some(block));
+        }
+
+        let latest = self
+            .block_header(BlockNumber::Latest)
+            .await?
+            .number
+            .as_u64();
+
+        if !self.is_active(address, latest).await? {
+            return Ok(None);
+        }
+
+        let first = if self.is_active(address, 0).await? {
+            0
+        } else {
+            let (mut low, mut high) = (0, latest);
+
+            while high - low > 1 {
+                let middle = low + (high - low) / 2;
+
+                if self.is_active(address, middle).await? {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+
+            high
+        };
+
+        self.first_activity.insert(address, first);
+
+        Ok(Some(first))
+    }
+
+    async fn first_activity(&self, address: Address) -> Result<Option<u64>, ProviderError> {
+        let Some(block) = self.first_active_block(address).await? else {
+            return Ok(None);
+        };
+
+        let header = self.block_header(BlockNumber::Number(block.into())).await?;
+
+        Ok(Some(header.timestamp.as_u64()))
+    }
+}
+
+#[async_trait]
+impl WalletActivity for Provider {
+    async fn get_transaction_count(
+        &self,
+        user_addresses: &[Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<u64, ProviderError>> {
+        let block = match self.block_id(snapshot).await {
+            Ok(Some(BlockId::Number(number))) => Some(number),
+            Ok(_) => None,
+            Err(e) => {
+                return user_addresses
+                    .iter()
+                    .map(|_| Err(ProviderError::Other(e.to_string())))
+                    .collect()
+            }
+        };
+
+        join_all(user_addresses.iter().map(|address| async move {
+            let count = self.single.eth().transaction_count(*address, block).await?;
+
+            Ok(count.low_u64())
+        }))
+        .await
+    }
+
+    async fn get_first_activity(
+        &self,
+        user_addresses: &[Address],
+    ) -> Vec<Result<Option<u64>, ProviderError>> {
+        join_all(
+            user_addresses
+                .iter()
+                .map(|address| self.first_activity(*address)),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WalletActivity;
+    use crate::{
+        address,
+        evm::general::Provider,
+        test_utils::{chain_config, json_rpc, serve},
+        transport::retry::RetryConfig,
+        Snapshot,
+    };
+
+    #[tokio::test]
+    async fn wallet_activity() {
+        let active = address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE");
+
+        // Blocks 0..=100 mined every 12 seconds starting at 1000, the active
+        // address receives funds in block 37 and sends 3 transactions in 60
+        let url = serve(json_rpc(move |method, params| {
+            let block = |param: &serde_json::Value| match param.as_str().unwrap() {
+                "latest" => 100,
+                "earliest" => 0,
+                hex => u64::from_str_radix(hex.trim_start_matches("0x"), 16).unwrap(),
+            };
+
+            match method {
+                "eth_getBlockByNumber" => {
+                    let number = block(&params[0]);
+
+                    serde_json::json!({
+                        "number": format!("{number:#x}"),
+                        "timestamp": format!("{:#x}", 1000 + 12 * number),
+                    })
+                }
+                "eth_getTransactionCount" | "eth_getBalance" => {
+                    let is_active = params[0].as_str().unwrap() == format!("{active:#x}");
+                    let value = match (method, block(&params[1])) {
+                        ("eth_getTransactionCount", 60..) => 3,
+                        ("eth_getBalance", 37..) => 1,
+                        _ => 0,
+                    };
+
+                    format!("{:#x}", if is_active { value } else { 0 }).into()
+                }
+                _ => serde_json::Value::Null,
+            }
+        }))
+        .await;
+
+        let provider = Provider::from_config(&chain_config(url), &RetryConfig::default()).unwrap();
+
+        let addresses = [
+            active,
+            address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503"),
+        ];
+
+        let counts = |snapshot| {
+            let provider = &provider;
+
+            async move {
+                provider
+                    .get_transaction_count(&addresses, snapshot)
+                    .await
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(counts(None).await, vec![3, 0]);
+        assert_eq!(counts(Some(Snapshot::Block(50))).await, vec![0, 0]);
+        assert_eq!(
+            provider
+                .get_first_activity(&addresses)
+                .await
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            vec![Some(1000 + 12 * 37), None]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+mod querier;
 pub mod types;
 
 use crate::{
@@ -5,69 +6,119 @@ use crate::{
         balancy::types::{AddressTokenResponse, BalancyError},
         EvmChain,
     },
+    transport::retry::{BackendRetry, ErrorClass},
     Address, U256,
 };
 use reqwest::StatusCode;
-use std::collections::HashMap;
 use tokio::sync::RwLock;
 
+pub use querier::BalancyQuerier;
+
 // Balancy
 const BASE_URL: &str = "https://balancy.guild.xyz/api";
 const ADDRESS_TOKENS: &str = "addressTokens?address=";
 const BALANCY_CHAIN: &str = "&chain=";
 
+// Chains indexed by Balancy, which uses the same ids as the chains
+const SUPPORTED_CHAINS: [EvmChain; 4] = [
+    EvmChain::Ethereum,
+    EvmChain::Polygon,
+    EvmChain::Bsc,
+    EvmChain::Gnosis,
+];
+
 lazy_static::lazy_static! {
     static ref CLIENT: RwLock<reqwest::Client> =
         RwLock::new(reqwest::Client::new());
-    static ref CHAIN_IDS: HashMap<u32, u32> = {
-        let mut h = HashMap::new();
+}
 
-        h.insert(EvmChain::Ethereum as u32, 1);
-        h.insert(EvmChain::Bsc as u32, 56);
-        h.insert(EvmChain::Gnosis as u32, 100);
-        h.insert(EvmChain::Polygon as u32, 137);
+fn classify(error: &BalancyError) -> Option<ErrorClass> {
+    match error {
+        BalancyError::TooManyRequests => Some(ErrorClass::RateLimited),
+        BalancyError::Unknown(500..=599) => Some(ErrorClass::ServerError),
+        BalancyError::Reqwest(e) if e.is_timeout() || e.is_connect() => Some(ErrorClass::Transport),
+        _ => None,
+    }
+}
 
-        h
-    };
+#[derive(Clone)]
+pub struct BalancyProvider {
+    base_url: String,
+    retry: BackendRetry,
 }
 
-pub async fn get_address_tokens(
-    chain: EvmChain,
-    address: Address,
-) -> Result<AddressTokenResponse, BalancyError> {
-    match CHAIN_IDS.get(&(chain as u32)) {
-        None => Err(BalancyError::ChainNotSupported(format!("{chain:?}"))),
-        Some(id) => {
-            let res = CLIENT
-                .read()
-                .await
-                .get(format!(
-                    "{BASE_URL}/{ADDRESS_TOKENS}{address:#x}{BALANCY_CHAIN}{id}"
-                ))
-                .send()
-                .await?;
-
-            let status = res.status();
-
-            match status {
-                StatusCode::OK => Ok(res.json::<AddressTokenResponse>().await?),
-                StatusCode::BAD_REQUEST => Err(BalancyError::InvalidBalancyRequest),
-                StatusCode::TOO_MANY_REQUESTS => Err(BalancyError::TooManyRequests),
-                _ => Err(BalancyError::Unknown(status.as_u16())),
-            }
-        }
+impl Default for BalancyProvider {
+    fn default() -> Self {
+        Self::new(BASE_URL)
     }
 }
 
-pub struct BalancyProvider;
-
 impl BalancyProvider {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            retry: BackendRetry::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: BackendRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn supports(chain: EvmChain) -> bool {
+        SUPPORTED_CHAINS.contains(&chain)
+    }
+
+    pub async fn get_address_tokens(
+        &self,
+        chain: EvmChain,
+        address: Address,
+    ) -> Result<AddressTokenResponse, BalancyError> {
+        if !Self::supports(chain) {
+            return Err(BalancyError::ChainNotSupported(format!("{chain:?}")));
+        }
+
+        self.retry
+            .run("Balancy request", classify, || {
+                self.fetch_address_tokens(chain.id(), address)
+            })
+            .await
+            .0
+    }
+
+    async fn fetch_address_tokens(
+        &self,
+        chain_id: u64,
+        address: Address,
+    ) -> Result<AddressTokenResponse, BalancyError> {
+        let res = CLIENT
+            .read()
+            .await
+            .get(format!(
+                "{}/{ADDRESS_TOKENS}{address:#x}{BALANCY_CHAIN}{chain_id}",
+                self.base_url
+            ))
+            .send()
+            .await?;
+
+        let status = res.status();
+
+        match status {
+            StatusCode::OK => Ok(res.json::<AddressTokenResponse>().await?),
+            StatusCode::BAD_REQUEST => Err(BalancyError::InvalidBalancyRequest),
+            StatusCode::TOO_MANY_REQUESTS => Err(BalancyError::TooManyRequests),
+            _ => Err(BalancyError::Unknown(status.as_u16())),
+        }
+    }
+
     pub async fn get_total_erc1155_of_address(
+        &self,
         chain: EvmChain,
         token_address: Address,
         user_address: Address,
     ) -> Result<U256, BalancyError> {
-        let body = get_address_tokens(chain, user_address).await?;
+        let body = self.get_address_tokens(chain, user_address).await?;
 
         let amount = body
             .erc1155
@@ -83,33 +134,43 @@ impl BalancyProvider {
 
 #[cfg(test)]
 mod test {
-    use crate::{
-        address,
-        evm::balancy::{get_address_tokens, BalancyProvider},
-        evm::EvmChain,
-    };
+    use super::{BalancyProvider, BASE_URL};
+    use crate::{address, test_utils::replay, EvmChain};
     use web3::types::U256;
 
-    #[tokio::test]
-    async fn balancy_address_tokens() {
-        assert!(get_address_tokens(
-            EvmChain::Ethereum,
-            address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")
+    async fn balancy(fixture: &str) -> BalancyProvider {
+        BalancyProvider::new(
+            replay(fixture, || {
+                std::env::var("BALANCY_URL").unwrap_or_else(|_| BASE_URL.to_string())
+            })
+            .await,
         )
-        .await
-        .is_ok());
     }
 
     #[tokio::test]
-    async fn balancy_total_erc1155_of_address() {
-        assert!(
-            BalancyProvider::get_total_erc1155_of_address(
+    async fn balancy_address_tokens() {
+        assert!(balancy("balancy_address_tokens")
+            .await
+            .get_address_tokens(
                 EvmChain::Ethereum,
-                address!("0x76be3b62873462d2142405439777e971754e8e77"),
-                address!("0x283d678711daa088640c86a1ad3f12c00ec1252e")
+                address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")
             )
             .await
-            .unwrap()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn balancy_total_erc1155_of_address() {
+        assert!(
+            balancy("balancy_total_erc1155_of_address")
+                .await
+                .get_total_erc1155_of_address(
+                    EvmChain::Ethereum,
+                    address!("0x76be3b62873462d2142405439777e971754e8e77"),
+                    address!("0x283d678711daa088640c86a1ad3f12c00ec1252e")
+                )
+                .await
+                .unwrap()
                 > U256::from_dec_str("5000").unwrap()
         );
     }
@@ -0,0 +1,265 @@
+use crate::{
+    evm::{
+        balancy::{
+            types::{AddressTokenResponse, BalancyError},
+            BalancyProvider,
+        },
+        general::{fail_all, Provider, ProviderError},
+        EvmChain,
+    },
+    Address, Amount, BalanceQuerier, Snapshot, U256,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::sync::Arc;
+
+/// Querier answering token balances from the `addressTokens` response of
+/// Balancy, a single request per address. Native balances and snapshots are
+/// not provided by Balancy. The decimals of ERC20 tokens come from the
+/// `token_decimals` of the chain config or the ones already read through
+/// RPC, only the others are read from the RPC provider of the chain, which
+/// fails along with it.
+pub struct BalancyQuerier {
+    balancy: BalancyProvider,
+    chain: EvmChain,
+    rpc: Arc<Provider>,
+}
+
+impl BalancyQuerier {
+    pub fn new(balancy: BalancyProvider, chain: EvmChain, rpc: Arc<Provider>) -> Self {
+        Self {
+            balancy,
+            chain,
+            rpc,
+        }
+    }
+
+    async fn balances<F>(
+        &self,
+        user_addresses: &[Address],
+        snapshot: Option<Snapshot>,
+        balance: F,
+    ) -> Vec<Result<U256, ProviderError>>
+    where
+        F: Fn(&AddressTokenResponse) -> U256,
+    {
+        if snapshot.is_some() {
+            return user_addresses
+                .iter()
+                .map(|_| Err(BalancyError::SnapshotNotSupported.into()))
+                .collect();
+        }
+
+        join_all(user_addresses.iter().map(|ua| async {
+            self.balancy
+                .get_address_tokens(self.chain, *ua)
+                .await
+                .map(|tokens| balance(&tokens))
+                .map_err(ProviderError::Balancy)
+        }))
+        .await
+    }
+}
+
+#[async_trait]
+impl BalanceQuerier for BalancyQuerier {
+    type Address = Address;
+    type Id = U256;
+    type Balance = Amount;
+    type Chain = EvmChain;
+    type Error = ProviderError;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        user_addresses
+            .iter()
+            .map(|_| Err(BalancyError::NotProvided("native balances").into()))
+            .collect()
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        let decimals = match self.rpc.decimals(token_address).await {
+            Ok(decimals) => decimals,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+
+        self.balances(user_addresses, snapshot, |tokens| {
+            tokens
+                .erc20
+                .iter()
+                .filter(|token| token.address == token_address)
+                .fold(U256::zero(), |sum, token| sum.saturating_add(token.amount))
+        })
+        .await
+        .into_iter()
+        .map(|res| res.map(|v| Amount::new(v, decimals)))
+        .collect()
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        self.balances(user_addresses, snapshot, |tokens| {
+            let owned = tokens
+                .erc721
+                .iter()
+                .filter(|token| {
+                    token.address == token_address && token_id.is_none_or(|id| token.token_id == id)
+                })
+                .count();
+
+            U256::from(owned)
+        })
+        .await
+        .into_iter()
+        .map(|res| res.map(|v| Amount::new(v, 0)))
+        .collect()
+    }
+
+    async fn get_special_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        self.balances(user_addresses, snapshot, |tokens| {
+            tokens
+                .erc1155
+                .iter()
+                .filter(|token| {
+                    token.addr == token_address && token_id.is_none_or(|id| token.token_id == id)
+                })
+                .fold(U256::zero(), |sum, token| sum.saturating_add(token.amount))
+        })
+        .await
+        .into_iter()
+        .map(|res| res.map(|v| Amount::new(v, 0)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BalancyQuerier;
+    use crate::{
+        address,
+        evm::{balancy::BalancyProvider, general::Provider, EvmChain},
+        test_utils::{chain_config, json_rpc, serve},
+        transport::retry::RetryConfig,
+        BalanceQuerier, Snapshot, U256,
+    };
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn balancy_querier() {
+        let token = address!("0x76be3b62873462d2142405439777e971754e8e77");
+        let nft = address!("0x57f1887a8bf19b14fc0df6fd9b2acc9af147ea85");
+        let user = address!("0x283d678711daa088640c86a1ad3f12c00ec1252e");
+
+        // GET requests are sent to Balancy, the `decimals` call to the RPC
+        let rpc = json_rpc(|_, _| format!("0x{:064x}", 2).into());
+        let url = serve(move |path, body| {
+            if body.is_empty() {
+                serde_json::json!({
+                    "erc20": [
+                        { "address": format!("{token:#x}"), "amount": "1500" },
+                        { "address": format!("{nft:#x}"), "amount": "7" },
+                    ],
+                    "erc721": [
+                        { "address": format!("{nft:#x}"), "tokenId": "1" },
+                        { "address": format!("{nft:#x}"), "tokenId": "2" },
+                    ],
+                    "erc1155": [
+                        { "Addr": format!("{token:#x}"), "TokenId": "3", "Amount": "4" },
+                    ],
+                })
+                .to_string()
+            } else {
+                rpc(path, body)
+            }
+        })
+        .await;
+
+        let config = chain_config(url.clone());
+        let rpc = Provider::from_config(&config, &RetryConfig::default()).unwrap();
+        let querier = BalancyQuerier::new(
+            BalancyProvider::new(url.clone()),
+            EvmChain::Ethereum,
+            Arc::new(rpc),
+        );
+
+        let erc20 = querier.get_fungible_balance(token, &[user], None).await;
+        let erc20 = erc20[0].as_ref().unwrap();
+
+        assert_eq!((erc20.raw(), erc20.decimals()), (U256::from(1500), 2));
+        assert_eq!(
+            querier
+                .get_non_fungible_balance(nft, None, &[user], None)
+                .await[0]
+                .as_ref()
+                .unwrap()
+                .raw(),
+            U256::from(2)
+        );
+        assert_eq!(
+            querier
+                .get_non_fungible_balance(nft, Some(U256::from(3)), &[user], None)
+                .await[0]
+                .as_ref()
+                .unwrap()
+                .raw(),
+            U256::zero()
+        );
+        assert_eq!(
+            querier
+                .get_special_balance(token, Some(U256::from(3)), &[user], None)
+                .await[0]
+                .as_ref()
+                .unwrap()
+                .raw(),
+            U256::from(4)
+        );
+        assert!(querier.get_native_balance(&[user], None).await[0].is_err());
+        assert!(querier
+            .get_fungible_balance(token, &[user], Some(Snapshot::Block(1)))
+            .await[0]
+            .is_err());
+
+        // Nothing listens on port 1, so only the configured decimals work
+        let dead_rpc = |token_decimals| {
+            let mut config = config.clone();
+            config.rpc_urls = vec!["http://127.0.0.1:1".to_string()];
+            config.token_decimals = token_decimals;
+
+            BalancyQuerier::new(
+                BalancyProvider::new(url.clone()),
+                EvmChain::Ethereum,
+                Arc::new(Provider::from_config(&config, &RetryConfig::default()).unwrap()),
+            )
+        };
+
+        let erc20 = dead_rpc([(token, 6)].into())
+            .get_fungible_balance(token, &[user], None)
+            .await;
+        let erc20 = erc20[0].as_ref().unwrap();
+
+        assert_eq!((erc20.raw(), erc20.decimals()), (U256::from(1500), 6));
+        assert!(dead_rpc(Default::default())
+            .get_fungible_balance(token, &[user], None)
+            .await[0]
+            .is_err());
+    }
+}
@@ -12,6 +12,10 @@ pub enum BalancyError {
     InvalidBalancyRequest,
     #[error("Too many requests to Balancy")]
     TooManyRequests,
+    #[error("Balancy does not provide {0}")]
+    NotProvided(&'static str),
+    #[error("Balancy does not support snapshots")]
+    SnapshotNotSupported,
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
     #[error("Got response with status code `{0}`")]
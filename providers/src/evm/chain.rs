@@ -0,0 +1,264 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use std::{str::FromStr, time::Duration};
+use thiserror::Error;
+
+#[derive(Serialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EvmChain {
+    Ethereum,
+    Polygon,
+    Gnosis,
+    Bsc,
+    Fantom,
+    Avalanche,
+    Heco,
+    Harmony,
+    Goerli,
+    Arbitrum,
+    Celo,
+    Optimism,
+    Moonriver,
+    Rinkeby,
+    Metis,
+    Cronos,
+    Boba,
+    Palm,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChainMetadata {
+    pub name: &'static str,
+    pub id: u64,
+    pub native_symbol: &'static str,
+    pub native_decimals: u8,
+    pub block_time: Duration,
+    pub explorer: &'static str,
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown chain `{0}`")]
+pub struct UnknownChain(String);
+
+const fn chain(
+    name: &'static str,
+    id: u64,
+    native_symbol: &'static str,
+    block_time_ms: u64,
+    explorer: &'static str,
+) -> ChainMetadata {
+    ChainMetadata {
+        name,
+        id,
+        native_symbol,
+        native_decimals: 18,
+        block_time: Duration::from_millis(block_time_ms),
+        explorer,
+    }
+}
+
+// The single source of chain ids, every backend derives its ids from here
+const CHAINS: [(EvmChain, ChainMetadata); 18] = [
+    (
+        EvmChain::Ethereum,
+        chain("ETHEREUM", 1, "ETH", 12_000, "https://etherscan.io"),
+    ),
+    (
+        EvmChain::Polygon,
+        chain("POLYGON", 137, "MATIC", 2_000, "https://polygonscan.com"),
+    ),
+    (
+        EvmChain::Gnosis,
+        chain("GNOSIS", 100, "XDAI", 5_000, "https://gnosisscan.io"),
+    ),
+    (
+        EvmChain::Bsc,
+        chain("BSC", 56, "BNB", 3_000, "https://bscscan.com"),
+    ),
+    (
+        EvmChain::Fantom,
+        chain("FANTOM", 250, "FTM", 1_000, "https://ftmscan.com"),
+    ),
+    (
+        EvmChain::Avalanche,
+        chain("AVALANCHE", 43114, "AVAX", 2_000, "https://snowtrace.io"),
+    ),
+    (
+        EvmChain::Heco,
+        chain("HECO", 128, "HT", 3_000, "https://hecoinfo.com"),
+    ),
+    (
+        EvmChain::Harmony,
+        chain(
+            "HARMONY",
+            1666600000,
+            "ONE",
+            2_000,
+            "https://explorer.harmony.one",
+        ),
+    ),
+    (
+        EvmChain::Goerli,
+        chain("GOERLI", 5, "ETH", 12_000, "https://goerli.etherscan.io"),
+    ),
+    (
+        EvmChain::Arbitrum,
+        chain("ARBITRUM", 42161, "ETH", 250, "https://arbiscan.io"),
+    ),
+    (
+        EvmChain::Celo,
+        chain("CELO", 42220, "CELO", 5_000, "https://celoscan.io"),
+    ),
+    (
+        EvmChain::Optimism,
+        chain(
+            "OPTIMISM",
+            10,
+            "ETH",
+            2_000,
+            "https://optimistic.etherscan.io",
+        ),
+    ),
+    (
+        EvmChain::Moonriver,
+        chain(
+            "MOONRIVER",
+            1285,
+            "MOVR",
+            12_000,
+            "https://moonriver.moonscan.io",
+        ),
+    ),
+    (
+        EvmChain::Rinkeby,
+        chain("RINKEBY", 4, "ETH", 15_000, "https://rinkeby.etherscan.io"),
+    ),
+    (
+        EvmChain::Metis,
+        chain(
+            "METIS",
+            1088,
+            "METIS",
+            4_000,
+            "https://andromeda-explorer.metis.io",
+        ),
+    ),
+    (
+        EvmChain::Cronos,
+        chain("CRONOS", 25, "CRO", 6_000, "https://cronoscan.com"),
+    ),
+    (
+        EvmChain::Boba,
+        chain("BOBA", 288, "ETH", 2_000, "https://bobascan.com"),
+    ),
+    (
+        EvmChain::Palm,
+        chain(
+            "PALM",
+            11297108109,
+            "PALM",
+            5_000,
+            "https://explorer.palm.io",
+        ),
+    ),
+];
+
+impl EvmChain {
+    pub fn all() -> impl Iterator<Item = EvmChain> {
+        CHAINS.iter().map(|(chain, _)| *chain)
+    }
+
+    pub fn metadata(&self) -> &'static ChainMetadata {
+        CHAINS
+            .iter()
+            .find(|(chain, _)| chain == self)
+            .map(|(_, metadata)| metadata)
+            .expect("This should be fine")
+    }
+
+    pub fn id(&self) -> u64 {
+        self.metadata().id
+    }
+
+    pub fn from_id(id: u64) -> Option<Self> {
+        CHAINS
+            .iter()
+            .find(|(_, metadata)| metadata.id == id)
+            .map(|(chain, _)| *chain)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        CHAINS
+            .iter()
+            .find(|(_, metadata)| metadata.name == name)
+            .map(|(chain, _)| *chain)
+    }
+}
+
+impl FromStr for EvmChain {
+    type Err = UnknownChain;
+
+    // Like a JSON string, a string of digits is not taken for a chain id
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| UnknownChain(s.to_string()))
+    }
+}
+
+// Chains are deserialized from their name or from their chain id as a
+// number, a string of digits is not taken for an id
+impl<'de> Deserialize<'de> for EvmChain {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ChainRef {
+            Id(u64),
+            Name(String),
+        }
+
+        match ChainRef::deserialize(deserializer)? {
+            ChainRef::Id(id) => Self::from_id(id).ok_or_else(|| UnknownChain(id.to_string())),
+            ChainRef::Name(name) => Self::from_name(&name).ok_or(UnknownChain(name)),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EvmChain;
+    use std::collections::HashSet;
+
+    #[test]
+    fn chain_metadata() {
+        assert_eq!(EvmChain::all().count(), 18);
+        assert_eq!(
+            EvmChain::all()
+                .map(|chain| chain.id())
+                .collect::<HashSet<_>>()
+                .len(),
+            18
+        );
+
+        for chain in EvmChain::all() {
+            assert_eq!(serde_json::to_value(chain).unwrap(), chain.metadata().name);
+            assert_eq!(EvmChain::from_id(chain.id()), Some(chain));
+        }
+
+        assert_eq!(EvmChain::Polygon.metadata().native_symbol, "MATIC");
+        assert_eq!(
+            serde_json::from_str::<EvmChain>("\"GNOSIS\"").unwrap(),
+            EvmChain::Gnosis
+        );
+        assert_eq!(
+            serde_json::from_str::<EvmChain>("42161").unwrap(),
+            EvmChain::Arbitrum
+        );
+        assert_eq!("BSC".parse::<EvmChain>().unwrap(), EvmChain::Bsc);
+        assert!("56".parse::<EvmChain>().is_err());
+        assert!(serde_json::from_str::<EvmChain>("31337").is_err());
+        assert!(serde_json::from_str::<EvmChain>("\"56\"").is_err());
+        assert!("NOT_A_CHAIN".parse::<EvmChain>().is_err());
+    }
+}
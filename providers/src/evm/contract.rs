@@ -0,0 +1,245 @@
+use crate::{
+    evm::{
+        general::{Provider, ProviderError},
+        multicall::{self, Call},
+    },
+    Address, Snapshot,
+};
+use async_trait::async_trait;
+use web3::ethabi::{
+    param_type::Reader,
+    token::{LenientTokenizer, Tokenizer},
+    Function, ParamType,
+};
+
+pub use web3::ethabi::Token;
+
+/// Read-only function of a contract given by its signature, like
+/// `balanceOf(address)`, along with the type of the single value it returns.
+#[derive(Debug, Clone)]
+pub struct ViewCall {
+    function: Function,
+}
+
+// Splits the parameter list of a signature at the commas outside of tuples
+fn split_params(params: &str) -> Vec<&str> {
+    let (mut parts, mut depth, mut start) = (vec![], 0, 0);
+
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&params[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn is_hex(kind: &ParamType) -> bool {
+    matches!(
+        kind,
+        ParamType::Address | ParamType::Bytes | ParamType::FixedBytes(_)
+    )
+}
+
+// The tokenizer takes addresses and bytes in hex without the `0x` prefix
+fn without_hex_prefix(kind: &ParamType, arg: &str) -> String {
+    match kind {
+        kind if is_hex(kind) => arg.trim().trim_start_matches("0x").to_string(),
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) if is_hex(inner) => {
+            arg.replace("0x", "")
+        }
+        _ => arg.to_string(),
+    }
+}
+
+fn param_type(name: &str) -> Result<ParamType, ProviderError> {
+    match Reader::read(name)? {
+        // Tuples need their components spelled out in the ABI
+        ParamType::Tuple(_) => Err(ProviderError::Other(format!(
+            "Tuple parameters are not supported: `{name}`"
+        ))),
+        kind => Ok(kind),
+    }
+}
+
+impl ViewCall {
+    pub fn new(signature: &str, returns: &str) -> Result<Self, ProviderError> {
+        let invalid = || ProviderError::Other(format!("Invalid function signature `{signature}`"));
+
+        let (name, params) = signature.trim().split_once('(').ok_or_else(invalid)?;
+        let params = params.strip_suffix(')').ok_or_else(invalid)?;
+
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+
+        let param = |kind: ParamType| serde_json::json!({ "name": "", "type": kind.to_string() });
+        let inputs = split_params(params)
+            .into_iter()
+            .map(|p| param_type(p).map(param))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let function = serde_json::from_value(serde_json::json!({
+            "type": "function",
+            "name": name,
+            "inputs": inputs,
+            "outputs": [param(param_type(returns)?)],
+            "stateMutability": "view",
+        }))
+        .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        Ok(Self { function })
+    }
+
+    /// Call data with the arguments given in their text form.
+    pub fn encode(&self, args: &[String]) -> Result<Vec<u8>, ProviderError> {
+        if args.len() != self.function.inputs.len() {
+            return Err(ProviderError::Other(format!(
+                "`{}` takes {} arguments, got {}",
+                self.function.signature(),
+                self.function.inputs.len(),
+                args.len()
+            )));
+        }
+
+        let tokens = self
+            .function
+            .inputs
+            .iter()
+            .zip(args)
+            .map(|(input, arg)| {
+                LenientTokenizer::tokenize(&input.kind, &without_hex_prefix(&input.kind, arg))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self.function.encode_input(&tokens)?)
+    }
+
+    pub fn returns_number(&self) -> bool {
+        matches!(
+            self.function.outputs[0].kind,
+            ParamType::Uint(_) | ParamType::Int(_)
+        )
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Token, ProviderError> {
+        self.function
+            .decode_output(data)?
+            .pop()
+            .ok_or_else(|| ProviderError::Other("Empty return data".into()))
+    }
+}
+
+/// Executes read-only calls against contracts of a chain.
+#[async_trait]
+pub trait ContractCaller {
+    /// Return data of each call to the contract, in the order of the calls.
+    async fn call(
+        &self,
+        contract: Address,
+        calls: Vec<Vec<u8>>,
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Vec<u8>, ProviderError>>;
+
+    /// Decimals of an ERC20 token.
+    async fn decimals(&self, token: Address) -> Result<u8, ProviderError> {
+        let call = ViewCall::new("decimals()", "uint8")?;
+
+        let data = self
+            .call(token, vec![call.encode(&[])?], None)
+            .await
+            .pop()
+            .ok_or_else(|| ProviderError::Other("Missing return data".into()))??;
+
+        match call.decode(&data)?.into_uint() {
+            Some(decimals) => Ok(decimals.low_u32() as u8),
+            None => Err(ProviderError::Other("Invalid decimals".into())),
+        }
+    }
+}
+
+#[async_trait]
+impl ContractCaller for Provider {
+    async fn call(
+        &self,
+        contract: Address,
+        calls: Vec<Vec<u8>>,
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Vec<u8>, ProviderError>> {
+        let block = match self.block_id(snapshot).await {
+            Ok(block) => block,
+            Err(e) => {
+                return calls
+                    .iter()
+                    .map(|_| Err(ProviderError::Other(e.to_string())))
+                    .collect()
+            }
+        };
+
+        let calls: Vec<Call> = calls
+            .into_iter()
+            .map(|data| Call {
+                target: contract,
+                data,
+            })
+            .collect();
+
+        multicall::aggregate(&self.single, self.multi.address, &calls, block).await
+    }
+
+    // Cached and taken from the config when listed there
+    async fn decimals(&self, token: Address) -> Result<u8, ProviderError> {
+        Provider::decimals(self, token).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ViewCall;
+    use crate::{address, U256};
+    use web3::{
+        ethabi::{encode, Token},
+        signing::keccak256,
+    };
+
+    #[test]
+    fn view_call() {
+        let call = ViewCall::new("stakedOf(address, uint256)", "uint256").unwrap();
+        let data = call
+            .encode(&[
+                "0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE".into(),
+                "7".into(),
+            ])
+            .unwrap();
+
+        assert_eq!(data[..4], keccak256(b"stakedOf(address,uint256)")[..4]);
+        assert_eq!(
+            &data[4..],
+            encode(&[
+                Token::Address(address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE")),
+                Token::Uint(U256::from(7)),
+            ])
+        );
+        assert_eq!(
+            call.decode(&encode(&[Token::Uint(U256::from(42))]))
+                .unwrap(),
+            Token::Uint(U256::from(42))
+        );
+
+        assert!(call.encode(&["7".into()]).is_err());
+        assert!(ViewCall::new("isMember(address", "bool").is_err());
+        assert!(ViewCall::new("isMember(address)", "(bool,uint256)").is_err());
+        assert!(ViewCall::new("totalSupply()", "uint256").is_ok());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    address,
+    evm::{
+        general::{Provider, ProviderError},
+        DELEGATE_REGISTRY_ABI, DELEGATE_REGISTRY_V2_ABI,
+    },
+    Address,
+};
+use async_trait::async_trait;
+use web3::{
+    ethabi::{Contract as Abi, Token},
+    types::{Bytes, CallRequest, H256},
+};
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Address = address!("0x00000000000076A84feF008CDAbe6409d2FE638B");
+    static ref REGISTRY_V2: Address = address!("0x00000000000000447e69651d841bD8D104Bed493");
+    static ref V1: Abi = Abi::load(DELEGATE_REGISTRY_ABI).expect("Invalid DelegateRegistry ABI");
+    static ref V2: Abi =
+        Abi::load(DELEGATE_REGISTRY_V2_ABI).expect("Invalid DelegateRegistryV2 ABI");
+}
+
+// `ALL` and `CONTRACT` in the `DelegationType` enum of both versions, token
+// level delegations don't cover the rest of the holdings of vaults
+const DELEGATE_ALL: u64 = 1;
+const DELEGATE_CONTRACT: u64 = 2;
+
+/// Finds the vaults that delegated to addresses through the delegate.cash
+/// registries, deployed at the same addresses on every chain.
+#[async_trait]
+pub trait DelegationQuerier {
+    /// Vaults that delegated all of their rights or the rights for the
+    /// contract to the address, the zero address standing for native coins.
+    async fn get_vaults(
+        &self,
+        delegate: Address,
+        contract: Address,
+    ) -> Result<Vec<Address>, ProviderError>;
+}
+
+// Registry of a version along with the positions of the vault and contract
+// fields in the delegations it returns
+struct Registry {
+    address: Address,
+    abi: &'static Abi,
+    enumerate: &'static str,
+    vault: usize,
+    contract: usize,
+}
+
+impl Registry {
+    fn v1() -> Self {
+        Self {
+            address: *REGISTRY,
+            abi: &V1,
+            enumerate: "getDelegationsByDelegate",
+            vault: 1,
+            contract: 3,
+        }
+    }
+
+    fn v2() -> Self {
+        Self {
+            address: *REGISTRY_V2,
+            abi: &V2,
+            enumerate: "getIncomingDelegations",
+            vault: 2,
+            contract: 4,
+        }
+    }
+
+    // Arguments of `checkDelegateForContract`, v2 takes the empty rights
+    // standing for full delegations as well
+    fn check_args(&self, delegate: Address, vault: Address, contract: Address) -> Vec<Token> {
+        let mut args = vec![
+            Token::Address(delegate),
+            Token::Address(vault),
+            Token::Address(contract),
+        ];
+        if self.address == *REGISTRY_V2 {
+            args.push(Token::FixedBytes(H256::zero().as_bytes().to_vec()));
+        }
+
+        args
+    }
+
+    // Vaults of the delegations covering the contract
+    fn candidates(&self, delegations: Token, contract: Address) -> Vec<Address> {
+        let Token::Array(delegations) = delegations else {
+            return vec![];
+        };
+
+        delegations
+            .into_iter()
+            .filter_map(|delegation| {
+                let Token::Tuple(fields) = delegation else {
+                    return None;
+                };
+
+                let covers = match (&fields[0], &fields[self.contract]) {
+                    (Token::Uint(kind), _) if kind.as_u64() == DELEGATE_ALL => true,
+                    (Token::Uint(kind), Token::Address(target)) => {
+                        kind.as_u64() == DELEGATE_CONTRACT && *target == contract
+                    }
+                    _ => false,
+                };
+
+                match &fields[self.vault] {
+                    Token::Address(vault) if covers => Some(*vault),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Provider {
+    // Single value returned by a registry function, `None` on chains the
+    // registry isn't deployed to
+    async fn registry_call(
+        &self,
+        registry: &Registry,
+        function: &str,
+        args: &[Token],
+    ) -> Result<Option<Token>, ProviderError> {
+        let function = registry.abi.function(function)?;
+        let output = self
+            .single
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(registry.address),
+                    data: Some(Bytes(function.encode_input(args)?)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        if output.0.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(function.decode_output(&output.0)?.into_iter().next())
+    }
+
+    async fn registry_vaults(
+        &self,
+        registry: &Registry,
+        delegate: Address,
+        contract: Address,
+    ) -> Result<Vec<Address>, ProviderError> {
+        let Some(delegations) = self
+            .registry_call(registry, registry.enumerate, &[Token::Address(delegate)])
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+
+        let mut vaults = vec![];
+
+        // Revoked delegations may linger in the enumeration of v1
+        for vault in registry.candidates(delegations, contract) {
+            let args = registry.check_args(delegate, vault, contract);
+
+            if let Some(Token::Bool(true)) = self
+                .registry_call(registry, "checkDelegateForContract", &args)
+                .await?
+            {
+                vaults.push(vault);
+            }
+        }
+
+        Ok(vaults)
+    }
+}
+
+#[async_trait]
+impl DelegationQuerier for Provider {
+    async fn get_vaults(
+        &self,
+        delegate: Address,
+        contract: Address,
+    ) -> Result<Vec<Address>, ProviderError> {
+        let (v1, v2) = (Registry::v1(), Registry::v2());
+        let (v1, v2) = futures::try_join!(
+            self.registry_vaults(&v1, delegate, contract),
+            self.registry_vaults(&v2, delegate, contract)
+        )?;
+
+        let mut vaults = v1;
+        for vault in v2 {
+            if !vaults.contains(&vault) {
+                vaults.push(vault);
+            }
+        }
+
+        Ok(vaults)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DelegationQuerier, REGISTRY, REGISTRY_V2, V1, V2};
+    use crate::{
+        address,
+        evm::general::Provider,
+        test_utils::{chain_config, json_rpc, serve},
+        transport::retry::RetryConfig,
+        Address, U256,
+    };
+    use web3::ethabi::{encode, Token};
+
+    #[tokio::test]
+    async fn delegated_vaults() {
+        let delegate = address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE");
+        let token = address!("0x57f1887a8BF19b14fC0dF6Fd9B2acc9Af147eA85");
+        let (all, contract, other, revoked) = (
+            address!("0x849D52316331967b6fF1198e5E32A0eB168D039d"),
+            address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503"),
+            address!("0x2c00000000000000000000000000000000000000"),
+            address!("0x3c00000000000000000000000000000000000000"),
+        );
+
+        let selector = |abi: &web3::ethabi::Contract, name: &str| {
+            let selector = abi.function(name).unwrap().short_signature();
+            format!("0x{}", hex(&selector))
+        };
+        let (enumerate_v1, check_v1, enumerate_v2, check_v2) = (
+            selector(&V1, "getDelegationsByDelegate"),
+            selector(&V1, "checkDelegateForContract"),
+            selector(&V2, "getIncomingDelegations"),
+            selector(&V2, "checkDelegateForContract"),
+        );
+
+        // On v1 the first vault delegated everything, the second one the
+        // token, the third one another contract and the last one revoked
+        // its delegation, on v2 the first vault delegated everything again
+        let url = serve(json_rpc(move |_, params| {
+            let to: Address = params[0]["to"].as_str().unwrap().parse().unwrap();
+            let data = params[0]["data"].as_str().unwrap();
+            let (selector, argument) = data.split_at(10);
+            let word = |i: usize| &argument[i * 64 + 24..(i + 1) * 64];
+            let v1 = |kind: u64, vault: Address, target: Address| {
+                Token::Tuple(vec![
+                    Token::Uint(U256::from(kind)),
+                    Token::Address(vault),
+                    Token::Address(delegate),
+                    Token::Address(target),
+                    Token::Uint(U256::zero()),
+                ])
+            };
+
+            let token = match selector {
+                s if s == enumerate_v1 && to == *REGISTRY => Token::Array(vec![
+                    v1(1, all, Address::zero()),
+                    v1(2, contract, token),
+                    v1(2, other, all),
+                    v1(1, revoked, Address::zero()),
+                ]),
+                s if s == check_v1 && to == *REGISTRY => {
+                    Token::Bool(word(1) != format!("{revoked:x}"))
+                }
+                s if s == enumerate_v2 && to == *REGISTRY_V2 => {
+                    Token::Array(vec![Token::Tuple(vec![
+                        Token::Uint(U256::one()),
+                        Token::Address(delegate),
+                        Token::Address(all),
+                        Token::FixedBytes(vec![0; 32]),
+                        Token::Address(Address::zero()),
+                        Token::Uint(U256::zero()),
+                        Token::Uint(U256::zero()),
+                    ])])
+                }
+                s if s == check_v2 && to == *REGISTRY_V2 => Token::Bool(true),
+                _ => return "0x".into(),
+            };
+
+            format!("0x{}", hex(&encode(&[token]))).into()
+        }))
+        .await;
+
+        let provider = Provider::from_config(&chain_config(url), &RetryConfig::default()).unwrap();
+
+        assert_eq!(
+            provider.get_vaults(delegate, token).await.unwrap(),
+            vec![all, contract]
+        );
+        assert_eq!(
+            provider
+                .get_vaults(delegate, Address::zero())
+                .await
+                .unwrap(),
+            vec![all]
+        );
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
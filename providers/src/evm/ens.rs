@@ -0,0 +1,329 @@
+use crate::{
+    address,
+    evm::{
+        general::{Provider, ProviderError},
+        ENS_REGISTRY_ABI, ENS_RESOLVER_ABI, ERC721_ABI, NAME_WRAPPER_ABI,
+    },
+    Address, U256,
+};
+use async_trait::async_trait;
+use std::time::{SystemTime, UNIX_EPOCH};
+use web3::{
+    contract::{tokens::Detokenize, Contract, Error, Options},
+    signing::keccak256,
+    types::H256,
+};
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Address = address!("0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+    static ref BASE_REGISTRAR: Address = address!("0x57f1887a8BF19b14fC0dF6Fd9B2acc9Af147eA85");
+    static ref NAME_WRAPPER: Address = address!("0xD4416b13d2b3a9aBae7AcD5D6C2BbDBE25686401");
+}
+
+/// Resolves ENS names through the registry deployed on Ethereum mainnet.
+#[async_trait]
+pub trait EnsResolver {
+    /// Address the name points to, `None` if it has no resolver or address.
+    async fn resolve(&self, name: &str) -> Result<Option<Address>, ProviderError>;
+
+    /// Owner of the name: the registrant of `.eth` names, the owner of the
+    /// wrapped token for names held by the NameWrapper, `None` once the name
+    /// expired.
+    async fn owner(&self, name: &str) -> Result<Option<Address>, ProviderError>;
+
+    /// Primary name of the address, only if it resolves back to the address.
+    async fn primary_name(&self, address: Address) -> Result<Option<String>, ProviderError>;
+}
+
+/// Names are compared in lowercase, full UTS-46 normalization is left to
+/// the clients registering them.
+pub fn normalize(name: &str) -> String {
+    name.trim().trim_end_matches('.').to_lowercase()
+}
+
+pub fn namehash(name: &str) -> H256 {
+    let node = normalize(name)
+        .rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold([0u8; 32], |node, label| {
+            keccak256(&[node, keccak256(label.as_bytes())].concat())
+        });
+
+    H256(node)
+}
+
+fn non_zero(address: Address) -> Option<Address> {
+    (!address.is_zero()).then_some(address)
+}
+
+// Other errors of the node (e.g. a pruned state or a missing block) don't
+// tell anything about the name
+fn is_revert(error: &Error) -> bool {
+    match error {
+        Error::Api(web3::Error::Rpc(e)) => {
+            e.code.code() == 3 || e.message.contains("execution reverted")
+        }
+        _ => false,
+    }
+}
+
+// Only called on the provider of `EvmChain::Ethereum`
+impl Provider {
+    async fn ens_query<R: Detokenize>(
+        &self,
+        contract: Address,
+        abi: &[u8],
+        function: &str,
+        node: H256,
+    ) -> Result<R, ProviderError> {
+        let contract = Contract::from_json(self.single.eth(), contract, abi)?;
+
+        Ok(contract
+            .query(function, (node,), None, Options::default(), None)
+            .await?)
+    }
+
+    async fn ens_resolver(&self, node: H256) -> Result<Option<Address>, ProviderError> {
+        let resolver = self
+            .ens_query(*REGISTRY, ENS_REGISTRY_ABI, "resolver", node)
+            .await?;
+
+        Ok(non_zero(resolver))
+    }
+
+    async fn token_query<R: Detokenize>(
+        &self,
+        contract: Address,
+        abi: &[u8],
+        function: &str,
+        id: U256,
+    ) -> Result<R, Error> {
+        let contract = Contract::from_json(self.single.eth(), contract, abi)?;
+
+        contract
+            .query(function, (id,), None, Options::default(), None)
+            .await
+    }
+}
+
+#[async_trait]
+impl EnsResolver for Provider {
+    async fn resolve(&self, name: &str) -> Result<Option<Address>, ProviderError> {
+        let node = namehash(name);
+
+        let Some(resolver) = self.ens_resolver(node).await? else {
+            return Ok(None);
+        };
+
+        let address = self
+            .ens_query(resolver, ENS_RESOLVER_ABI, "addr", node)
+            .await?;
+
+        Ok(non_zero(address))
+    }
+
+    async fn owner(&self, name: &str) -> Result<Option<Address>, ProviderError> {
+        let name = normalize(name);
+        let node = namehash(&name);
+
+        // The registry keeps the last owner of expired `.eth` names, while
+        // the BaseRegistrar token of their label reverts once they expire
+        let owner: Address = match name.strip_suffix(".eth").filter(|l| !l.contains('.')) {
+            Some(label) => {
+                let id = U256::from_big_endian(&keccak256(label.as_bytes()));
+
+                match self
+                    .token_query(*BASE_REGISTRAR, ERC721_ABI, "ownerOf", id)
+                    .await
+                {
+                    Ok(owner) => owner,
+                    Err(e) if is_revert(&e) => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            None => {
+                self.ens_query(*REGISTRY, ENS_REGISTRY_ABI, "owner", node)
+                    .await?
+            }
+        };
+
+        if owner != *NAME_WRAPPER {
+            return Ok(non_zero(owner));
+        }
+
+        // Wrapped names are ERC1155 tokens with the namehash as their id,
+        // which keep their owner after expiring unless emancipated. Names
+        // wrapped without an expiry, like most subnames, never expire.
+        let (owner, _, expiry): (Address, U256, U256) = self
+            .token_query(
+                *NAME_WRAPPER,
+                NAME_WRAPPER_ABI,
+                "getData",
+                U256::from_big_endian(node.as_bytes()),
+            )
+            .await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("This should be fine")
+            .as_secs();
+
+        if !expiry.is_zero() && expiry <= U256::from(now) {
+            return Ok(None);
+        }
+
+        Ok(non_zero(owner))
+    }
+
+    async fn primary_name(&self, address: Address) -> Result<Option<String>, ProviderError> {
+        let node = namehash(&format!("{address:x}.addr.reverse"));
+
+        let Some(resolver) = self.ens_resolver(node).await? else {
+            return Ok(None);
+        };
+
+        let name: String = self
+            .ens_query(resolver, ENS_RESOLVER_ABI, "name", node)
+            .await?;
+
+        // Anyone can claim any name in their reverse record
+        if name.is_empty() || self.resolve(&name).await? != Some(address) {
+            return Ok(None);
+        }
+
+        Ok(Some(normalize(&name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{namehash, EnsResolver};
+    use crate::{
+        address,
+        evm::general::Provider,
+        test_utils::{chain_config, serve},
+        transport::retry::RetryConfig,
+        Address, U256,
+    };
+    use web3::{
+        ethabi::{encode, Token},
+        signing::keccak256,
+        types::H256,
+    };
+
+    #[tokio::test]
+    async fn ens_resolution() {
+        assert_eq!(namehash(""), H256::zero());
+        assert_eq!(
+            namehash("ETH"),
+            "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            namehash("foo.eth"),
+            "0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+                .parse()
+                .unwrap()
+        );
+
+        let holder = address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE");
+        let resolver = address!("0x231b0Ee14048e9dCcD1d247744d114a4EB5E8E63");
+        let registrar = address!("0x57f1887a8BF19b14fC0dF6Fd9B2acc9Af147eA85");
+        let wrapper = address!("0xD4416b13d2b3a9aBae7AcD5D6C2BbDBE25686401");
+        let node = |name: &str| format!("{:x}", namehash(name));
+        let label = |label: &str| hex(&keccak256(label.as_bytes()));
+        let (vitalik, reverse, wrapped, sub, expired_sub) = (
+            node("vitalik.eth"),
+            node(&format!("{holder:x}.addr.reverse")),
+            node("wrapped.eth"),
+            node("sub.wrapped.eth"),
+            node("old.wrapped.eth"),
+        );
+        let (vitalik_label, wrapped_label, pruned_label) =
+            (label("vitalik"), label("wrapped"), label("pruned"));
+
+        // `vitalik.eth` is registered to and resolves to the holder, who set
+        // it as their primary name, `wrapped.eth` and its subnames are held by
+        // the NameWrapper, the state of `pruned.eth` is missing and other
+        // `.eth` names expired
+        let url = serve(move |_, body| {
+            let request: serde_json::Value = serde_json::from_str(body).unwrap();
+            let params = &request["params"];
+            let to: Address = params[0]["to"].as_str().unwrap().parse().unwrap();
+            let data = params[0]["data"].as_str().unwrap();
+            let (selector, argument) = data.split_at(10);
+            let address = |address: Address| vec![Token::Address(address)];
+            let wrapped_data = |expiry: u64| {
+                vec![
+                    Token::Address(holder),
+                    Token::Uint(U256::zero()),
+                    Token::Uint(U256::from(expiry)),
+                ]
+            };
+
+            let tokens = match (selector, argument) {
+                // resolver(bytes32) and addr(bytes32)
+                ("0x0178b8bf", a) if a == vitalik || a == reverse => address(resolver),
+                ("0x3b3b57de", a) if a == vitalik && to == resolver => address(holder),
+                // ownerOf(uint256) of the BaseRegistrar
+                ("0x6352211e", a) if a == vitalik_label && to == registrar => address(holder),
+                ("0x6352211e", a) if a == wrapped_label && to == registrar => address(wrapper),
+                ("0x6352211e", a) if a == pruned_label && to == registrar => {
+                    return serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": -32000, "message": "missing trie node" },
+                    })
+                    .to_string();
+                }
+                ("0x6352211e", _) if to == registrar => {
+                    return serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": 3, "message": "execution reverted" },
+                    })
+                    .to_string();
+                }
+                // owner(bytes32) and getData(uint256)
+                ("0x02571be3", a) if a == sub || a == expired_sub => address(wrapper),
+                ("0x0178fe3f", a) if a == wrapped && to == wrapper => wrapped_data(u64::MAX),
+                ("0x0178fe3f", a) if a == sub && to == wrapper => wrapped_data(0),
+                ("0x0178fe3f", a) if a == expired_sub && to == wrapper => wrapped_data(1),
+                // name(bytes32)
+                ("0x691f3431", a) if a == reverse => vec![Token::String("Vitalik.eth".into())],
+                _ => address(Address::zero()),
+            };
+
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": format!("0x{}", hex(&encode(&tokens))),
+            })
+            .to_string()
+        })
+        .await;
+
+        let provider = Provider::from_config(&chain_config(url), &RetryConfig::default()).unwrap();
+
+        assert_eq!(provider.resolve("vitalik.eth").await.unwrap(), Some(holder));
+        assert_eq!(provider.resolve("nobody.eth").await.unwrap(), None);
+        assert_eq!(provider.owner("vitalik.eth").await.unwrap(), Some(holder));
+        assert_eq!(provider.owner("wrapped.eth").await.unwrap(), Some(holder));
+        assert_eq!(provider.owner("expired.eth").await.unwrap(), None);
+        assert!(provider.owner("pruned.eth").await.is_err());
+        assert_eq!(
+            provider.owner("sub.wrapped.eth").await.unwrap(),
+            Some(holder)
+        );
+        assert_eq!(provider.owner("old.wrapped.eth").await.unwrap(), None);
+        assert_eq!(
+            provider.primary_name(holder).await.unwrap().as_deref(),
+            Some("vitalik.eth")
+        );
+        assert_eq!(provider.primary_name(resolver).await.unwrap(), None);
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
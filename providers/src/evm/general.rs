@@ -1,43 +1,91 @@
 use crate::{
-    address,
+    cache::{BalanceCache, CachedQuerier, MissingBalance, TtlCache},
+    config::{BalancyMode, ChainConfig, ProvidersConfig},
+    cosmos::CosmosProvider,
     evm::{
-        balancy::{types::BalancyError, BalancyProvider},
+        balancy::{types::BalancyError, BalancyProvider, BalancyQuerier},
+        multicall::{self, Call},
+        price::{PriceError, PriceFeeds},
+        safe::{SafeError, SafeService},
         EvmChain, ERC1155_ABI, ERC20_ABI, ERC721_ABI,
     },
-    Address, BalanceQuerier, U256,
+    solana::SolanaProvider,
+    substrate::SubstrateProvider,
+    transport::{limiter::RequestLimits, retry::RetryConfig, FailoverTransport},
+    Address, Amount, BalanceQuerier, CosmosQuerier, EvmQuerier, FallbackQuerier, ProviderContext,
+    Snapshot, SolanaQuerier, U256,
 };
 use async_trait::async_trait;
 use futures::future::join_all;
-use std::{collections::HashMap, sync::Arc};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use web3::{
     contract::{Contract, Options},
-    transports::Http,
-    Web3,
+    ethabi::{Contract as Abi, Token},
+    helpers,
+    types::{BlockId, BlockNumber, U64},
+    Transport, Web3,
 };
 
-type Balance = f64;
+// Blocks of past timestamps and the first activities of addresses never
+// change, so the ones found are only dropped to bound the memory they take
+const HISTORY_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const HISTORY_CACHE_SIZE: usize = 10_000;
 
 pub struct MulticallParams {
-    pub rpc_url: String,
     pub address: Address,
 }
 
 pub struct Provider {
-    chain: EvmChain,
-    pub single: Web3<Http>,
+    pub(crate) chain: EvmChain,
+    balancy: BalancyProvider,
+    reprobe_interval: Duration,
+    resolved_timestamps: TtlCache<u64, u64>,
+    pub(crate) first_activity: TtlCache<Address, u64>,
+    pub(crate) safe: Option<SafeService>,
+    pub(crate) prices: PriceFeeds,
+    decimals: Mutex<HashMap<Address, u8>>,
+    pub single: Web3<FailoverTransport>,
     pub multi: MulticallParams,
 }
 
 impl Provider {
-    pub fn new(chain: EvmChain, rpc_url: String, address: Address) -> Self {
-        Self {
-            chain,
-            single: match Http::new(&rpc_url) {
-                Ok(transport) => Web3::new(transport),
-                Err(e) => panic!("{e}"),
+    pub fn from_config(config: &ChainConfig, retry: &RetryConfig) -> Result<Self, ProviderError> {
+        let transport = FailoverTransport::new(
+            &config.rpc_urls,
+            Duration::from_millis(config.timeout_ms),
+            RequestLimits {
+                max_concurrent_requests: config.max_concurrent_requests,
+                requests_per_second: config.requests_per_second,
             },
-            multi: MulticallParams { rpc_url, address },
-        }
+            retry.rpc,
+        )?;
+
+        Ok(Self {
+            chain: config.chain,
+            balancy: BalancyProvider::default().with_retry(retry.balancy),
+            reprobe_interval: Duration::from_secs(config.reprobe_interval_secs),
+            resolved_timestamps: TtlCache::new(HISTORY_CACHE_TTL, HISTORY_CACHE_SIZE),
+            first_activity: TtlCache::new(HISTORY_CACHE_TTL, HISTORY_CACHE_SIZE),
+            safe: config
+                .safe_service_url
+                .as_ref()
+                .map(|url| SafeService::new(url).with_retry(retry.safe)),
+            prices: PriceFeeds {
+                feeds: config.price_feeds.clone(),
+                registry: config.feed_registry,
+                max_age: Duration::from_secs(config.price_max_age_secs),
+            },
+            decimals: Mutex::new(config.token_decimals.clone()),
+            single: Web3::new(transport),
+            multi: MulticallParams {
+                address: config.multicall,
+            },
+        })
     }
 }
 
@@ -48,62 +96,225 @@ pub enum ProviderError {
     #[error(transparent)]
     Balancy(#[from] BalancyError),
     #[error(transparent)]
+    Safe(#[from] SafeError),
+    #[error(transparent)]
+    Price(#[from] PriceError),
+    #[error(transparent)]
     Web3Contract(#[from] web3::contract::Error),
     #[error(transparent)]
     Web3(#[from] web3::Error),
+    #[error(transparent)]
+    Abi(#[from] web3::ethabi::Error),
     #[error("{0}")]
     Other(String),
 }
 
-const DECIMALS: u32 = 18;
-const DIVISOR: Balance = 10_u128.pow(DECIMALS) as Balance;
+impl From<MissingBalance> for ProviderError {
+    fn from(error: MissingBalance) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref ERC20: Abi = Abi::load(ERC20_ABI).expect("Invalid ERC20 ABI");
+    static ref ERC721: Abi = Abi::load(ERC721_ABI).expect("Invalid ERC721 ABI");
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BlockHeader {
+    pub(crate) number: U64,
+    pub(crate) timestamp: U64,
+}
+
+pub(crate) fn fail_all(
+    user_addresses: &[Address],
+    error: ProviderError,
+) -> Vec<Result<Amount, ProviderError>> {
+    user_addresses
+        .iter()
+        .map(|_| Err(ProviderError::Other(error.to_string())))
+        .collect()
+}
+
+impl Provider {
+    pub(crate) async fn block_header(
+        &self,
+        block: BlockNumber,
+    ) -> Result<BlockHeader, ProviderError> {
+        let header = self
+            .single
+            .transport()
+            .execute(
+                "eth_getBlockByNumber",
+                vec![helpers::serialize(&block), helpers::serialize(&false)],
+            )
+            .await?;
+
+        if header.is_null() {
+            return Err(ProviderError::Other(format!("Block {block:?} not found")));
+        }
+
+        Ok(helpers::decode(header)?)
+    }
+
+    // Binary search for the last block mined at or before the timestamp
+    async fn block_at_timestamp(&self, timestamp: u64) -> Result<u64, ProviderError> {
+        if let Some(number) = self.resolved_timestamps.get(&timestamp) {
+            return Ok(number);
+        }
+
+        let latest = self.block_header(BlockNumber::Latest).await?;
+
+        // Later blocks may still be mined before a timestamp in the future,
+        // so only past timestamps are resolved
+        if latest.timestamp.as_u64() <= timestamp {
+            return Ok(latest.number.as_u64());
+        }
+
+        if self
+            .block_header(BlockNumber::Earliest)
+            .await?
+            .timestamp
+            .as_u64()
+            > timestamp
+        {
+            return Err(ProviderError::Other(format!(
+                "No block mined before timestamp {timestamp}"
+            )));
+        }
+
+        let (mut low, mut high) = (0, latest.number.as_u64());
+
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            let header = self
+                .block_header(BlockNumber::Number(middle.into()))
+                .await?;
+
+            if header.timestamp.as_u64() <= timestamp {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        self.resolved_timestamps.insert(timestamp, low);
+
+        Ok(low)
+    }
+
+    pub(crate) async fn block_id(
+        &self,
+        snapshot: Option<Snapshot>,
+    ) -> Result<Option<BlockId>, ProviderError> {
+        let number = match snapshot {
+            None => return Ok(None),
+            Some(Snapshot::Block(number)) => number,
+            Some(Snapshot::Timestamp(timestamp)) => self.block_at_timestamp(timestamp).await?,
+        };
+
+        Ok(Some(BlockId::Number(BlockNumber::Number(number.into()))))
+    }
+
+    // Decimals of a token never change, so they are only queried once,
+    // unless the config already lists them
+    pub async fn decimals(&self, token_address: Address) -> Result<u8, ProviderError> {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        if let Some(decimals) = self.decimals.lock().unwrap().get(&token_address) {
+            return Ok(*decimals);
+        }
+
+        let contract = Contract::from_json(self.single.eth(), token_address, ERC20_ABI)?;
+        let decimals: u8 = contract
+            .query("decimals", (), None, Options::default(), None)
+            .await?;
+
+        self.decimals
+            .lock()
+            .unwrap()
+            .insert(token_address, decimals);
+
+        Ok(decimals)
+    }
+
+    async fn balances_of(
+        &self,
+        abi: &Abi,
+        token_address: Address,
+        user_addresses: &[Address],
+        block: Option<BlockId>,
+    ) -> Vec<Result<U256, ProviderError>> {
+        let calls: Vec<Call> = user_addresses
+            .iter()
+            .map(|ua| {
+                Call::new(token_address, abi, "balanceOf", &[Token::Address(*ua)])
+                    .expect("This should be fine")
+            })
+            .collect();
+
+        multicall::aggregate(&self.single, self.multi.address, &calls, block)
+            .await
+            .into_iter()
+            .map(|res| res.and_then(|data| multicall::decode_uint(&data)))
+            .collect()
+    }
+}
 
 #[async_trait]
 impl BalanceQuerier for Provider {
     type Address = Address;
     type Id = U256;
-    type Balance = Balance;
+    type Balance = Amount;
     type Chain = EvmChain;
     type Error = ProviderError;
 
     async fn get_native_balance(
         &self,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>> {
-        join_all(user_addresses.iter().map(|ua| async {
-            self.single
-                .eth()
-                .balance(*ua, None)
-                .await
-                .map_err(ProviderError::Web3)
-                .map(|v| v.as_u128() as Balance / DIVISOR)
-        }))
-        .await
+        let block = match self.block_id(snapshot).await {
+            Ok(block) => block,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+
+        let calls: Vec<Call> = user_addresses
+            .iter()
+            .map(|ua| Call::eth_balance(self.multi.address, *ua))
+            .collect();
+
+        multicall::aggregate(&self.single, self.multi.address, &calls, block)
+            .await
+            .into_iter()
+            .map(|res| {
+                res.and_then(|data| multicall::decode_uint(&data))
+                    .map(|v| Amount::new(v, self.chain.metadata().native_decimals))
+            })
+            .collect()
     }
 
     async fn get_fungible_balance(
         &self,
         token_address: Self::Address,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>> {
-        let contract =
-            Arc::new(Contract::from_json(self.single.eth(), token_address, ERC20_ABI).unwrap());
-
-        let decimals: u8 = contract
-            .query("decimals", (), None, Options::default(), None)
-            .await
-            .unwrap();
+        let block = match self.block_id(snapshot).await {
+            Ok(block) => block,
+            Err(e) => return fail_all(user_addresses, e),
+        };
 
-        join_all(user_addresses.iter().map(|ua| async {
-            let contract = Arc::clone(&contract);
+        let decimals = match self.decimals(token_address).await {
+            Ok(decimals) => decimals,
+            Err(e) => return fail_all(user_addresses, e),
+        };
 
-            contract
-                .query("balanceOf", (*ua,), None, Options::default(), None)
-                .await
-                .map_err(ProviderError::Web3Contract)
-                .map(|v: U256| v.as_u128() as Balance / 10_u128.pow(decimals as u32) as Balance)
-        }))
-        .await
+        self.balances_of(&ERC20, token_address, user_addresses, block)
+            .await
+            .into_iter()
+            .map(|res| res.map(|v| Amount::new(v, decimals)))
+            .collect()
     }
 
     async fn get_non_fungible_balance(
@@ -111,40 +322,37 @@ impl BalanceQuerier for Provider {
         token_address: Self::Address,
         token_id: Option<Self::Id>,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>> {
-        let contract =
-            Arc::new(Contract::from_json(self.single.eth(), token_address, ERC721_ABI).unwrap());
-
-        join_all(user_addresses.iter().map(|ua| async {
-            let contract = Arc::clone(&contract);
-
-            let response: Result<U256, web3::contract::Error> = match token_id {
-                Some(id) => {
-                    let owner_res: Result<Address, web3::contract::Error> = contract
-                        .clone()
-                        .query("ownerOf", (id,), None, Options::default(), None)
-                        .await;
-
-                    let res = match owner_res {
-                        Ok(owner) => i32::from(owner == *ua).into(),
-                        Err(_) => 0.into(),
-                    };
-
-                    Ok(res)
-                }
-                None => {
-                    contract
-                        .clone()
-                        .query("balanceOf", (*ua,), None, Options::default(), None)
-                        .await
-                }
-            };
+        let block = match self.block_id(snapshot).await {
+            Ok(block) => block,
+            Err(e) => return fail_all(user_addresses, e),
+        };
 
-            response
-                .map_err(ProviderError::Web3Contract)
-                .map(|v: U256| v.as_u128() as Balance)
-        }))
-        .await
+        match token_id {
+            Some(id) => {
+                let contract =
+                    Contract::from_json(self.single.eth(), token_address, ERC721_ABI).unwrap();
+
+                // The owner is the same for every user, so a single call is
+                // enough to answer the whole batch
+                let owner: Option<Address> = contract
+                    .query("ownerOf", (id,), None, Options::default(), block)
+                    .await
+                    .ok();
+
+                user_addresses
+                    .iter()
+                    .map(|ua| Ok(Amount::new(U256::from((owner == Some(*ua)) as u8), 0)))
+                    .collect()
+            }
+            None => self
+                .balances_of(&ERC721, token_address, user_addresses, block)
+                .await
+                .into_iter()
+                .map(|res| res.map(|v| Amount::new(v, 0)))
+                .collect(),
+        }
     }
 
     async fn get_special_balance(
@@ -152,45 +360,66 @@ impl BalanceQuerier for Provider {
         token_address: Self::Address,
         token_id: Option<Self::Id>,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>> {
+        let block = match self.block_id(snapshot).await {
+            Ok(block) => block,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+
         let contract = Contract::from_json(self.single.eth(), token_address, ERC1155_ABI).unwrap();
 
         match token_id {
             Some(id) => {
-                let balances: Result<Vec<U256>, web3::contract::Error> = contract
-                    .clone()
-                    .query(
-                        "balanceOfBatch",
-                        (user_addresses.to_vec(), vec![id]),
-                        None,
-                        Options::default(),
-                        None,
-                    )
-                    .await;
-
-                match balances {
-                    Ok(balances) => balances
-                        .iter()
-                        .map(|b| Ok(b.as_u128() as Balance))
-                        .collect(),
-                    Err(e) => user_addresses
-                        .iter()
-                        .map(|_| Err(ProviderError::Other(e.to_string())))
-                        .collect(),
-                }
+                // Batches are split like multicalls so that each call stays
+                // under the gas cap of `eth_call`
+                let chunks = join_all(user_addresses.chunks(multicall::CHUNK_SIZE).map(|chunk| {
+                    let contract = &contract;
+
+                    async move {
+                        let balances: Result<Vec<U256>, web3::contract::Error> = contract
+                            .query(
+                                "balanceOfBatch",
+                                (chunk.to_vec(), vec![id; chunk.len()]),
+                                None,
+                                Options::default(),
+                                block,
+                            )
+                            .await;
+
+                        match balances {
+                            Ok(balances) => balances
+                                .into_iter()
+                                .map(|b| Ok(Amount::new(b, 0)))
+                                .collect::<Vec<_>>(),
+                            Err(e) => chunk
+                                .iter()
+                                .map(|_| Err(ProviderError::Other(e.to_string())))
+                                .collect(),
+                        }
+                    }
+                }))
+                .await;
+
+                chunks.into_iter().flatten().collect()
             }
             None => {
+                if snapshot.is_some() {
+                    return fail_all(
+                        user_addresses,
+                        ProviderError::Balancy(BalancyError::SnapshotNotSupported),
+                    );
+                }
+
                 join_all(user_addresses.iter().map(|ua| async {
-                    let response = BalancyProvider::get_total_erc1155_of_address(
-                        self.chain,
-                        token_address,
-                        *ua,
-                    )
-                    .await;
+                    let response = self
+                        .balancy
+                        .get_total_erc1155_of_address(self.chain, token_address, *ua)
+                        .await;
 
                     response
                         .map_err(ProviderError::Balancy)
-                        .map(|v: U256| v.as_u128() as Balance)
+                        .map(|v: U256| Amount::new(v, 0))
                 }))
                 .await
             }
@@ -198,152 +427,421 @@ impl BalanceQuerier for Provider {
     }
 }
 
-macro_rules! dotenv {
-    ($var: expr) => {
-        match std::env::var($var) {
-            Ok(val) => val,
-            Err(_) => panic!("Environment variable `{}` not found", $var),
-        }
-    };
+pub type CachedProvider = CachedQuerier<Arc<EvmQuerier>>;
+
+/// Providers of the configured EVM chains, sharing a single balance cache,
+/// along with the providers of Solana, the Cosmos and the Substrate chains
+/// when they are configured.
+pub struct EvmProviders {
+    providers: HashMap<EvmChain, Arc<Provider>>,
+    solana: Option<Arc<SolanaProvider>>,
+    substrate: Vec<Arc<SubstrateProvider>>,
+    cache: Arc<BalanceCache<Arc<EvmQuerier>>>,
+    context: ProviderContext,
 }
 
-lazy_static::lazy_static! {
-    pub static ref PROVIDERS: Arc<HashMap<u8, Provider>> = Arc::new({
-        dotenv::dotenv().ok();
+impl EvmProviders {
+    pub fn from_config(config: &ProvidersConfig) -> Result<Self, ProviderError> {
+        let cache = Arc::new(BalanceCache::new(
+            Duration::from_secs(config.cache.ttl_secs),
+            config.cache.max_size,
+        ));
+
+        let providers = config
+            .chains
+            .iter()
+            .map(|chain| {
+                Ok((
+                    chain.chain,
+                    Arc::new(Provider::from_config(chain, &config.retry)?),
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, ProviderError>>()?;
+
+        let solana = config
+            .solana
+            .as_ref()
+            .map(|solana| SolanaProvider::from_config(solana, &config.retry).map(Arc::new))
+            .transpose()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        let context = config
+            .chains
+            .iter()
+            .fold(ProviderContext::new(), |context, chain| {
+                let provider = Arc::clone(&providers[&chain.chain]);
+                let balancy = || {
+                    BalancyQuerier::new(
+                        provider.balancy.clone(),
+                        chain.chain,
+                        Arc::clone(&provider),
+                    )
+                };
+
+                let querier: Arc<EvmQuerier> = match chain.balancy_mode {
+                    Some(BalancyMode::Primary) => {
+                        Arc::new(FallbackQuerier::new(balancy(), Arc::clone(&provider)))
+                    }
+                    Some(BalancyMode::Fallback) => {
+                        Arc::new(FallbackQuerier::new(Arc::clone(&provider), balancy()))
+                    }
+                    None => provider,
+                };
+
+                let context = context
+                    .with_caller(chain.chain, Arc::clone(&providers[&chain.chain]) as _)
+                    .with_activity(chain.chain, Arc::clone(&providers[&chain.chain]) as _)
+                    .with_delegations(chain.chain, Arc::clone(&providers[&chain.chain]) as _)
+                    .with_prices(chain.chain, Arc::clone(&providers[&chain.chain]) as _)
+                    .with_querier(
+                        chain.chain,
+                        Arc::new(CachedQuerier::new(
+                            Arc::clone(&querier),
+                            chain.chain,
+                            Arc::clone(&cache),
+                        )),
+                    )
+                    .with_refreshing_querier(
+                        chain.chain,
+                        Arc::new(
+                            CachedQuerier::new(querier, chain.chain, Arc::clone(&cache))
+                                .refreshing(),
+                        ),
+                    );
+
+                // Safes are only looked up on chains with a Safe Transaction
+                // Service
+                match chain.safe_service_url {
+                    Some(_) => {
+                        context.with_safes(chain.chain, Arc::clone(&providers[&chain.chain]) as _)
+                    }
+                    None => context,
+                }
+            });
 
-        let mut providers = HashMap::new();
+        // ENS names are resolved through the registry on Ethereum mainnet
+        let context = match providers.get(&EvmChain::Ethereum) {
+            Some(ethereum) => context.with_ens(Arc::clone(ethereum) as _),
+            None => context,
+        };
 
-        providers.insert(
-            EvmChain::Ethereum as u8,
-            Provider::new(
-                EvmChain::Ethereum,
-                dotenv!("ETHEREUM_RPC"),
-                address!("0x5ba1e12693dc8f9c48aad8770482f4739beed696")
-            )
-        );
-        providers.insert(
-            EvmChain::Polygon as u8,
-            Provider::new(
-                EvmChain::Polygon,
-                dotenv!("POLYGON_RPC"),
-                address!("0x11ce4B23bD875D7F5C6a31084f55fDe1e9A87507")
-            )
-        );
-        providers.insert(
-            EvmChain::Bsc as u8,
-            Provider::new(
-                EvmChain::Bsc,
-                dotenv!("BSC_RPC"),
-                address!("0x41263cba59eb80dc200f3e2544eda4ed6a90e76c")
-            )
-        );
-        providers.insert(
-            EvmChain::Gnosis as u8,
-            Provider::new(
-                EvmChain::Gnosis,
-                dotenv!("GNOSIS_RPC"),
-                address!("0xb5b692a88bdfc81ca69dcb1d924f59f0413a602a")
-            )
-        );
-        providers.insert(
-            EvmChain::Arbitrum as u8,
-            Provider::new(
-                EvmChain::Arbitrum,
-                dotenv!("ARBITRUM_RPC"),
-                address!("0x52bfe8fE06c8197a8e3dCcE57cE012e13a7315EB")
-            )
-        );
-        providers.insert(
-            EvmChain::Goerli as u8,
-            Provider::new(
-                EvmChain::Goerli,
-                dotenv!("GOERLI_RPC"),
-                address!("0x77dCa2C955b15e9dE4dbBCf1246B4B85b651e50e")
-            )
+        let context = match &solana {
+            Some(solana) => context.with_solana(Arc::clone(solana) as Arc<SolanaQuerier>),
+            None => context,
+        };
+
+        let substrate = config
+            .substrate
+            .iter()
+            .map(|substrate| {
+                SubstrateProvider::from_config(substrate, &config.retry)
+                    .map(Arc::new)
+                    .map_err(|e| ProviderError::Other(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let context = config.cosmos.iter().try_fold(context, |context, cosmos| {
+            let provider = CosmosProvider::from_config(cosmos, &config.retry)
+                .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+            Ok::<_, ProviderError>(context.with_cosmos(
+                cosmos.chain_id.clone(),
+                Arc::new(provider) as Arc<CosmosQuerier>,
+            ))
+        })?;
+
+        let context = config.substrate.iter().zip(substrate.iter()).fold(
+            context,
+            |context, (config, provider)| {
+                context.with_substrate(config.name.clone(), Arc::clone(provider) as _)
+            },
         );
-        providers
-    });
+
+        Ok(Self {
+            providers,
+            solana,
+            substrate,
+            cache,
+            context,
+        })
+    }
+
+    pub fn get(&self, chain: EvmChain) -> Option<Arc<Provider>> {
+        self.providers.get(&chain).cloned()
+    }
+
+    pub fn cache(&self) -> &BalanceCache<Arc<EvmQuerier>> {
+        &self.cache
+    }
+
+    pub fn context(&self) -> &ProviderContext {
+        &self.context
+    }
+
+    pub fn spawn_health_checks(&self) {
+        for provider in self.providers.values() {
+            let provider = Arc::clone(provider);
+
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(provider.reprobe_interval);
+
+                loop {
+                    interval.tick().await;
+                    provider.single.transport().probe("eth_blockNumber").await;
+                }
+            });
+        }
+
+        if let Some(solana) = &self.solana {
+            let solana = Arc::clone(solana);
+
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(solana.reprobe_interval());
+
+                loop {
+                    interval.tick().await;
+                    solana.transport().probe("getHealth").await;
+                }
+            });
+        }
+
+        for provider in self.substrate.iter() {
+            let provider = Arc::clone(provider);
+
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(provider.reprobe_interval());
+
+                loop {
+                    interval.tick().await;
+                    provider.transport().probe("system_health").await;
+                }
+            });
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        address,
+        test_utils::{chain_config, json_rpc, replay, serve},
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Provider of the chain as configured in `providers.toml`, talking to a
+    // server that replays the RPC traffic recorded in `fixtures/<fixture>.json`
+    async fn provider(chain: EvmChain, fixture: &str) -> Provider {
+        dotenv::dotenv().ok();
+
+        let source = include_str!("../../../providers.toml");
+        let configured = |config: ProvidersConfig| {
+            config
+                .chains
+                .into_iter()
+                .find(|c| c.chain == chain)
+                .unwrap()
+        };
+
+        // RPC urls are only read from the environment when recording
+        let mut config = configured(toml::from_str(source).unwrap());
+        let url = replay(fixture, || {
+            configured(ProvidersConfig::parse(source).unwrap()).rpc_urls[0].clone()
+        })
+        .await;
+
+        config.rpc_urls = vec![url];
+
+        Provider::from_config(&config, &RetryConfig::default()).unwrap()
+    }
 
     #[tokio::test]
     async fn eth_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Ethereum as u8)).unwrap();
+        let provider = provider(EvmChain::Ethereum, "eth_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
     }
 
     #[tokio::test]
     async fn polygon_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Polygon as u8)).unwrap();
+        let provider = provider(EvmChain::Polygon, "polygon_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
     }
 
     #[tokio::test]
     async fn bsc_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Bsc as u8)).unwrap();
+        let provider = provider(EvmChain::Bsc, "bsc_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
     }
 
     #[tokio::test]
     async fn arbitrum_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Arbitrum as u8)).unwrap();
+        let provider = provider(EvmChain::Arbitrum, "arbitrum_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
     }
 
     #[tokio::test]
     async fn gnosis_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Gnosis as u8)).unwrap();
+        let provider = provider(EvmChain::Gnosis, "gnosis_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
     }
 
     #[tokio::test]
     async fn goerli_balance() {
-        let provider = PROVIDERS.get(&(EvmChain::Goerli as u8)).unwrap();
+        let provider = provider(EvmChain::Goerli, "goerli_balance").await;
         let maybe_balances = provider
-            .get_native_balance(&[address!("0x0000000000000000000000000000000000000000")])
+            .get_native_balance(
+                &[address!("0x0000000000000000000000000000000000000000")],
+                None,
+            )
             .await;
-        let Some(Ok(balance)) = maybe_balances.get(0) else {
+        let Some(Ok(balance)) = maybe_balances.first() else {
             panic!("should be ok")
         };
 
-        assert!(balance > &0.0)
+        assert!(!balance.is_zero())
+    }
+
+    #[tokio::test]
+    async fn erc1155_balance_chunks() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+
+        // Answers `balanceOfBatch` with a balance of 1 for each address
+        let url = serve(json_rpc(move |_, params| {
+            counter.fetch_add(1, Ordering::Relaxed);
+
+            let data = params[0]["data"].as_str().unwrap();
+            let count = usize::from_str_radix(&data[2 + 8 + 128..2 + 8 + 192], 16).unwrap();
+            let word = |value: usize| format!("{value:064x}");
+
+            serde_json::json!(format!(
+                "0x{}{}{}",
+                word(32),
+                word(count),
+                word(1).repeat(count)
+            ))
+        }))
+        .await;
+
+        let provider = Provider::from_config(&chain_config(url), &RetryConfig::default()).unwrap();
+
+        let users = vec![address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"); 600];
+        let balances = provider
+            .get_special_balance(
+                address!("0x76BE3b62873462d2142405439777e971754E8E77"),
+                Some(U256::from(10)),
+                &users,
+                None,
+            )
+            .await;
+
+        assert_eq!(balances.len(), 600);
+        assert!(balances
+            .iter()
+            .all(|b| b.as_ref().unwrap().raw() == U256::one()));
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn snapshot_block_resolution() {
+        // Blocks 0..=100 mined every 12 seconds starting at 1000
+        let url = serve(json_rpc(|_, params| {
+            let number = match params[0].as_str().unwrap() {
+                "latest" => 100,
+                "earliest" => 0,
+                hex => u64::from_str_radix(hex.trim_start_matches("0x"), 16).unwrap(),
+            };
+
+            serde_json::json!({
+                "number": format!("{number:#x}"),
+                "timestamp": format!("{:#x}", 1000 + 12 * number),
+            })
+        }))
+        .await;
+
+        let provider = Provider::from_config(&chain_config(url), &RetryConfig::default()).unwrap();
+
+        let block = |number: u64| Some(BlockId::Number(BlockNumber::Number(number.into())));
+
+        assert_eq!(provider.block_id(None).await.unwrap(), None);
+        assert_eq!(
+            provider.block_id(Some(Snapshot::Block(7))).await.unwrap(),
+            block(7)
+        );
+        assert_eq!(
+            provider
+                .block_id(Some(Snapshot::Timestamp(1000 + 12 * 50 + 5)))
+                .await
+                .unwrap(),
+            block(50)
+        );
+        assert_eq!(
+            provider
+                .block_id(Some(Snapshot::Timestamp(1000 + 12 * 37)))
+                .await
+                .unwrap(),
+            block(37)
+        );
+        assert_eq!(
+            provider
+                .block_id(Some(Snapshot::Timestamp(5000)))
+                .await
+                .unwrap(),
+            block(100)
+        );
+        assert!(provider
+            .block_id(Some(Snapshot::Timestamp(999)))
+            .await
+            .is_err());
     }
 }
@@ -1,37 +1,31 @@
+pub mod activity;
 pub mod balancy;
+mod chain;
+pub mod contract;
+pub mod delegation;
+pub mod ens;
 pub mod general;
+pub mod multicall;
+pub mod price;
+pub mod safe;
 
 use crate::U256;
 pub use balancy::BalancyProvider;
+pub use chain::{ChainMetadata, EvmChain, UnknownChain};
 pub use general::Provider;
-use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde::{de::Error, Deserialize, Deserializer};
 
 pub const ERC20_ABI: &[u8] = include_bytes!("../../../abi/ERC20.json");
 pub const ERC721_ABI: &[u8] = include_bytes!("../../../abi/ERC721.json");
 pub const ERC1155_ABI: &[u8] = include_bytes!("../../../abi/ERC1155.json");
-
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum EvmChain {
-    Ethereum,
-    Polygon,
-    Gnosis,
-    Bsc,
-    Fantom,
-    Avalanche,
-    Heco,
-    Harmony,
-    Goerli,
-    Arbitrum,
-    Celo,
-    Optimism,
-    Moonriver,
-    Rinkeby,
-    Metis,
-    Cronos,
-    Boba,
-    Palm,
-}
+pub const MULTICALL_ABI: &[u8] = include_bytes!("../../../abi/Multicall.json");
+pub const ENS_REGISTRY_ABI: &[u8] = include_bytes!("../../../abi/ENSRegistry.json");
+pub const ENS_RESOLVER_ABI: &[u8] = include_bytes!("../../../abi/ENSResolver.json");
+pub const NAME_WRAPPER_ABI: &[u8] = include_bytes!("../../../abi/NameWrapper.json");
+pub const DELEGATE_REGISTRY_ABI: &[u8] = include_bytes!("../../../abi/DelegateRegistry.json");
+pub const CHAINLINK_AGGREGATOR_ABI: &[u8] = include_bytes!("../../../abi/ChainlinkAggregator.json");
+pub const FEED_REGISTRY_ABI: &[u8] = include_bytes!("../../../abi/FeedRegistry.json");
+pub const DELEGATE_REGISTRY_V2_ABI: &[u8] = include_bytes!("../../../abi/DelegateRegistryV2.json");
 
 pub fn u256_from_str<'de, D>(deserializer: D) -> Result<U256, D::Error>
 where
@@ -0,0 +1,225 @@
+use crate::{
+    evm::{general::ProviderError, MULTICALL_ABI},
+    Address, U256,
+};
+use futures::future::join_all;
+use web3::{
+    ethabi::{Contract, Token},
+    types::{BlockId, Bytes, CallRequest},
+    Transport, Web3,
+};
+
+// Number of calls packed into a single `aggregate` request, low enough to
+// stay under the gas cap of `eth_call` on every supported chain
+pub const CHUNK_SIZE: usize = 250;
+
+lazy_static::lazy_static! {
+    static ref MULTICALL: Contract =
+        Contract::load(MULTICALL_ABI).expect("Invalid Multicall ABI");
+}
+
+pub struct Call {
+    pub target: Address,
+    pub data: Vec<u8>,
+}
+
+impl Call {
+    pub fn new(
+        target: Address,
+        abi: &Contract,
+        function: &str,
+        args: &[Token],
+    ) -> Result<Self, ProviderError> {
+        let data = abi.function(function)?.encode_input(args)?;
+
+        Ok(Self { target, data })
+    }
+
+    pub fn eth_balance(multicall: Address, user_address: Address) -> Self {
+        Self::new(
+            multicall,
+            &MULTICALL,
+            "getEthBalance",
+            &[Token::Address(user_address)],
+        )
+        .expect("This should be fine")
+    }
+}
+
+pub fn decode_uint(data: &[u8]) -> Result<U256, ProviderError> {
+    match web3::ethabi::decode(&[web3::ethabi::ParamType::Uint(256)], data)?.pop() {
+        Some(Token::Uint(value)) => Ok(value),
+        _ => Err(ProviderError::Other("Invalid uint256 return data".into())),
+    }
+}
+
+pub async fn aggregate<T: Transport>(
+    web3: &Web3<T>,
+    multicall: Address,
+    calls: &[Call],
+    block: Option<BlockId>,
+) -> Vec<Result<Vec<u8>, ProviderError>> {
+    join_all(
+        calls
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| aggregate_chunk(web3, multicall, chunk, block)),
+    )
+    .await
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+async fn aggregate_chunk<T: Transport>(
+    web3: &Web3<T>,
+    multicall: Address,
+    calls: &[Call],
+    block: Option<BlockId>,
+) -> Vec<Result<Vec<u8>, ProviderError>> {
+    match try_aggregate(web3, multicall, calls, block).await {
+        Ok(data) => data.into_iter().map(Ok).collect(),
+        // A single reverting call fails the whole batch, so the calls of the
+        // chunk are sent one by one to keep the results of the others
+        Err(e) if calls.len() > 1 && is_call_failure(&e) => {
+            join_all(calls.iter().map(|c| call(web3, c, block))).await
+        }
+        Err(e) if calls.len() == 1 => vec![Err(e)],
+        // Failures of the endpoint itself would only be multiplied by
+        // sending the calls one by one
+        Err(e) => calls
+            .iter()
+            .map(|_| Err(ProviderError::Other(e.to_string())))
+            .collect(),
+    }
+}
+
+// Errors returned by the node for the call (reverts) or caused by its return
+// data, as opposed to failures reaching the node
+pub fn is_call_failure(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::Web3(web3::Error::Rpc(_)) | ProviderError::Abi(_) | ProviderError::Other(_)
+    )
+}
+
+async fn try_aggregate<T: Transport>(
+    web3: &Web3<T>,
+    multicall: Address,
+    calls: &[Call],
+    block: Option<BlockId>,
+) -> Result<Vec<Vec<u8>>, ProviderError> {
+    let function = MULTICALL.function("aggregate")?;
+    let input = function.encode_input(&[Token::Array(
+        calls
+            .iter()
+            .map(|c| Token::Tuple(vec![Token::Address(c.target), Token::Bytes(c.data.clone())]))
+            .collect(),
+    )])?;
+
+    let output = web3
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(multicall),
+                data: Some(Bytes(input)),
+                ..Default::default()
+            },
+            block,
+        )
+        .await?;
+
+    match function.decode_output(&output.0)?.pop() {
+        Some(Token::Array(return_data)) if return_data.len() == calls.len() => return_data
+            .into_iter()
+            .map(|token| match token {
+                Token::Bytes(data) => Ok(data),
+                _ => Err(ProviderError::Other("Invalid multicall return data".into())),
+            })
+            .collect(),
+        _ => Err(ProviderError::Other("Invalid multicall response".into())),
+    }
+}
+
+async fn call<T: Transport>(
+    web3: &Web3<T>,
+    call: &Call,
+    block: Option<BlockId>,
+) -> Result<Vec<u8>, ProviderError> {
+    let output = web3
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(call.target),
+                data: Some(Bytes(call.data.clone())),
+                ..Default::default()
+            },
+            block,
+        )
+        .await?;
+
+    Ok(output.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{aggregate, Call};
+    use crate::{
+        address,
+        test_utils::serve,
+        transport::{limiter::RequestLimits, retry::BackendRetry, FailoverTransport},
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use web3::Web3;
+
+    // Number of requests needed for two calls answered with `response`
+    async fn requests_for(response: &'static str) -> usize {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        let url = serve(move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            response.to_string()
+        })
+        .await;
+
+        let transport = FailoverTransport::new(
+            &[url],
+            Duration::from_secs(1),
+            RequestLimits::default(),
+            BackendRetry::default(),
+        )
+        .unwrap();
+        let multicall = address!("0x5ba1e12693dc8f9c48aad8770482f4739beed696");
+        let calls = [
+            Call::eth_balance(
+                multicall,
+                address!("0xE43878Ce78934fe8007748FF481f03B8Ee3b97DE"),
+            ),
+            Call::eth_balance(
+                multicall,
+                address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503"),
+            ),
+        ];
+
+        let results = aggregate(&Web3::new(transport), multicall, &calls, None).await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_err));
+
+        requests.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn split_only_reverted_batches() {
+        let reverted =
+            r#"{"jsonrpc":"2.0","id":0,"error":{"code":3,"message":"execution reverted"}}"#;
+
+        assert_eq!(requests_for(reverted).await, 3);
+        assert_eq!(requests_for("not json").await, 1);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    address,
+    evm::{
+        general::{Provider, ProviderError},
+        CHAINLINK_AGGREGATOR_ABI, FEED_REGISTRY_ABI,
+    },
+    Address, Amount, U256,
+};
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use web3::contract::{Contract, Options};
+
+lazy_static::lazy_static! {
+    // `Denominations` of the Feed Registry
+    static ref NATIVE: Address = address!("0xEeeeeEeeeEeEeEeEeEeEEEeeeeEeeeeeeeEEeE");
+    static ref USD: Address = address!("0x0000000000000000000000000000000000000348");
+}
+
+#[derive(Error, Debug)]
+pub enum PriceError {
+    #[error("No USD price feed configured for `{0}`")]
+    NoFeed(String),
+    #[error("Price of `{0}` was last updated {1} seconds ago")]
+    Stale(String, u64),
+    #[error("Invalid price answered for `{0}`")]
+    InvalidAnswer(String),
+}
+
+/// USD price feeds of a chain: Chainlink aggregators of tokens and the Feed
+/// Registry answering the tokens without one.
+#[derive(Debug, Clone, Default)]
+pub struct PriceFeeds {
+    pub feeds: HashMap<Address, Address>,
+    pub registry: Option<Address>,
+    pub max_age: Duration,
+}
+
+/// Prices of tokens in USD, read from on-chain Chainlink feeds.
+#[async_trait]
+pub trait PriceQuerier {
+    /// Latest USD price of the token, the zero address standing for the
+    /// native coin.
+    async fn get_usd_price(&self, token: Address) -> Result<Amount, ProviderError>;
+}
+
+// Answer of `latestRoundData`
+type RoundData = (U256, U256, U256, U256, U256);
+
+impl PriceFeeds {
+    // Price of the round if it is positive, complete and recent enough
+    fn validate(
+        &self,
+        token: Address,
+        (round_id, answer, _, updated_at, answered_in_round): RoundData,
+        decimals: U256,
+        now: u64,
+    ) -> Result<Amount, PriceError> {
+        let token = format!("{token:#x}");
+
+        // Answers are `int256`, so negative ones have their top bit set
+        if answer.is_zero() || answer.bit(255) || decimals > U256::from(u8::MAX) {
+            return Err(PriceError::InvalidAnswer(token));
+        }
+
+        let age = now.saturating_sub(updated_at.low_u64());
+
+        if updated_at.is_zero() || answered_in_round < round_id || age > self.max_age.as_secs() {
+            return Err(PriceError::Stale(token, age));
+        }
+
+        Ok(Amount::new(answer, decimals.low_u32() as u8))
+    }
+}
+
+#[async_trait]
+impl PriceQuerier for Provider {
+    async fn get_usd_price(&self, token: Address) -> Result<Amount, ProviderError> {
+        let options = Options::default;
+
+        let (round, decimals): (RoundData, U256) =
+            match (self.prices.feeds.get(&token), self.prices.registry) {
+                (Some(feed), _) => {
+                    let contract =
+                        Contract::from_json(self.single.eth(), *feed, CHAINLINK_AGGREGATOR_ABI)?;
+
+                    futures::try_join!(
+                        contract.query("latestRoundData", (), None, options(), None),
+                        contract.query("decimals", (), None, options(), None)
+                    )?
+                }
+                (None, Some(registry)) => {
+                    let contract =
+                        Contract::from_json(self.single.eth(), registry, FEED_REGISTRY_ABI)?;
+                    let base = if token.is_zero() { *NATIVE } else { token };
+
+                    futures::try_join!(
+                        contract.query("latestRoundData", (base, *USD), None, options(), None),
+                        contract.query("decimals", (base, *USD), None, options(), None)
+                    )?
+                }
+                (None, None) => return Err(PriceError::NoFeed(format!("{token:#x}")).into()),
+            };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("This should be fine")
+            .as_secs();
+
+        Ok(self.prices.validate(token, round, decimals, now)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PriceError, PriceFeeds, PriceQuerier};
+    use crate::{
+        address,
+        config::ChainConfig,
+        evm::general::Provider,
+        test_utils::{chain_config, json_rpc, serve},
+        transport::retry::RetryConfig,
+        Address, U256,
+    };
+    use std::{collections::HashMap, time::Duration};
+    use web3::ethabi::{encode, Token};
+
+    #[tokio::test]
+    async fn usd_price() {
+        let feeds = PriceFeeds {
+            feeds: HashMap::new(),
+            registry: None,
+            max_age: Duration::from_secs(3600),
+        };
+        let token = Address::zero();
+        let round = |answer: U256, updated_at: u64| {
+            (
+                U256::from(2),
+                answer,
+                U256::zero(),
+                U256::from(updated_at),
+                U256::from(2),
+            )
+        };
+
+        assert_eq!(
+            feeds
+                .validate(
+                    token,
+                    round(U256::from(200_000_000_000u64), 1000),
+                    8.into(),
+                    1000
+                )
+                .unwrap()
+                .to_string(),
+            "2000"
+        );
+        assert!(matches!(
+            feeds.validate(token, round(U256::from(1), 1000), 8.into(), 5000),
+            Err(PriceError::Stale(_, 4000))
+        ));
+        assert!(matches!(
+            feeds.validate(token, round(U256::MAX, 1000), 8.into(), 1000),
+            Err(PriceError::InvalidAnswer(_))
+        ));
+
+        // An ETH / USD aggregator answering $1850.5 updated just now
+        let url = serve(json_rpc(|_, params| {
+            let data = params[0]["data"].as_str().unwrap();
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let tokens = match &data[..10] {
+                // latestRoundData()
+                "0xfeaf968c" => vec![
+                    Token::Uint(U256::from(7)),
+                    Token::Int(U256::from(185_050_000_000u64)),
+                    Token::Uint(U256::from(now)),
+                    Token::Uint(U256::from(now)),
+                    Token::Uint(U256::from(7)),
+                ],
+                // decimals()
+                _ => vec![Token::Uint(U256::from(8))],
+            };
+
+            format!("0x{}", hex(&encode(&tokens))).into()
+        }))
+        .await;
+
+        let provider = Provider::from_config(
+            &ChainConfig {
+                price_feeds: HashMap::from([(
+                    Address::zero(),
+                    address!("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"),
+                )]),
+                ..chain_config(url)
+            },
+            &RetryConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            provider
+                .get_usd_price(Address::zero())
+                .await
+                .unwrap()
+                .to_string(),
+            "1850.5"
+        );
+        assert!(provider
+            .get_usd_price(address!("0x6B175474E89094C44Da98b954EedeAC495271d0F"))
+            .await
+            .is_err());
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    evm::general::{Provider, ProviderError},
+    transport::retry::{BackendRetry, ErrorClass},
+    Address,
+};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+use web3::signing::keccak256;
+
+lazy_static::lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+#[derive(Error, Debug)]
+pub enum SafeError {
+    #[error("No Safe Transaction Service configured for {0}")]
+    NotConfigured(String),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Safe Transaction Service responded with status code `{0}`")]
+    Status(u16),
+}
+
+fn classify(error: &SafeError) -> Option<ErrorClass> {
+    match error {
+        SafeError::Status(429) => Some(ErrorClass::RateLimited),
+        SafeError::Status(500..=599) => Some(ErrorClass::ServerError),
+        SafeError::Reqwest(e) if e.is_timeout() || e.is_connect() => Some(ErrorClass::Transport),
+        _ => None,
+    }
+}
+
+/// Finds the Safes of owners on chains where assets held by Safes count as
+/// held by their owners.
+#[async_trait]
+pub trait SafeQuerier {
+    /// Safes the address is one of the owners of.
+    async fn get_safes(&self, owner: Address) -> Result<Vec<Address>, ProviderError>;
+}
+
+#[derive(Deserialize)]
+struct OwnerSafes {
+    safes: Vec<Address>,
+}
+
+// The service only accepts EIP-55 checksummed addresses
+fn checksummed(address: Address) -> String {
+    let hex = format!("{address:x}");
+    let hash = keccak256(hex.as_bytes());
+
+    let checksummed: String = hex
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf {
+            8.. => c.to_ascii_uppercase(),
+            _ => c,
+        })
+        .collect();
+
+    format!("0x{checksummed}")
+}
+
+/// Client of the Safe Transaction Service of a chain, which indexes the
+/// owners of Safes.
+#[derive(Clone)]
+pub struct SafeService {
+    base_url: String,
+    retry: BackendRetry,
+}
+
+impl SafeService {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            retry: BackendRetry::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: BackendRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub async fn get_safes(&self, owner: Address) -> Result<Vec<Address>, SafeError> {
+        self.retry
+            .run("Safe Transaction Service request", classify, || {
+                self.fetch_safes(owner)
+            })
+            .await
+            .0
+    }
+
+    async fn fetch_safes(&self, owner: Address) -> Result<Vec<Address>, SafeError> {
+        let res = CLIENT
+            .get(format!(
+                "{}/api/v1/owners/{}/safes/",
+                self.base_url.trim_end_matches('/'),
+                checksummed(owner)
+            ))
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.json::<OwnerSafes>().await?.safes),
+            // Addresses the service has never seen
+            StatusCode::NOT_FOUND => Ok(vec![]),
+            status => Err(SafeError::Status(status.as_u16())),
+        }
+    }
+}
+
+#[async_trait]
+impl SafeQuerier for Provider {
+    async fn get_safes(&self, owner: Address) -> Result<Vec<Address>, ProviderError> {
+        let Some(safe) = &self.safe else {
+            return Err(SafeError::NotConfigured(format!("{:?}", self.chain)).into());
+        };
+
+        Ok(safe.get_safes(owner).await?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{checksummed, SafeService};
+    use crate::{address, test_utils::serve};
+
+    #[tokio::test]
+    async fn safe_service() {
+        let owner = address!("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359");
+        assert_eq!(
+            checksummed(owner),
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"
+        );
+
+        let url = serve(move |path, _| match path {
+            "/api/v1/owners/0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359/safes/" => {
+                r#"{"safes": ["0x849D52316331967b6fF1198e5E32A0eB168D039d"]}"#.into()
+            }
+            _ => r#"{"safes": []}"#.into(),
+        })
+        .await;
+        let service = SafeService::new(url);
+
+        assert_eq!(
+            service.get_safes(owner).await.unwrap(),
+            vec![address!("0x849D52316331967b6fF1198e5E32A0eB168D039d")]
+        );
+        assert!(service
+            .get_safes(address!("0x20CC54c7ebc5f43b74866D839b4BD5c01BB23503"))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{BalanceQuerier, Snapshot};
+use async_trait::async_trait;
+use std::{fmt::Display, future::Future};
+
+/// Querier asking the primary querier first and only forwarding the
+/// addresses it failed to answer to the fallback querier.
+pub struct FallbackQuerier<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P, F> FallbackQuerier<P, F> {
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+}
+
+async fn with_fallback<A, B, E, Fut>(
+    user_addresses: &[A],
+    mut results: Vec<Result<B, E>>,
+    fetch: impl FnOnce(Vec<A>) -> Fut,
+) -> Vec<Result<B, E>>
+where
+    A: Clone,
+    E: Display,
+    Fut: Future<Output = Vec<Result<B, E>>>,
+{
+    let failed: Vec<usize> = (0..results.len())
+        .filter(|idx| results[*idx].is_err())
+        .collect();
+
+    if failed.is_empty() {
+        return results;
+    }
+
+    let fetched = fetch(
+        failed
+            .iter()
+            .map(|idx| user_addresses[*idx].clone())
+            .collect(),
+    )
+    .await;
+
+    // When both sources fail the error of the primary one is kept
+    for (idx, result) in failed.into_iter().zip(fetched) {
+        match result {
+            Ok(balance) => results[idx] = Ok(balance),
+            Err(e) => log::warn!("Fallback balance query failed: {e}"),
+        }
+    }
+
+    results
+}
+
+#[async_trait]
+impl<P, F> BalanceQuerier for FallbackQuerier<P, F>
+where
+    P: BalanceQuerier + Send + Sync,
+    F: BalanceQuerier<
+            Address = P::Address,
+            Id = P::Id,
+            Balance = P::Balance,
+            Chain = P::Chain,
+            Error = P::Error,
+        > + Send
+        + Sync,
+    P::Address: Clone + Send + Sync,
+    P::Id: Clone + Send + Sync,
+    P::Balance: Send,
+    P::Error: Display + Send,
+{
+    type Address = P::Address;
+    type Id = P::Id;
+    type Balance = P::Balance;
+    type Chain = P::Chain;
+    type Error = P::Error;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        with_fallback(
+            user_addresses,
+            self.primary
+                .get_native_balance(user_addresses, snapshot)
+                .await,
+            |failed| async move { self.fallback.get_native_balance(&failed, snapshot).await },
+        )
+        .await
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        with_fallback(
+            user_addresses,
+            self.primary
+                .get_fungible_balance(token_address.clone(), user_addresses, snapshot)
+                .await,
+            |failed| async move {
+                self.fallback
+                    .get_fungible_balance(token_address, &failed, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        with_fallback(
+            user_addresses,
+            self.primary
+                .get_non_fungible_balance(
+                    token_address.clone(),
+                    token_id.clone(),
+                    user_addresses,
+                    snapshot,
+                )
+                .await,
+            |failed| async move {
+                self.fallback
+                    .get_non_fungible_balance(token_address, token_id, &failed, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+
+    async fn get_special_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        with_fallback(
+            user_addresses,
+            self.primary
+                .get_special_balance(
+                    token_address.clone(),
+                    token_id.clone(),
+                    user_addresses,
+                    snapshot,
+                )
+                .await,
+            |failed| async move {
+                self.fallback
+                    .get_special_balance(token_address, token_id, &failed, snapshot)
+                    .await
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FallbackQuerier;
+    use crate::{
+        address, evm::general::ProviderError, Address, Amount, BalanceQuerier, EvmChain,
+        MemoryQuerier, Snapshot, U256,
+    };
+    use async_trait::async_trait;
+
+    // Querier failing for every address but the listed ones
+    struct PartialQuerier(Vec<Address>);
+
+    #[async_trait]
+    impl BalanceQuerier for PartialQuerier {
+        type Address = Address;
+        type Id = U256;
+        type Balance = Amount;
+        type Chain = EvmChain;
+        type Error = ProviderError;
+
+        async fn get_native_balance(
+            &self,
+            user_addresses: &[Self::Address],
+            _snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            user_addresses
+                .iter()
+                .map(|ua| match self.0.contains(ua) {
+                    true => Ok(Amount::new(U256::from(1), 0)),
+                    false => Err(ProviderError::Other("Unavailable".into())),
+                })
+                .collect()
+        }
+
+        async fn get_fungible_balance(
+            &self,
+            _token_address: Self::Address,
+            user_addresses: &[Self::Address],
+            snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.get_native_balance(user_addresses, snapshot).await
+        }
+
+        async fn get_non_fungible_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.get_native_balance(user_addresses, snapshot).await
+        }
+
+        async fn get_special_balance(
+            &self,
+            _token_address: Self::Address,
+            _token_id: Option<Self::Id>,
+            user_addresses: &[Self::Address],
+            snapshot: Option<Snapshot>,
+        ) -> Vec<Result<Self::Balance, Self::Error>> {
+            self.get_native_balance(user_addresses, snapshot).await
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_querier() {
+        let token = address!("0x76be3b62873462d2142405439777e971754e8e77");
+        let users = [
+            address!("0x14ddfe8ea7ffc338015627d160ccaf99e8f16dd3"),
+            address!("0x20cc54c7ebc5f43b74866d839b4bd5c01bb23503"),
+            address!("0x283d678711daa088640c86a1ad3f12c00ec1252e"),
+        ];
+
+        let querier = FallbackQuerier::new(
+            PartialQuerier(vec![users[0]]),
+            MemoryQuerier::new().with_fungible_balance(
+                token,
+                users[1],
+                Amount::new(U256::from(5), 0),
+            ),
+        );
+
+        let balances: Vec<U256> = querier
+            .get_fungible_balance(token, &users, None)
+            .await
+            .into_iter()
+            .map(|balance| balance.unwrap().raw())
+            .collect();
+
+        assert_eq!(balances, [U256::from(1), U256::from(5), U256::zero()]);
+
+        let querier = FallbackQuerier::new(
+            PartialQuerier(vec![users[0]]),
+            PartialQuerier(vec![users[1]]),
+        );
+        let balances = querier.get_native_balance(&users, None).await;
+
+        assert!(balances[0].is_ok() && balances[1].is_ok());
+        assert_eq!(balances[2].as_ref().unwrap_err().to_string(), "Unavailable");
+    }
+}
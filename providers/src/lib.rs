@@ -1,12 +1,40 @@
 #![deny(clippy::dbg_macro)]
 
+mod amount;
+pub mod cache;
+pub mod config;
+mod context;
+pub mod cosmos;
 pub mod evm;
+mod fallback;
+mod memory;
+pub mod solana;
+pub mod substrate;
+#[cfg(test)]
+mod test_utils;
+pub mod transport;
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
+pub use amount::{Amount, AmountError};
+pub use context::{CosmosQuerier, EvmQuerier, ProviderContext, SolanaQuerier};
 pub use evm::EvmChain;
+pub use fallback::FallbackQuerier;
+pub use memory::MemoryQuerier;
+pub use substrate::SubstrateQuerier;
 pub use web3::types::{Address, U256};
 
+/// Point in the history of a chain at which balances are queried instead of
+/// the latest block.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Snapshot {
+    Block(u64),
+    Timestamp(u64),
+}
+
 #[async_trait]
 pub trait BalanceQuerier {
     type Address;
@@ -18,12 +46,14 @@ pub trait BalanceQuerier {
     async fn get_native_balance(
         &self,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>>;
 
     async fn get_fungible_balance(
         &self,
         token_address: Self::Address,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>>;
 
     async fn get_non_fungible_balance(
@@ -31,6 +61,7 @@ pub trait BalanceQuerier {
         token_address: Self::Address,
         token_id: Option<Self::Id>,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>>;
 
     async fn get_special_balance(
@@ -38,5 +69,63 @@ pub trait BalanceQuerier {
         token_address: Self::Address,
         token_id: Option<Self::Id>,
         user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
     ) -> Vec<Result<Self::Balance, Self::Error>>;
 }
+
+#[async_trait]
+impl<Q> BalanceQuerier for Arc<Q>
+where
+    Q: BalanceQuerier + Send + Sync + ?Sized,
+    Q::Address: Send + Sync,
+    Q::Id: Send,
+{
+    type Address = Q::Address;
+    type Id = Q::Id;
+    type Balance = Q::Balance;
+    type Chain = Q::Chain;
+    type Error = Q::Error;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        (**self).get_native_balance(user_addresses, snapshot).await
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        (**self)
+            .get_fungible_balance(token_address, user_addresses, snapshot)
+            .await
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        (**self)
+            .get_non_fungible_balance(token_address, token_id, user_addresses, snapshot)
+            .await
+    }
+
+    async fn get_special_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        (**self)
+            .get_special_balance(token_address, token_id, user_addresses, snapshot)
+            .await
+    }
+}
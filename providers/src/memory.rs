@@ -0,0 +1,137 @@
+use crate::{
+    evm::general::ProviderError, Address, Amount, BalanceQuerier, EvmChain, Snapshot, U256,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Querier answering from balances set up in advance, for tests and other
+/// offline use. Balances that were not set are zero and snapshots are
+/// ignored.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryQuerier {
+    native: HashMap<Address, Amount>,
+    fungible: HashMap<(Address, Address), Amount>,
+    non_fungible: HashMap<(Address, Option<U256>, Address), Amount>,
+    special: HashMap<(Address, Option<U256>, Address), Amount>,
+}
+
+impl MemoryQuerier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_native_balance(mut self, user_address: Address, amount: Amount) -> Self {
+        self.native.insert(user_address, amount);
+        self
+    }
+
+    pub fn with_fungible_balance(
+        mut self,
+        token_address: Address,
+        user_address: Address,
+        amount: Amount,
+    ) -> Self {
+        self.fungible.insert((token_address, user_address), amount);
+        self
+    }
+
+    pub fn with_non_fungible_balance(
+        mut self,
+        token_address: Address,
+        token_id: Option<U256>,
+        user_address: Address,
+        amount: Amount,
+    ) -> Self {
+        self.non_fungible
+            .insert((token_address, token_id, user_address), amount);
+        self
+    }
+
+    pub fn with_special_balance(
+        mut self,
+        token_address: Address,
+        token_id: Option<U256>,
+        user_address: Address,
+        amount: Amount,
+    ) -> Self {
+        self.special
+            .insert((token_address, token_id, user_address), amount);
+        self
+    }
+}
+
+#[async_trait]
+impl BalanceQuerier for MemoryQuerier {
+    type Address = Address;
+    type Id = U256;
+    type Balance = Amount;
+    type Chain = EvmChain;
+    type Error = ProviderError;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        user_addresses
+            .iter()
+            .map(|ua| Ok(self.native.get(ua).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        user_addresses
+            .iter()
+            .map(|ua| {
+                Ok(self
+                    .fungible
+                    .get(&(token_address, *ua))
+                    .copied()
+                    .unwrap_or_default())
+            })
+            .collect()
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        user_addresses
+            .iter()
+            .map(|ua| {
+                Ok(self
+                    .non_fungible
+                    .get(&(token_address, token_id, *ua))
+                    .copied()
+                    .unwrap_or_default())
+            })
+            .collect()
+    }
+
+    async fn get_special_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        user_addresses
+            .iter()
+            .map(|ua| {
+                Ok(self
+                    .special
+                    .get(&(token_address, token_id, *ua))
+                    .copied()
+                    .unwrap_or_default())
+            })
+            .collect()
+    }
+}
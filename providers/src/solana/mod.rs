@@ -0,0 +1,105 @@
+mod provider;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+pub use provider::SolanaProvider;
+
+#[derive(Error, Debug)]
+pub enum SolanaError {
+    #[error(transparent)]
+    Rpc(#[from] web3::Error),
+    #[error("Invalid Solana address `{0}`")]
+    InvalidAddress(String),
+    #[error("Invalid Solana RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("Solana does not support {0}")]
+    NotSupported(&'static str),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Public key of a Solana account, represented as base58 text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SolanaAddress([u8; 32]);
+
+impl SolanaAddress {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Program derived address of `program` for `seeds`, derived with the
+    /// highest bump seed that puts it off the ed25519 curve, like
+    /// `Pubkey::find_program_address` of the Solana SDK.
+    pub fn find_program_address(seeds: &[&[u8]], program: &Self) -> Option<Self> {
+        (0..=u8::MAX).rev().find_map(|bump| {
+            let mut hasher = Sha256::new();
+
+            for seed in seeds {
+                hasher.update(seed);
+            }
+
+            hasher.update([bump]);
+            hasher.update(program.0);
+            hasher.update(b"ProgramDerivedAddress");
+
+            let hash: [u8; 32] = hasher.finalize().into();
+
+            CompressedEdwardsY(hash)
+                .decompress()
+                .is_none()
+                .then_some(Self(hash))
+        })
+    }
+}
+
+impl From<[u8; 32]> for SolanaAddress {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl FromStr for SolanaAddress {
+    type Err = SolanaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bs58::decode(s)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(Self)
+            .ok_or_else(|| SolanaError::InvalidAddress(s.to_string()))
+    }
+}
+
+impl fmt::Display for SolanaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", bs58::encode(self.0).into_string())
+    }
+}
+
+impl Serialize for SolanaAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SolanaAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[macro_export]
+macro_rules! solana_address {
+    ($addr:expr) => {{
+        use std::str::FromStr;
+        $crate::solana::SolanaAddress::from_str($addr)
+            .expect(&format!("Invalid Solana address {}", $addr))
+    }};
+}
@@ -0,0 +1,462 @@
+use crate::{
+    config::SolanaConfig,
+    solana::{SolanaAddress, SolanaError},
+    solana_address,
+    transport::{limiter::RequestLimits, retry::RetryConfig, FailoverTransport},
+    Amount, BalanceQuerier, Snapshot, U256,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use jsonrpc_core::{Call, Params};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use web3::Transport;
+
+const LAMPORTS_DECIMALS: u8 = 9;
+// Maximum number of accounts fetched by a single `getMultipleAccounts`
+const ACCOUNTS_PER_REQUEST: usize = 100;
+const ASSETS_PER_PAGE: usize = 1000;
+const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// Provider of SOL, SPL token and Metaplex NFT balances of a Solana cluster.
+/// SPL balances are read from the associated token accounts of the users, so
+/// tokens held in other token accounts are not counted. NFT collections are
+/// looked up through the Metaplex DAS API, which has to be supported by the
+/// RPC.
+pub struct SolanaProvider {
+    transport: FailoverTransport,
+    reprobe_interval: Duration,
+    mints: Mutex<HashMap<SolanaAddress, Mint>>,
+}
+
+// The token program owning a mint, either SPL Token or Token-2022, and the
+// decimals of the mint
+#[derive(Clone, Copy)]
+struct Mint {
+    program: SolanaAddress,
+    decimals: u8,
+}
+
+fn invalid(method: &str) -> SolanaError {
+    SolanaError::InvalidResponse(format!("unexpected `{method}` result"))
+}
+
+fn fail_all<T>(
+    user_addresses: &[SolanaAddress],
+    error: SolanaError,
+) -> Vec<Result<T, SolanaError>> {
+    user_addresses
+        .iter()
+        .map(|_| Err(SolanaError::Other(error.to_string())))
+        .collect()
+}
+
+impl SolanaProvider {
+    pub fn from_config(config: &SolanaConfig, retry: &RetryConfig) -> Result<Self, SolanaError> {
+        let transport = FailoverTransport::new(
+            &config.rpc_urls,
+            Duration::from_millis(config.timeout_ms),
+            RequestLimits {
+                max_concurrent_requests: config.max_concurrent_requests,
+                requests_per_second: config.requests_per_second,
+            },
+            retry.rpc,
+        )?;
+
+        Ok(Self {
+            transport,
+            reprobe_interval: Duration::from_secs(config.reprobe_interval_secs),
+            mints: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn transport(&self) -> &FailoverTransport {
+        &self.transport
+    }
+
+    pub fn reprobe_interval(&self) -> Duration {
+        self.reprobe_interval
+    }
+
+    // Solana methods take positional params, while the DAS ones take named
+    // params, which `Transport::execute` can't send
+    async fn call(&self, method: &str, params: Params) -> Result<Value, SolanaError> {
+        let (id, mut request) = self.transport.prepare(method, vec![]);
+
+        if let Call::MethodCall(call) = &mut request {
+            call.params = params;
+        }
+
+        Ok(self.transport.send(id, request).await?)
+    }
+
+    async fn accounts(
+        &self,
+        accounts: &[SolanaAddress],
+        config: Value,
+    ) -> Result<Vec<Value>, SolanaError> {
+        let mut result = self
+            .call(
+                "getMultipleAccounts",
+                Params::Array(vec![
+                    json!(accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>()),
+                    config,
+                ]),
+            )
+            .await?;
+
+        match result["value"].take() {
+            Value::Array(values) if values.len() == accounts.len() => Ok(values),
+            _ => Err(invalid("getMultipleAccounts")),
+        }
+    }
+
+    async fn lamports(&self, accounts: &[SolanaAddress]) -> Result<Vec<U256>, SolanaError> {
+        self.accounts(
+            accounts,
+            json!({ "encoding": "base64", "dataSlice": { "offset": 0, "length": 0 } }),
+        )
+        .await?
+        .iter()
+        .map(|account| match account {
+            // Accounts that were never funded don't exist
+            Value::Null => Ok(U256::zero()),
+            account => account["lamports"]
+                .as_u64()
+                .map(U256::from)
+                .ok_or_else(|| invalid("getMultipleAccounts")),
+        })
+        .collect()
+    }
+
+    // Mints never change their program or decimals, so they are only queried
+    // once
+    async fn mint(&self, mint: SolanaAddress) -> Result<Mint, SolanaError> {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        if let Some(info) = self.mints.lock().unwrap().get(&mint) {
+            return Ok(*info);
+        }
+
+        let result = self
+            .call(
+                "getAccountInfo",
+                Params::Array(vec![
+                    json!(mint.to_string()),
+                    json!({ "encoding": "jsonParsed" }),
+                ]),
+            )
+            .await?;
+        let account = &result["value"];
+        let info = account["owner"]
+            .as_str()
+            .and_then(|program| program.parse().ok())
+            .zip(
+                account
+                    .pointer("/data/parsed/info/decimals")
+                    .and_then(Value::as_u64)
+                    .and_then(|decimals| u8::try_from(decimals).ok()),
+            )
+            .map(|(program, decimals)| Mint { program, decimals })
+            .ok_or_else(|| invalid("getAccountInfo"))?;
+
+        self.mints.lock().unwrap().insert(mint, info);
+
+        Ok(info)
+    }
+
+    async fn token_amounts(
+        &self,
+        mint: SolanaAddress,
+        program: SolanaAddress,
+        owners: &[SolanaAddress],
+    ) -> Result<Vec<U256>, SolanaError> {
+        let associated_program = solana_address!(ASSOCIATED_TOKEN_PROGRAM);
+        let token_accounts = owners
+            .iter()
+            .map(|owner| {
+                SolanaAddress::find_program_address(
+                    &[owner.as_bytes(), program.as_bytes(), mint.as_bytes()],
+                    &associated_program,
+                )
+                .ok_or_else(|| SolanaError::Other(format!("No token account for `{owner}`")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.accounts(&token_accounts, json!({ "encoding": "jsonParsed" }))
+            .await?
+            .iter()
+            .map(|account| match account {
+                // Owners that never held the token have no token account
+                Value::Null => Ok(U256::zero()),
+                account => account
+                    .pointer("/data/parsed/info/tokenAmount/amount")
+                    .and_then(Value::as_str)
+                    .and_then(|amount| U256::from_dec_str(amount).ok())
+                    .ok_or_else(|| invalid("getMultipleAccounts")),
+            })
+            .collect()
+    }
+
+    async fn collection_assets(
+        &self,
+        collection: SolanaAddress,
+        owner: SolanaAddress,
+    ) -> Result<Vec<SolanaAddress>, SolanaError> {
+        let mut assets = vec![];
+        let mut page = 1;
+
+        loop {
+            let params = json!({
+                "ownerAddress": owner.to_string(),
+                "grouping": ["collection", collection.to_string()],
+                "page": page,
+                "limit": ASSETS_PER_PAGE,
+            });
+            let Value::Object(params) = params else {
+                unreachable!()
+            };
+
+            let result = self.call("searchAssets", Params::Map(params)).await?;
+            let items = result["items"]
+                .as_array()
+                .ok_or_else(|| invalid("searchAssets"))?;
+
+            for item in items {
+                assets.push(
+                    item["id"]
+                        .as_str()
+                        .and_then(|id| id.parse().ok())
+                        .ok_or_else(|| invalid("searchAssets"))?,
+                );
+            }
+
+            if items.len() < ASSETS_PER_PAGE {
+                return Ok(assets);
+            }
+
+            page += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl BalanceQuerier for SolanaProvider {
+    type Address = SolanaAddress;
+    type Id = SolanaAddress;
+    type Balance = Amount;
+    // Only a single cluster is configured
+    type Chain = ();
+    type Error = SolanaError;
+
+    async fn get_native_balance(
+        &self,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        if snapshot.is_some() {
+            return fail_all(user_addresses, SolanaError::NotSupported("snapshots"));
+        }
+
+        join_all(
+            user_addresses
+                .chunks(ACCOUNTS_PER_REQUEST)
+                .map(|chunk| async move {
+                    match self.lamports(chunk).await {
+                        Ok(lamports) => lamports
+                            .into_iter()
+                            .map(|v| Ok(Amount::new(v, LAMPORTS_DECIMALS)))
+                            .collect(),
+                        Err(e) => fail_all(chunk, e),
+                    }
+                }),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    async fn get_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        if snapshot.is_some() {
+            return fail_all(user_addresses, SolanaError::NotSupported("snapshots"));
+        }
+
+        let mint = match self.mint(token_address).await {
+            Ok(mint) => mint,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+
+        join_all(
+            user_addresses
+                .chunks(ACCOUNTS_PER_REQUEST)
+                .map(|chunk| async move {
+                    match self.token_amounts(token_address, mint.program, chunk).await {
+                        Ok(amounts) => amounts
+                            .into_iter()
+                            .map(|v| Ok(Amount::new(v, mint.decimals)))
+                            .collect(),
+                        Err(e) => fail_all(chunk, e),
+                    }
+                }),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    async fn get_non_fungible_balance(
+        &self,
+        token_address: Self::Address,
+        token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        if snapshot.is_some() {
+            return fail_all(user_addresses, SolanaError::NotSupported("snapshots"));
+        }
+
+        join_all(user_addresses.iter().map(|ua| async {
+            let assets = self.collection_assets(token_address, *ua).await?;
+            let owned = assets
+                .iter()
+                .filter(|asset| token_id.is_none_or(|id| **asset == id))
+                .count();
+
+            Ok(Amount::new(U256::from(owned), 0))
+        }))
+        .await
+    }
+
+    async fn get_special_balance(
+        &self,
+        _token_address: Self::Address,
+        _token_id: Option<Self::Id>,
+        user_addresses: &[Self::Address],
+        _snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Self::Balance, Self::Error>> {
+        fail_all(
+            user_addresses,
+            SolanaError::NotSupported("special balances"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SolanaProvider, ASSOCIATED_TOKEN_PROGRAM};
+    use crate::{
+        config::SolanaConfig,
+        solana::{SolanaAddress, SolanaError},
+        solana_address,
+        test_utils::{json_rpc, serve},
+        transport::retry::RetryConfig,
+        Amount, BalanceQuerier, Snapshot, U256,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn solana_balances() {
+        let mint = solana_address!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        let collection = solana_address!("J1S9H3QjnRtBbbuD4HjPV6RpRhwuk4zKbxsnCHuTgh9w");
+        let nft = solana_address!("8yq2LGMR1mSMqfMM9EvV2ZeU7UafuXMHdTVkXsMKqXuW");
+        let users = [
+            solana_address!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"),
+            solana_address!("4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T"),
+        ];
+
+        let token_program = solana_address!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+        let token_account = SolanaAddress::find_program_address(
+            &[
+                users[0].as_bytes(),
+                token_program.as_bytes(),
+                mint.as_bytes(),
+            ],
+            &solana_address!(ASSOCIATED_TOKEN_PROGRAM),
+        )
+        .unwrap();
+
+        let url = serve(json_rpc(move |method, params| match method {
+            "getMultipleAccounts" if params[1]["encoding"] == "jsonParsed" => {
+                let accounts = params[0].as_array().unwrap();
+
+                json!({
+                    "context": { "slot": 1 },
+                    "value": accounts.iter().map(|account| {
+                        (*account == token_account.to_string()).then(|| {
+                            json!({ "data": { "parsed": { "info": {
+                                "tokenAmount": { "amount": "3500000", "decimals": 6 },
+                            } } } })
+                        })
+                    }).collect::<Vec<_>>(),
+                })
+            }
+            "getMultipleAccounts" => json!({
+                "context": { "slot": 1 },
+                "value": [{ "lamports": 1_500_000_000u64 }, null],
+            }),
+            "getAccountInfo" => json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "owner": token_program.to_string(),
+                    "data": { "parsed": { "info": { "decimals": 6 } } },
+                },
+            }),
+            "searchAssets" if params["ownerAddress"] == users[1].to_string() => json!({
+                "total": 1,
+                "items": [{ "id": nft.to_string() }],
+            }),
+            "searchAssets" => json!({ "total": 0, "items": [] }),
+            _ => json!(null),
+        }))
+        .await;
+
+        let provider = SolanaProvider::from_config(
+            &SolanaConfig {
+                rpc_urls: vec![url],
+                timeout_ms: 1000,
+                reprobe_interval_secs: 30,
+                max_concurrent_requests: None,
+                requests_per_second: None,
+            },
+            &RetryConfig::default(),
+        )
+        .unwrap();
+
+        let raw = |balances: Vec<Result<Amount, SolanaError>>| -> Vec<U256> {
+            balances.into_iter().map(|b| b.unwrap().raw()).collect()
+        };
+
+        let native = provider.get_native_balance(&users, None).await;
+        assert_eq!(native[0].as_ref().unwrap().to_string(), "1.5");
+        assert!(native[1].as_ref().unwrap().is_zero());
+
+        assert_eq!(
+            raw(provider.get_fungible_balance(mint, &users, None).await),
+            [U256::from(3_500_000), U256::zero()]
+        );
+        assert_eq!(
+            raw(provider
+                .get_non_fungible_balance(collection, None, &users, None)
+                .await),
+            [U256::zero(), U256::one()]
+        );
+        assert_eq!(
+            raw(provider
+                .get_non_fungible_balance(collection, Some(mint), &users, None)
+                .await),
+            [U256::zero(), U256::zero()]
+        );
+        assert!(provider
+            .get_native_balance(&users, Some(Snapshot::Block(1)))
+            .await
+            .iter()
+            .all(Result::is_err));
+    }
+}
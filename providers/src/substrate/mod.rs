@@ -0,0 +1,212 @@
+mod provider;
+
+use crate::{Amount, Snapshot};
+use async_trait::async_trait;
+use blake2::{Blake2b512, Digest};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+pub use provider::SubstrateProvider;
+
+#[derive(Error, Debug)]
+pub enum SubstrateError {
+    #[error(transparent)]
+    Rpc(#[from] web3::Error),
+    #[error("Invalid SS58 address `{0}`")]
+    InvalidAddress(String),
+    #[error("Invalid Substrate RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("Substrate chains do not support {0}")]
+    NotSupported(&'static str),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Part of the native balance of an account that is checked.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NativeBalance {
+    Free,
+    Reserved,
+    #[default]
+    Total,
+}
+
+/// Pallet holding the NFT collections of a chain.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NftPallet {
+    #[default]
+    Nfts,
+    Uniques,
+}
+
+/// Querier of Substrate chains. Tokens are identified by the ids the
+/// `assets`, `uniques` and `nfts` pallets use, which don't fit the addresses
+/// of [`BalanceQuerier`](crate::BalanceQuerier).
+#[async_trait]
+pub trait SubstrateQuerier {
+    async fn get_native_balance(
+        &self,
+        kind: NativeBalance,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>>;
+
+    async fn get_asset_balance(
+        &self,
+        asset_id: u32,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>>;
+
+    async fn get_nft_balance(
+        &self,
+        pallet: NftPallet,
+        collection_id: u32,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>>;
+}
+
+const SS58_PREFIX: &[u8] = b"SS58PRE";
+
+fn ss58_checksum(payload: &[u8]) -> [u8; 2] {
+    let hash = Blake2b512::new()
+        .chain_update(SS58_PREFIX)
+        .chain_update(payload)
+        .finalize();
+
+    [hash[0], hash[1]]
+}
+
+/// Public key of a Substrate account along with the network format its SS58
+/// text form was encoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubstrateAddress {
+    account: [u8; 32],
+    format: u16,
+}
+
+impl SubstrateAddress {
+    pub fn new(account: [u8; 32], format: u16) -> Self {
+        Self { account, format }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.account
+    }
+
+    pub fn format(&self) -> u16 {
+        self.format
+    }
+}
+
+impl FromStr for SubstrateAddress {
+    type Err = SubstrateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SubstrateError::InvalidAddress(s.to_string());
+        let bytes = bs58::decode(s).into_vec().map_err(|_| invalid())?;
+
+        // Formats below 64 take a single byte, the rest up to 16383 two
+        let (format, prefix_len) = match bytes.first() {
+            Some(&first) if first < 64 => (u16::from(first), 1),
+            Some(&first) if first < 128 && bytes.len() > 1 => {
+                let second = bytes[1];
+                let lower = ((first & 0b0011_1111) << 2) | (second >> 6);
+                let upper = second & 0b0011_1111;
+
+                (u16::from(lower) | (u16::from(upper) << 8), 2)
+            }
+            _ => return Err(invalid()),
+        };
+
+        if bytes.len() != prefix_len + 34 {
+            return Err(invalid());
+        }
+
+        let (payload, checksum) = bytes.split_at(prefix_len + 32);
+
+        if ss58_checksum(payload) != checksum {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            account: payload[prefix_len..]
+                .try_into()
+                .expect("This should be fine"),
+            format,
+        })
+    }
+}
+
+impl fmt::Display for SubstrateAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = match self.format {
+            format @ 0..=63 => vec![format as u8],
+            format => vec![
+                ((format & 0b1111_1100) as u8 >> 2) | 0b0100_0000,
+                (format >> 8) as u8 | ((format & 0b11) as u8) << 6,
+            ],
+        };
+        payload.extend_from_slice(&self.account);
+        payload.extend_from_slice(&ss58_checksum(&payload));
+
+        write!(f, "{}", bs58::encode(payload).into_string())
+    }
+}
+
+impl Serialize for SubstrateAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SubstrateAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[macro_export]
+macro_rules! substrate_address {
+    ($addr:expr) => {{
+        use std::str::FromStr;
+        $crate::substrate::SubstrateAddress::from_str($addr)
+            .expect(&format!("Invalid SS58 address {}", $addr))
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::SubstrateAddress;
+
+    #[test]
+    fn ss58_address() {
+        let alice = substrate_address!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+
+        assert_eq!(alice.format(), 42);
+        assert_eq!(
+            alice.to_string(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert_eq!(
+            SubstrateAddress::new(*alice.as_bytes(), 0).to_string(),
+            "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+        );
+
+        let long = SubstrateAddress::new(*alice.as_bytes(), 2007);
+        assert_eq!(long.to_string().parse::<SubstrateAddress>().unwrap(), long);
+
+        assert!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ"
+            .parse::<SubstrateAddress>()
+            .is_err());
+        assert!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"
+            .parse::<SubstrateAddress>()
+            .is_err());
+    }
+}
@@ -0,0 +1,544 @@
+use crate::{
+    config::SubstrateConfig,
+    substrate::{NativeBalance, NftPallet, SubstrateAddress, SubstrateError, SubstrateQuerier},
+    transport::{limiter::RequestLimits, retry::RetryConfig, FailoverTransport},
+    Amount, Snapshot, U256,
+};
+use async_trait::async_trait;
+use blake2::{digest::consts::U16, Blake2b, Digest};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, hash::Hasher, sync::Mutex, time::Duration};
+use twox_hash::XxHash64;
+use web3::{types::Bytes, Transport};
+
+// Maximum number of keys returned by a single `state_getKeysPaged`
+const KEYS_PER_PAGE: usize = 1000;
+// Maximum number of storage entries read by a single `state_queryStorageAt`
+const KEYS_PER_QUERY: usize = 100;
+
+fn invalid(method: &str) -> SubstrateError {
+    SubstrateError::InvalidResponse(format!("unexpected `{method}` result"))
+}
+
+fn fail_all<T>(
+    user_addresses: &[SubstrateAddress],
+    error: SubstrateError,
+) -> Vec<Result<T, SubstrateError>> {
+    user_addresses
+        .iter()
+        .map(|_| Err(SubstrateError::Other(error.to_string())))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct StorageChangeSet {
+    changes: Vec<(Bytes, Option<Bytes>)>,
+}
+
+fn twox_128(data: &[u8]) -> [u8; 16] {
+    let mut hash = [0; 16];
+
+    for (seed, chunk) in hash.chunks_mut(8).enumerate() {
+        let mut hasher = XxHash64::with_seed(seed as u64);
+        hasher.write(data);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+
+    hash
+}
+
+fn blake2_128_concat(data: &[u8]) -> Vec<u8> {
+    let mut key = Blake2b::<U16>::digest(data).to_vec();
+    key.extend_from_slice(data);
+
+    key
+}
+
+// Key of a storage map entry, the keys of the map are hashed with
+// `Blake2_128Concat` as in every map queried here
+fn storage_key(pallet: &str, item: &str, keys: &[&[u8]]) -> Vec<u8> {
+    let mut key = twox_128(pallet.as_bytes()).to_vec();
+    key.extend_from_slice(&twox_128(item.as_bytes()));
+
+    for k in keys {
+        key.extend_from_slice(&blake2_128_concat(k));
+    }
+
+    key
+}
+
+fn read_u128(data: &[u8], offset: usize) -> Option<U256> {
+    data.get(offset..offset + 16).map(U256::from_little_endian)
+}
+
+// Length prefix of SCALE encoded vectors, returning the length and the size
+// of the prefix
+fn read_compact(data: &[u8]) -> Option<(usize, usize)> {
+    let first = *data.first()?;
+
+    match first & 0b11 {
+        0 => Some(((first >> 2) as usize, 1)),
+        1 => Some((
+            (u16::from_le_bytes(data.get(..2)?.try_into().ok()?) >> 2) as usize,
+            2,
+        )),
+        2 => Some((
+            (u32::from_le_bytes(data.get(..4)?.try_into().ok()?) >> 2) as usize,
+            4,
+        )),
+        _ => None,
+    }
+}
+
+/// Provider of native, `assets` pallet and NFT balances of a Substrate relay
+/// chain or parachain, read directly from its storage.
+pub struct SubstrateProvider {
+    transport: FailoverTransport,
+    reprobe_interval: Duration,
+    native_decimals: Mutex<Option<u8>>,
+    asset_decimals: Mutex<HashMap<u32, u8>>,
+}
+
+impl SubstrateProvider {
+    pub fn from_config(
+        config: &SubstrateConfig,
+        retry: &RetryConfig,
+    ) -> Result<Self, SubstrateError> {
+        let transport = FailoverTransport::new(
+            &config.rpc_urls,
+            Duration::from_millis(config.timeout_ms),
+            RequestLimits {
+                max_concurrent_requests: config.max_concurrent_requests,
+                requests_per_second: config.requests_per_second,
+            },
+            retry.rpc,
+        )?;
+
+        Ok(Self {
+            transport,
+            reprobe_interval: Duration::from_secs(config.reprobe_interval_secs),
+            native_decimals: Mutex::new(None),
+            asset_decimals: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn transport(&self) -> &FailoverTransport {
+        &self.transport
+    }
+
+    pub fn reprobe_interval(&self) -> Duration {
+        self.reprobe_interval
+    }
+
+    async fn block_hash(&self, snapshot: Option<Snapshot>) -> Result<Value, SubstrateError> {
+        match snapshot {
+            None => Ok(Value::Null),
+            Some(Snapshot::Block(number)) => {
+                match self
+                    .transport
+                    .execute("chain_getBlockHash", vec![json!(number)])
+                    .await?
+                {
+                    Value::Null => Err(SubstrateError::Other(format!("Unknown block {number}"))),
+                    hash => Ok(hash),
+                }
+            }
+            Some(Snapshot::Timestamp(_)) => {
+                Err(SubstrateError::NotSupported("timestamp snapshots"))
+            }
+        }
+    }
+
+    async fn storage(&self, key: Vec<u8>, at: &Value) -> Result<Option<Vec<u8>>, SubstrateError> {
+        let result = self
+            .transport
+            .execute("state_getStorage", vec![json!(Bytes(key)), at.clone()])
+            .await?;
+
+        serde_json::from_value::<Option<Bytes>>(result)
+            .map(|value| value.map(|bytes| bytes.0))
+            .map_err(|_| invalid("state_getStorage"))
+    }
+
+    async fn query_storage(
+        &self,
+        keys: &[Vec<u8>],
+        at: &Value,
+    ) -> Result<Vec<Option<Vec<u8>>>, SubstrateError> {
+        let result = self
+            .transport
+            .execute(
+                "state_queryStorageAt",
+                vec![
+                    json!(keys.iter().cloned().map(Bytes).collect::<Vec<_>>()),
+                    at.clone(),
+                ],
+            )
+            .await?;
+        let mut values = serde_json::from_value::<Vec<StorageChangeSet>>(result)
+            .map_err(|_| invalid("state_queryStorageAt"))?
+            .into_iter()
+            .flat_map(|set| set.changes)
+            .map(|(key, value)| (key.0, value.map(|bytes| bytes.0)))
+            .collect::<HashMap<_, _>>();
+
+        // Entries that don't exist may be left out of the changes
+        Ok(keys
+            .iter()
+            .map(|key| values.remove(key).flatten())
+            .collect())
+    }
+
+    // Storage entries of every user, read in batches
+    async fn storage_entries(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: &Value,
+    ) -> Vec<Result<Option<Vec<u8>>, SubstrateError>> {
+        join_all(keys.chunks(KEYS_PER_QUERY).map(|chunk| async move {
+            match self.query_storage(chunk, at).await {
+                Ok(values) => values.into_iter().map(Ok).collect(),
+                Err(e) => chunk
+                    .iter()
+                    .map(|_| Err(SubstrateError::Other(e.to_string())))
+                    .collect::<Vec<_>>(),
+            }
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    async fn count_keys(&self, prefix: Vec<u8>, at: &Value) -> Result<usize, SubstrateError> {
+        let prefix = json!(Bytes(prefix));
+        let mut start = Value::Null;
+        let mut count = 0;
+
+        loop {
+            let result = self
+                .transport
+                .execute(
+                    "state_getKeysPaged",
+                    vec![prefix.clone(), json!(KEYS_PER_PAGE), start, at.clone()],
+                )
+                .await?;
+            let keys = result
+                .as_array()
+                .ok_or_else(|| invalid("state_getKeysPaged"))?;
+
+            count += keys.len();
+
+            match keys.last() {
+                Some(last) if keys.len() == KEYS_PER_PAGE => start = last.clone(),
+                _ => return Ok(count),
+            }
+        }
+    }
+
+    // Decimals never change, so they are only queried once
+    async fn native_decimals(&self) -> Result<u8, SubstrateError> {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        if let Some(decimals) = *self.native_decimals.lock().unwrap() {
+            return Ok(decimals);
+        }
+
+        let properties = self.transport.execute("system_properties", vec![]).await?;
+        // Chains with more native tokens list the decimals of each
+        let decimals = match &properties["tokenDecimals"] {
+            Value::Array(decimals) => decimals.first().and_then(Value::as_u64),
+            decimals => decimals.as_u64(),
+        }
+        .and_then(|decimals| u8::try_from(decimals).ok())
+        .ok_or_else(|| invalid("system_properties"))?;
+
+        *self.native_decimals.lock().unwrap() = Some(decimals);
+
+        Ok(decimals)
+    }
+
+    async fn asset_decimals(&self, asset_id: u32) -> Result<u8, SubstrateError> {
+        if let Some(decimals) = self.asset_decimals.lock().unwrap().get(&asset_id) {
+            return Ok(*decimals);
+        }
+
+        let key = storage_key("Assets", "Metadata", &[&asset_id.to_le_bytes()]);
+        let metadata = self
+            .storage(key, &Value::Null)
+            .await?
+            .ok_or_else(|| SubstrateError::Other(format!("Unknown asset {asset_id}")))?;
+
+        // Deposit, then the name and the symbol as byte vectors
+        let mut offset = 16;
+        for _ in 0..2 {
+            let (len, prefix) = metadata
+                .get(offset..)
+                .and_then(read_compact)
+                .ok_or_else(|| invalid("state_getStorage"))?;
+            offset += prefix + len;
+        }
+        let decimals = *metadata
+            .get(offset)
+            .ok_or_else(|| invalid("state_getStorage"))?;
+
+        self.asset_decimals
+            .lock()
+            .unwrap()
+            .insert(asset_id, decimals);
+
+        Ok(decimals)
+    }
+}
+
+fn native_balance(kind: NativeBalance, info: Option<Vec<u8>>) -> Result<U256, SubstrateError> {
+    // Accounts that were never funded don't exist
+    let Some(info) = info else {
+        return Ok(U256::zero());
+    };
+
+    // Nonce and reference counters, then the free and reserved balances
+    let free = read_u128(&info, 16).ok_or_else(|| invalid("state_queryStorageAt"))?;
+    let reserved = read_u128(&info, 32).ok_or_else(|| invalid("state_queryStorageAt"))?;
+
+    Ok(match kind {
+        NativeBalance::Free => free,
+        NativeBalance::Reserved => reserved,
+        NativeBalance::Total => free.saturating_add(reserved),
+    })
+}
+
+#[async_trait]
+impl SubstrateQuerier for SubstrateProvider {
+    async fn get_native_balance(
+        &self,
+        kind: NativeBalance,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>> {
+        let (decimals, at) =
+            match futures::try_join!(self.native_decimals(), self.block_hash(snapshot)) {
+                Ok(result) => result,
+                Err(e) => return fail_all(user_addresses, e),
+            };
+
+        let keys = user_addresses
+            .iter()
+            .map(|ua| storage_key("System", "Account", &[ua.as_bytes()]))
+            .collect();
+
+        self.storage_entries(keys, &at)
+            .await
+            .into_iter()
+            .map(|info| native_balance(kind, info?).map(|v| Amount::new(v, decimals)))
+            .collect()
+    }
+
+    async fn get_asset_balance(
+        &self,
+        asset_id: u32,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>> {
+        let (decimals, at) =
+            match futures::try_join!(self.asset_decimals(asset_id), self.block_hash(snapshot)) {
+                Ok(result) => result,
+                Err(e) => return fail_all(user_addresses, e),
+            };
+
+        let keys = user_addresses
+            .iter()
+            .map(|ua| {
+                storage_key(
+                    "Assets",
+                    "Account",
+                    &[&asset_id.to_le_bytes(), ua.as_bytes()],
+                )
+            })
+            .collect();
+
+        self.storage_entries(keys, &at)
+            .await
+            .into_iter()
+            .map(|account| {
+                let balance = match account? {
+                    Some(account) => {
+                        read_u128(&account, 0).ok_or_else(|| invalid("state_queryStorageAt"))?
+                    }
+                    None => U256::zero(),
+                };
+
+                Ok(Amount::new(balance, decimals))
+            })
+            .collect()
+    }
+
+    async fn get_nft_balance(
+        &self,
+        pallet: NftPallet,
+        collection_id: u32,
+        user_addresses: &[SubstrateAddress],
+        snapshot: Option<Snapshot>,
+    ) -> Vec<Result<Amount, SubstrateError>> {
+        let at = match self.block_hash(snapshot).await {
+            Ok(at) => at,
+            Err(e) => return fail_all(user_addresses, e),
+        };
+        let pallet = match pallet {
+            NftPallet::Nfts => "Nfts",
+            NftPallet::Uniques => "Uniques",
+        };
+
+        // Items are keyed by owner, collection and item, so the owned items
+        // of a collection are the keys under the first two
+        join_all(user_addresses.iter().map(|ua| async {
+            let prefix = storage_key(
+                pallet,
+                "Account",
+                &[ua.as_bytes(), &collection_id.to_le_bytes()],
+            );
+            let owned = self.count_keys(prefix, &at).await?;
+
+            Ok(Amount::new(U256::from(owned), 0))
+        }))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{storage_key, twox_128, SubstrateProvider};
+    use crate::{
+        config::SubstrateConfig,
+        substrate::{NativeBalance, NftPallet, SubstrateQuerier},
+        substrate_address,
+        test_utils::{json_rpc, serve},
+        transport::retry::RetryConfig,
+        Snapshot,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+    use web3::types::Bytes;
+
+    fn hex(bytes: Vec<u8>) -> String {
+        json!(Bytes(bytes)).as_str().unwrap().to_string()
+    }
+
+    fn u128_le(value: u128) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+
+    #[tokio::test]
+    async fn substrate_balances() {
+        assert_eq!(
+            hex(twox_128(b"System").to_vec()),
+            "0x26aa394eea5630e07c48ae0c9558cef7"
+        );
+
+        let alice = substrate_address!("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5");
+        let bob = substrate_address!("14E5nqKAp3oAJcmzgZhUD2RcptBeUBScxKHgJKU4HPNcKVf3");
+        let users = [alice, bob];
+
+        let account_info = hex([
+            vec![0; 16],
+            u128_le(25_000_000_000),
+            u128_le(5_000_000_000),
+            vec![0; 32],
+        ]
+        .concat());
+        let asset_metadata = hex([
+            u128_le(0),
+            vec![4 << 2],
+            b"Tzzz".to_vec(),
+            vec![4 << 2],
+            b"USDT".to_vec(),
+            vec![6, 0],
+        ]
+        .concat());
+        let alice_key = hex(storage_key("System", "Account", &[alice.as_bytes()]));
+        let alice_asset = hex(storage_key(
+            "Assets",
+            "Account",
+            &[&1984u32.to_le_bytes(), alice.as_bytes()],
+        ));
+        let metadata_key = hex(storage_key("Assets", "Metadata", &[&1984u32.to_le_bytes()]));
+        let bob_nfts = hex(storage_key(
+            "Nfts",
+            "Account",
+            &[bob.as_bytes(), &7u32.to_le_bytes()],
+        ));
+
+        let storage = HashMap::from([
+            (alice_key, account_info),
+            (alice_asset, hex([u128_le(1_500_000), vec![0; 8]].concat())),
+            (metadata_key, asset_metadata),
+        ]);
+
+        let url = serve(json_rpc(move |method, params| match method {
+            "system_properties" => json!({ "ss58Format": 0, "tokenDecimals": [10] }),
+            "chain_getBlockHash" => json!(format!("0x{}", "ab".repeat(32))),
+            "state_getStorage" => json!(params[0].as_str().and_then(|key| storage.get(key))),
+            // Entries that don't exist are left out like some nodes do
+            "state_queryStorageAt" => json!([{
+                "block": format!("0x{}", "ab".repeat(32)),
+                "changes": params[0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .filter_map(|key| {
+                        let key = key.as_str().unwrap();
+                        storage.get(key).map(|value| json!([key, value]))
+                    })
+                    .collect::<Vec<_>>(),
+            }]),
+            "state_getKeysPaged" if params[0] == bob_nfts => {
+                json!([format!("{bob_nfts}01"), format!("{bob_nfts}02")])
+            }
+            "state_getKeysPaged" => json!([]),
+            _ => json!(null),
+        }))
+        .await;
+
+        let provider = SubstrateProvider::from_config(
+            &SubstrateConfig {
+                name: "polkadot".into(),
+                rpc_urls: vec![url],
+                timeout_ms: 1000,
+                reprobe_interval_secs: 30,
+                max_concurrent_requests: None,
+                requests_per_second: None,
+            },
+            &RetryConfig::default(),
+        )
+        .unwrap();
+
+        let native = provider
+            .get_native_balance(NativeBalance::Total, &users, None)
+            .await;
+        assert_eq!(native[0].as_ref().unwrap().to_string(), "3");
+        assert!(native[1].as_ref().unwrap().is_zero());
+
+        let reserved = provider
+            .get_native_balance(NativeBalance::Reserved, &users, Some(Snapshot::Block(1)))
+            .await;
+        assert_eq!(reserved[0].as_ref().unwrap().to_string(), "0.5");
+
+        let assets = provider.get_asset_balance(1984, &users, None).await;
+        assert_eq!(assets[0].as_ref().unwrap().to_string(), "1.5");
+        assert!(assets[1].as_ref().unwrap().is_zero());
+
+        let nfts = provider
+            .get_nft_balance(NftPallet::Nfts, 7, &users, None)
+            .await;
+        assert!(nfts[0].as_ref().unwrap().is_zero());
+        assert_eq!(nfts[1].as_ref().unwrap().to_string(), "2");
+
+        assert!(provider
+            .get_native_balance(NativeBalance::Free, &users, Some(Snapshot::Timestamp(1)))
+            .await
+            .iter()
+            .all(Result::is_err));
+    }
+}
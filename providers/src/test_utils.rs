@@ -0,0 +1,269 @@
+use crate::{address, config::ChainConfig, EvmChain};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+type Handler = Arc<dyn Fn(String, String) -> BoxFuture<'static, (u16, String)> + Send + Sync>;
+
+/// Starts a local HTTP server answering every request with the result of
+/// `handler(path, body)` as JSON, and returns its url.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(&str, &str) -> String + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+
+    serve_with(Arc::new(move |path, body| {
+        let response = handler(&path, &body);
+
+        Box::pin(async move { (200, response) })
+    }))
+    .await
+}
+
+async fn serve_with(handler: Handler) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = Arc::clone(&handler);
+
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut chunk = [0u8; 4096];
+
+                let (head, body) = loop {
+                    let Ok(n) = stream.read(&mut chunk).await else {
+                        return;
+                    };
+                    request.extend_from_slice(&chunk[..n]);
+
+                    let text = String::from_utf8_lossy(&request).to_string();
+
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .filter_map(|line| line.split_once(':'))
+                            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                            .unwrap_or_default();
+
+                        if body.len() >= length || n == 0 {
+                            break (head.to_string(), body.to_string());
+                        }
+                    } else if n == 0 {
+                        return;
+                    }
+                };
+
+                let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let (status, response) = handler(path, body).await;
+
+                let _ = stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {status} OK\r\nContent-Type: application/json\r\n\
+                            Content-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                            response.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+            });
+        }
+    });
+
+    url
+}
+
+/// Handler answering JSON-RPC requests with `result(method, params)`.
+pub fn json_rpc<F>(result: F) -> impl Fn(&str, &str) -> String + Send + Sync + 'static
+where
+    F: Fn(&str, &Value) -> Value + Send + Sync + 'static,
+{
+    move |_, body| {
+        let request: Value = serde_json::from_str(body).unwrap();
+
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": result(request["method"].as_str().unwrap(), &request["params"]),
+        })
+        .to_string()
+    }
+}
+
+/// Configuration of Ethereum with `url` as its only RPC, without request
+/// limits, Balancy, Safe service or price feeds.
+pub fn chain_config(url: String) -> ChainConfig {
+    ChainConfig {
+        chain: EvmChain::Ethereum,
+        rpc_urls: vec![url],
+        multicall: address!("0x5ba1e12693dc8f9c48aad8770482f4739beed696"),
+        balancy_mode: None,
+        timeout_ms: 1000,
+        reprobe_interval_secs: 30,
+        max_concurrent_requests: None,
+        requests_per_second: None,
+        safe_service_url: None,
+        price_feeds: Default::default(),
+        feed_registry: None,
+        price_max_age_secs: 3600,
+        token_decimals: Default::default(),
+    }
+}
+
+// Setting this variable records the fixtures again from the live upstreams
+const RECORD_FIXTURES: &str = "RECORD_FIXTURES";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Exchange {
+    path: String,
+    // Method and params of JSON-RPC requests, `None` for plain GET requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request: Option<Value>,
+    status: u16,
+    // Without the id and version fields in case of JSON-RPC
+    response: Value,
+}
+
+impl Exchange {
+    fn matches(&self, path: &str, request: &Option<Value>) -> bool {
+        self.path == path && &self.request == request
+    }
+}
+
+fn fixture_path(name: &str) -> String {
+    format!("{}/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"))
+}
+
+fn strip_envelope(mut value: Value) -> (Value, Value) {
+    let id = value
+        .as_object_mut()
+        .and_then(|object| {
+            object.remove("jsonrpc");
+            object.remove("id")
+        })
+        .unwrap_or_default();
+
+    (id, value)
+}
+
+/// Starts a local HTTP server answering from the exchanges recorded in
+/// `fixtures/<name>.json`, and returns its url. With `RECORD_FIXTURES` set,
+/// requests are forwarded to the url returned by `upstream` instead, and the
+/// fixture is rewritten with the exchanges.
+pub async fn replay<F>(name: &str, upstream: F) -> String
+where
+    F: FnOnce() -> String,
+{
+    let path = fixture_path(name);
+
+    if std::env::var(RECORD_FIXTURES).is_ok() {
+        return record(path, upstream()).await;
+    }
+
+    let exchanges: Vec<Exchange> = serde_json::from_str(
+        &std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read fixture `{path}`: {e}")),
+    )
+    .unwrap();
+    let exchanges = Arc::new(exchanges);
+
+    serve_with(Arc::new(move |path, body| {
+        let exchanges = Arc::clone(&exchanges);
+
+        Box::pin(async move {
+            let (id, request) = match serde_json::from_str::<Value>(&body) {
+                Ok(body) => {
+                    let (id, request) = strip_envelope(body);
+                    (Some(id), Some(request))
+                }
+                Err(_) => (None, None),
+            };
+
+            let Some(exchange) = exchanges.iter().find(|e| e.matches(&path, &request)) else {
+                return (
+                    501,
+                    format!("\"No recorded exchange for `{path}` with body `{body}`\""),
+                );
+            };
+
+            let mut response = exchange.response.clone();
+
+            if let (Some(id), Some(object)) = (id, response.as_object_mut()) {
+                object.insert("jsonrpc".into(), "2.0".into());
+                object.insert("id".into(), id);
+            }
+
+            (exchange.status, response.to_string())
+        })
+    }))
+    .await
+}
+
+async fn record(fixture: String, upstream: String) -> String {
+    let exchanges = Arc::new(Mutex::new(Vec::<Exchange>::new()));
+    let client = reqwest::Client::new();
+
+    serve_with(Arc::new(move |path, body| {
+        let (exchanges, client, fixture) =
+            (Arc::clone(&exchanges), client.clone(), fixture.clone());
+        let url = if path == "/" {
+            upstream.clone()
+        } else {
+            format!("{}{path}", upstream.trim_end_matches('/'))
+        };
+
+        Box::pin(async move {
+            let response = if body.is_empty() {
+                client.get(url).send().await
+            } else {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body.clone())
+                    .send()
+                    .await
+            }
+            .unwrap();
+
+            let status = response.status().as_u16();
+            let text = response.text().await.unwrap();
+
+            let request = serde_json::from_str::<Value>(&body)
+                .ok()
+                .map(|body| strip_envelope(body).1);
+            let response = match serde_json::from_str::<Value>(&text) {
+                Ok(value) if request.is_some() => strip_envelope(value).1,
+                Ok(value) => value,
+                Err(_) => Value::String(text.clone()),
+            };
+
+            let mut exchanges = exchanges.lock().unwrap();
+
+            exchanges.retain(|e| !e.matches(&path, &request));
+            exchanges.push(Exchange {
+                path,
+                request,
+                status,
+                response,
+            });
+
+            std::fs::write(
+                &fixture,
+                serde_json::to_string_pretty(&*exchanges).unwrap() + "\n",
+            )
+            .unwrap();
+
+            (status, text)
+        })
+    }))
+    .await
+}
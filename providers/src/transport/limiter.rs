@@ -0,0 +1,106 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+    pub max_concurrent_requests: Option<usize>,
+    pub requests_per_second: Option<u32>,
+}
+
+/// Bounds the number of requests in flight and spaces them out evenly so
+/// that no more than the allowed number is started in a second.
+#[derive(Debug)]
+pub struct Limiter {
+    semaphore: Option<Semaphore>,
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+}
+
+impl Limiter {
+    pub fn new(limits: RequestLimits) -> Self {
+        Self {
+            semaphore: limits.max_concurrent_requests.map(Semaphore::new),
+            interval: limits
+                .requests_per_second
+                .map(|rps| Duration::from_secs(1) / rps.max(1)),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until a request may be sent, the returned permit has to be
+    /// kept until the request is finished.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("The semaphore is never closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(interval) = self.interval {
+            let slot = {
+                // Calling unwrap is fine here, read the documentation of the
+                // lock function for details.
+                let mut next_slot = self.next_slot.lock().unwrap();
+                let slot = (*next_slot).max(Instant::now());
+                *next_slot = slot + interval;
+                slot
+            };
+
+            tokio::time::sleep_until(slot.into()).await;
+        }
+
+        permit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Limiter, RequestLimits};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    #[tokio::test]
+    async fn limiter_bounds_requests() {
+        let limiter = Arc::new(Limiter::new(RequestLimits {
+            max_concurrent_requests: Some(2),
+            requests_per_second: Some(50),
+        }));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let start = Instant::now();
+
+        futures::future::join_all((0..6).map(|_| {
+            let (limiter, in_flight, max_in_flight) = (
+                Arc::clone(&limiter),
+                Arc::clone(&in_flight),
+                Arc::clone(&max_in_flight),
+            );
+
+            async move {
+                let _permit = limiter.acquire().await;
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        }))
+        .await;
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        // The sixth request can't start earlier than 5 intervals of 20ms
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
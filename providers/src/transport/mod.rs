@@ -0,0 +1,249 @@
+pub mod limiter;
+pub mod retry;
+
+use crate::transport::{
+    limiter::{Limiter, RequestLimits},
+    retry::{BackendRetry, ErrorClass},
+};
+use futures::future::BoxFuture;
+use jsonrpc_core::{Call, Value};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use web3::{error::TransportError, helpers, transports::Http, Error, RequestId, Transport};
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    transport: Http,
+    limiter: Limiter,
+    failed_at: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        // Calling unwrap is fine here, read the documentation of the lock
+        // function for details.
+        self.failed_at.lock().unwrap().is_none()
+    }
+
+    fn mark_healthy(&self) {
+        if self.failed_at.lock().unwrap().take().is_some() {
+            log::info!("RPC endpoint `{}` is healthy again", self.url);
+        }
+    }
+
+    fn mark_failed(&self, error: &Error) {
+        log::warn!("RPC endpoint `{}` failed: {error}", self.url);
+
+        self.failed_at
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    next_id: AtomicUsize,
+    timeout: Duration,
+    retry: BackendRetry,
+}
+
+impl Inner {
+    async fn send_once(&self, id: RequestId, request: &Call) -> Result<Value, Error> {
+        let healthy: Vec<&Endpoint> = self.endpoints.iter().filter(|e| e.is_healthy()).collect();
+
+        // When every endpoint is down it is still worth trying them all
+        // instead of failing right away
+        let candidates = if healthy.is_empty() {
+            self.endpoints.iter().collect()
+        } else {
+            healthy
+        };
+
+        let mut last_error = Error::Unreachable;
+
+        for endpoint in candidates {
+            // Waiting for a slot doesn't count towards the timeout, only the
+            // request itself does
+            let _permit = endpoint.limiter.acquire().await;
+            let result =
+                tokio::time::timeout(self.timeout, endpoint.transport.send(id, request.clone()))
+                    .await
+                    .unwrap_or(Err(Error::Unreachable));
+
+            match result {
+                Err(e) if should_failover(&e) => {
+                    endpoint.mark_failed(&e);
+                    last_error = e;
+                }
+                result => {
+                    endpoint.mark_healthy();
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+/// Transport sending every request to the first healthy endpoint of an
+/// ordered list, and failing over to the next one on transport errors or
+/// timeouts.
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    inner: Arc<Inner>,
+}
+
+impl FailoverTransport {
+    pub fn new(
+        urls: &[String],
+        timeout: Duration,
+        limits: RequestLimits,
+        retry: BackendRetry,
+    ) -> Result<Self, Error> {
+        if urls.is_empty() {
+            return Err(Error::Unreachable);
+        }
+
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                Ok(Endpoint {
+                    url: url.clone(),
+                    transport: Http::new(url)?,
+                    limiter: Limiter::new(limits),
+                    failed_at: Mutex::new(None),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                endpoints,
+                next_id: AtomicUsize::new(0),
+                timeout,
+                retry,
+            }),
+        })
+    }
+
+    /// Urls of the endpoints along with whether they are considered healthy.
+    pub fn health(&self) -> Vec<(String, bool)> {
+        self.inner
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.url.clone(), endpoint.is_healthy()))
+            .collect()
+    }
+
+    /// Sends a cheap request calling `method` to every failed endpoint and
+    /// puts the ones answering it back into rotation.
+    pub async fn probe(&self, method: &str) {
+        let inner = &self.inner;
+
+        futures::future::join_all(
+            inner
+                .endpoints
+                .iter()
+                .filter(|endpoint| !endpoint.is_healthy())
+                .map(|endpoint| async move {
+                    let id = inner.next_id.fetch_add(1, Ordering::Relaxed);
+                    let request = helpers::build_request(id, method, vec![]);
+                    let _permit = endpoint.limiter.acquire().await;
+
+                    if let Ok(Ok(_)) =
+                        tokio::time::timeout(inner.timeout, endpoint.transport.send(id, request))
+                            .await
+                    {
+                        endpoint.mark_healthy();
+                    }
+                }),
+        )
+        .await;
+    }
+}
+
+// Errors returned by the node itself (e.g. a reverted call) would be the same
+// on every endpoint, so only the other ones trigger a failover
+fn should_failover(error: &Error) -> bool {
+    !matches!(error, Error::Rpc(_))
+}
+
+fn classify(error: &Error) -> Option<ErrorClass> {
+    match error {
+        Error::Transport(TransportError::Code(429)) => Some(ErrorClass::RateLimited),
+        Error::Transport(TransportError::Code(500..=599)) => Some(ErrorClass::ServerError),
+        Error::Transport(TransportError::Message(_)) | Error::Unreachable | Error::Io(_) => {
+            Some(ErrorClass::Transport)
+        }
+        _ => None,
+    }
+}
+
+impl Transport for FailoverTransport {
+    type Out = BoxFuture<'static, Result<Value, Error>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: Call) -> Self::Out {
+        let inner = Arc::clone(&self.inner);
+
+        Box::pin(async move {
+            inner
+                .retry
+                .run("RPC request", classify, || inner.send_once(id, &request))
+                .await
+                .0
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FailoverTransport;
+    use crate::{
+        test_utils::{json_rpc, serve},
+        transport::{limiter::RequestLimits, retry::BackendRetry},
+    };
+    use std::time::Duration;
+    use web3::Transport;
+
+    #[tokio::test]
+    async fn failover_to_next_endpoint() {
+        // Nothing listens on port 1, so requests to it fail right away
+        let dead = "http://127.0.0.1:1".to_string();
+        let live = serve(json_rpc(|_, _| "0x10".into())).await;
+        let transport = FailoverTransport::new(
+            &[dead.clone(), live.clone()],
+            Duration::from_secs(1),
+            RequestLimits::default(),
+            BackendRetry::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            transport.execute("eth_blockNumber", vec![]).await.unwrap(),
+            "0x10"
+        );
+        assert_eq!(
+            transport.health(),
+            vec![(dead.clone(), false), (live.clone(), true)]
+        );
+
+        transport.probe("eth_blockNumber").await;
+
+        assert_eq!(transport.health(), vec![(dead, false), (live, true)]);
+    }
+}
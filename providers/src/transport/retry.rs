@@ -0,0 +1,201 @@
+use rand::Rng;
+use serde::Deserialize;
+use std::{cell::Cell, fmt::Display, future::Future, time::Duration};
+
+tokio::task_local! {
+    static RETRIES: Cell<u32>;
+}
+
+/// Runs `future` and returns its output along with the number of retries
+/// the requests it sent needed, so that they can be reported with it.
+pub async fn count_retries<F: Future>(future: F) -> (F::Output, u32) {
+    RETRIES
+        .scope(Cell::new(0), async move {
+            let output = future.await;
+
+            (output, RETRIES.with(Cell::get))
+        })
+        .await
+}
+
+/// Kinds of failures that are worth retrying, anything else fails right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Transport,
+    RateLimited,
+    ServerError,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 5_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the attempt following `attempt`, doubling every time and
+    /// randomized between half and the whole of it so that clients failing
+    /// together don't retry together.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .initial_backoff_ms
+            .saturating_mul(1 << attempt.saturating_sub(1).min(32))
+            .min(self.max_backoff_ms);
+
+        Duration::from_millis(rand::thread_rng().gen_range(ceiling / 2..=ceiling))
+    }
+}
+
+/// Retry policies of a single backend per class of error.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct BackendRetry {
+    pub transport: RetryPolicy,
+    pub rate_limited: RetryPolicy,
+    pub server_error: RetryPolicy,
+}
+
+impl Default for BackendRetry {
+    fn default() -> Self {
+        Self {
+            transport: RetryPolicy::default(),
+            rate_limited: RetryPolicy {
+                max_attempts: 5,
+                initial_backoff_ms: 1_000,
+                max_backoff_ms: 10_000,
+            },
+            server_error: RetryPolicy::default(),
+        }
+    }
+}
+
+impl BackendRetry {
+    pub fn policy(&self, class: ErrorClass) -> &RetryPolicy {
+        match class {
+            ErrorClass::Transport => &self.transport,
+            ErrorClass::RateLimited => &self.rate_limited,
+            ErrorClass::ServerError => &self.server_error,
+        }
+    }
+
+    /// Calls `f` until it succeeds, fails with an error that `classify`
+    /// doesn't consider transient, or runs out of attempts. The number of
+    /// attempts made is returned along with the last result.
+    pub async fn run<T, E, C, F, Fut>(
+        &self,
+        what: &str,
+        classify: C,
+        mut f: F,
+    ) -> (Result<T, E>, u32)
+    where
+        E: Display,
+        C: Fn(&E) -> Option<ErrorClass>,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+
+        let result = loop {
+            let error = match f().await {
+                Ok(value) => break Ok(value),
+                Err(error) => error,
+            };
+
+            let Some(policy) = classify(&error).map(|class| self.policy(class)) else {
+                break Err(error);
+            };
+
+            if attempt >= policy.max_attempts {
+                break Err(error);
+            }
+
+            let backoff = policy.backoff(attempt);
+
+            log::warn!(
+                "{what} failed on attempt {attempt}/{}: {error}, retrying in {backoff:?}",
+                policy.max_attempts
+            );
+
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        };
+
+        // Requests sent outside of `count_retries` are only logged
+        let _ = RETRIES.try_with(|retries| retries.set(retries.get() + attempt - 1));
+
+        (result, attempt)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub rpc: BackendRetry,
+    pub balancy: BackendRetry,
+    pub safe: BackendRetry,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{count_retries, BackendRetry, ErrorClass, RetryPolicy};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn retry_transient_errors() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 4,
+        };
+        let retry = BackendRetry {
+            transport: policy,
+            rate_limited: policy,
+            server_error: policy,
+        };
+        let classify = |e: &&str| (*e == "transient").then_some(ErrorClass::Transport);
+
+        let mut calls = 0;
+        let result = retry
+            .run("test", classify, || {
+                calls += 1;
+                async move {
+                    match calls {
+                        1 | 2 => Err("transient"),
+                        _ => Ok(calls),
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result, (Ok(3), 3));
+
+        let (result, retries) = count_retries(async {
+            retry
+                .run("test", classify, || async { Err::<(), _>("transient") })
+                .await
+        })
+        .await;
+        assert_eq!(result, (Err("transient"), 3));
+        assert_eq!(retries, 2);
+
+        let result = retry
+            .run("test", classify, || async { Err::<(), _>("reverted") })
+            .await;
+        assert_eq!(result, (Err("reverted"), 1));
+
+        assert!(policy.backoff(1) <= Duration::from_millis(1));
+        assert!(policy.backoff(10) <= Duration::from_millis(4));
+        assert!(policy.backoff(10) >= Duration::from_millis(2));
+    }
+}
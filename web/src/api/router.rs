@@ -1,16 +1,27 @@
 use crate::api::service;
-use actix_web::{post, web, Responder};
+use actix_web::{get, post, web, Responder};
+use providers::evm::general::EvmProviders;
 use rusty_gate::types::CheckRolesOfMembersRequest;
 
 #[post("/checkRolesOfMembers")]
-async fn check_roles_of_members(body: web::Json<CheckRolesOfMembersRequest>) -> impl Responder {
+async fn check_roles_of_members(
+    providers: web::Data<EvmProviders>,
+    body: web::Json<CheckRolesOfMembersRequest>,
+) -> impl Responder {
     log::info!("check_roles_of_members - {:?}", body);
     web::Json(
         service::check_roles_of_members(
+            &providers,
             &body.users,
             &body.roles,
             body.send_details.unwrap_or_default(),
+            body.no_cache.unwrap_or_default(),
         )
         .await,
     )
 }
+
+#[get("/metrics/cache")]
+async fn cache_metrics(providers: web::Data<EvmProviders>) -> impl Responder {
+    web::Json(providers.cache().stats())
+}
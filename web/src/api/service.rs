@@ -1,15 +1,25 @@
+use providers::evm::general::EvmProviders;
 use rusty_gate::{
     requirements::check_access,
     types::{CheckRolesOfMembersResult, Role, User},
 };
 
 pub async fn check_roles_of_members(
+    providers: &EvmProviders,
     users: &[User],
     roles: &[Role],
     send_details: bool,
+    no_cache: bool,
 ) -> Vec<CheckRolesOfMembersResult> {
+    // Balances are fetched again for this request only, including the ones
+    // of the Safes and vaults of the users
+    let ctx = match no_cache {
+        true => providers.context().without_cache(),
+        false => providers.context().clone(),
+    };
+
     futures::future::join_all(roles.iter().map(|role| async {
-        let result = check_access(users, &role.requirements, &role.logic, send_details).await;
+        let result = check_access(&ctx, users, &role.requirements, &role.logic, send_details).await;
 
         CheckRolesOfMembersResult {
             role_id: role.id.expect("Unwrapping the ID should be fine"),
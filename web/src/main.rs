@@ -1,10 +1,12 @@
 #![deny(clippy::all)]
 #![deny(clippy::dbg_macro)]
 
-use actix_web::{middleware::Logger, App, HttpServer};
+use actix_web::{middleware::Logger, web::Data, App, HttpServer};
 use anyhow::Error;
 use env_logger::{Builder, Env};
 use log::{error, info};
+use providers::{config::ProvidersConfig, evm::general::EvmProviders};
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 mod api;
 
@@ -25,6 +27,10 @@ struct Opt {
     /// Set port number
     #[structopt(long, short, default_value = "8080")]
     port: u16,
+
+    /// Set path of the chain and provider config
+    #[structopt(long, short, default_value = "providers.toml", parse(from_os_str))]
+    config: PathBuf,
 }
 
 #[tokio::main]
@@ -33,8 +39,20 @@ async fn main() -> ! {
 
     Builder::from_env(Env::default().default_filter_or(opt.log)).init();
 
+    dotenv::dotenv().ok();
+
+    let providers = match load_providers(&opt.config) {
+        Ok(providers) => Data::new(providers),
+        Err(e) => {
+            error!("Failed to load `{}`: {e}", opt.config.display());
+            std::process::exit(1);
+        }
+    };
+
+    providers.spawn_health_checks();
+
     loop {
-        if let Err(e) = try_main(&opt.ip, opt.port).await {
+        if let Err(e) = try_main(&opt.ip, opt.port, providers.clone()).await {
             error!("{e}");
         } else {
             info!("Exiting gracefully");
@@ -43,15 +61,28 @@ async fn main() -> ! {
     }
 }
 
-async fn try_main(ip: &str, port: u16) -> Result<(), Error> {
+fn load_providers(path: &Path) -> Result<EvmProviders, Error> {
+    let config = ProvidersConfig::from_file(path)?;
+    let providers = EvmProviders::from_config(&config)?;
+
+    for chain in config.chains.iter() {
+        info!("Loaded provider for {:?}", chain.chain);
+    }
+
+    Ok(providers)
+}
+
+async fn try_main(ip: &str, port: u16, providers: Data<EvmProviders>) -> Result<(), Error> {
     info!("Listening on http://{}:{}", ip, port);
 
     use api::router::*;
 
-    HttpServer::new(|| {
+    HttpServer::new(move || {
         App::new()
+            .app_data(providers.clone())
             .wrap(Logger::default())
             .service(check_roles_of_members)
+            .service(cache_metrics)
     })
     .bind((ip, port))
     .map_err(Error::msg)?